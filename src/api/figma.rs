@@ -1,16 +1,30 @@
+use crate::api::rate_limiter::RateLimiter;
+use crate::api::view::View;
 use crate::common::error::AppError;
-use crate::common::fileutils::{create_temp_dir, TEMP_DIR_PATH};
-use crate::models::config::ImageFormat;
-use crate::models::figma::Document;
+use crate::common::fileutils::{
+    claim_partial_file, create_temp_dir, create_temp_files_dir, etag_file_name, move_file,
+    remove_partial_file, temp_file_name, TEMP_DIR_PATH,
+};
+use crate::common::http_client::{create_http_client, token_header};
+use crate::common::lockfile::text_hash;
+use crate::common::renderer::Render;
+use crate::common::token::{resolve_auth_type, validate_token};
+use crate::models::config::{AuthType, FigmaRetryConfig, HttpConfig, ImageFormat};
+use crate::models::figma::{Color, Document, Style, StyleNode};
 use reqwest::{
-    blocking::{Client, Response},
-    Error, StatusCode,
+    blocking::{Client, RequestBuilder, Response},
+    header::{HeaderName, HeaderValue, ETAG, IF_RANGE, RANGE, RETRY_AFTER},
+    Error, StatusCode, Url,
 };
 use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
-use std::fs::{self, File};
+use std::env;
+use std::fs::{self, File, OpenOptions};
 use std::io::BufReader;
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
 
 /// Response from Figma API.
 ///
@@ -18,8 +32,24 @@ use std::io::BufReader;
 ///
 /// Details: https://www.figma.com/developers/api#get-files-endpoint
 #[derive(Debug, Deserialize, Serialize)]
-struct FigmaGetFileResponse {
-    document: Document,
+pub struct FigmaGetFileResponse {
+    pub document: Document,
+    /// Version of the file, changes every time the file is changed.
+    /// Missing in the responses cached by the older versions of the app.
+    #[serde(default)]
+    pub version: Option<String>,
+    /// Styles of the file by ids of their nodes.
+    /// Missing in the responses cached by the older versions of the app.
+    #[serde(default)]
+    pub styles: HashMap<String, Style>,
+}
+
+/// Response from Figma API with the pages of the file only.
+///
+/// Endpoint: `https://api.figma.com/v1/files/:file_key?depth=1`
+#[derive(Debug, Deserialize)]
+struct FigmaGetFileVersionResponse {
+    version: Option<String>,
 }
 
 /// Response from Figma API.
@@ -29,22 +59,173 @@ struct FigmaGetFileResponse {
 /// Details: https://www.figma.com/developers/api#get-images-endpoint
 #[derive(Debug, Deserialize)]
 struct FigmaGetImageResponse {
-    images: HashMap<String, String>,
+    /// Urls by node ids. Url is `null` if Figma has failed to render the node.
+    images: HashMap<String, Option<String>>,
+}
+
+/// Response from Figma API.
+///
+/// Endpoint: `https://api.figma.com/v1/files/:file_key/nodes`
+///
+/// Details: https://www.figma.com/developers/api#get-file-nodes-endpoint
+#[derive(Debug, Deserialize)]
+struct FigmaGetNodesResponse {
+    /// Nodes by ids. Node is `null` if it doesn't exist.
+    nodes: HashMap<String, Option<FigmaNode>>,
+}
+
+#[derive(Debug, Deserialize)]
+struct FigmaNode {
+    /// Whole subtree of the node with all its properties.
+    document: serde_json::Value,
+}
+
+/// Image downloaded to the temporary directory with [FigmaApi::get_image].
+#[derive(Debug)]
+pub struct DownloadedImage {
+    pub file_name: String,
+    /// Size of the image from `Content-Length` header, if the server has sent it.
+    pub expected_size: Option<u64>,
+}
+
+impl DownloadedImage {
+    /// Returns the file name of the image, if its size matches the expected one.
+    /// Otherwise the download has been interrupted, and the file can't be decoded.
+    ///
+    /// # Arguments
+    ///
+    /// * `image_name` - Name of the image to show to the user.
+    pub fn verified(self, image_name: &String) -> Result<String, AppError> {
+        let expected_size = match self.expected_size {
+            Some(expected_size) => expected_size,
+            None => return Ok(self.file_name),
+        };
+        let actual_size = fs::metadata(&self.file_name)
+            .map_err(|_| AppError::GetImageTemporarySave)?
+            .len();
+        if actual_size == expected_size {
+            Ok(self.file_name)
+        } else {
+            Err(AppError::IncompleteDownload(
+                image_name.clone(),
+                expected_size,
+                actual_size,
+            ))
+        }
+    }
 }
 
 /// An `FigmaApi` to make requests to Figma API endpoints.
 ///
-/// Use `FigmaApi::new(client)` to build new instance.
+/// Use `FigmaApi::new(token)` to build new instance. Every request takes the renderer of the
+/// caller, which shows the retries, the rate limit waits and the debug output.
 ///
 /// # Example
 ///
-/// ```rust
-/// let api = FigmaApi::new(create_http_client(&figma_personal_access_token));
-/// let document = api.get_document(&file_id).unwrap();
-/// println!("{}", document);
+/// ```ignore
+/// let api = FigmaApi::new(&figma_personal_access_token).unwrap();
+/// let version = api.verify_file(&file_id, &Renderer()).unwrap();
+/// let (file, from_cache) = api.get_file(&file_id, &version, &Renderer()).unwrap();
+/// println!("{:?}", file.document);
 /// ```
 pub struct FigmaApi {
+    token: String,
+    /// Type of the token, resolved by [resolve_auth_type].
+    auth_type: AuthType,
+    /// Header with the token for the requests to Figma API, see [token_header].
+    token_header: (HeaderName, HeaderValue),
     client: Client,
+    retry_policy: RetryPolicy,
+    timeouts: Timeouts,
+    rate_limiter: Option<RateLimiter>,
+    verbose: bool,
+    /// Url the endpoints of Figma API are resolved against, always with the trailing slash.
+    base_url: String,
+    /// Download urls fetched by this instance, so the same image is never requested twice.
+    download_urls: Mutex<HashMap<DownloadUrlKey, String>>,
+    /// Nodes fetched by this instance by file id and node id.
+    nodes: Mutex<HashMap<(String, String), serde_json::Value>>,
+    stats: Mutex<ApiStats>,
+}
+
+/// Requests made by one [FigmaApi], to see how much of the quota and time they take.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct ApiStats {
+    /// Requests to the images endpoint, one for every batch of download urls.
+    pub download_url_requests: usize,
+    /// Images downloaded from the image storage. A resumed download is counted once.
+    pub image_downloads: usize,
+    /// Size of the downloaded images.
+    pub downloaded_bytes: u64,
+    /// Time spent in all requests, including the downloads of the bodies. The requests
+    /// made in parallel are summed up, so it may be longer than the wall-clock time.
+    pub network_time: Duration,
+}
+
+/// File id, node id, bits of the scale and the format the image is downloaded in.
+type DownloadUrlKey = (String, String, u32, String);
+
+/// How to retry requests failed with `429 Too Many Requests`, `5xx` statuses or timeouts.
+/// Other errors are returned immediately.
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    /// How many times the failed request is repeated.
+    pub max_retries: u32,
+    /// Delay before the first retry, doubled for every next retry.
+    /// Ignored if the response has `Retry-After` header.
+    pub base_delay: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_retries: 3,
+            base_delay: Duration::from_millis(1000),
+        }
+    }
+}
+
+impl From<&FigmaRetryConfig> for RetryPolicy {
+    fn from(config: &FigmaRetryConfig) -> Self {
+        Self {
+            max_retries: config.max_retries,
+            base_delay: Duration::from_millis(config.base_delay_ms),
+        }
+    }
+}
+
+/// Timeouts of the requests, separate for the quick requests to Figma API
+/// and for the downloads of the large files.
+#[derive(Debug, Clone)]
+pub struct Timeouts {
+    pub request: Duration,
+    pub download: Duration,
+}
+
+impl Default for Timeouts {
+    fn default() -> Self {
+        Self {
+            request: Duration::from_secs(30),
+            download: Duration::from_secs(300),
+        }
+    }
+}
+
+impl From<&HttpConfig> for Timeouts {
+    fn from(config: &HttpConfig) -> Self {
+        Self {
+            request: Duration::from_secs(config.timeout_secs),
+            download: Duration::from_secs(config.download_timeout_secs),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Delay before the retry with number `attempt`, starting from 1.
+    fn delay(&self, attempt: u32) -> Duration {
+        self.base_delay
+            .saturating_mul(2u32.saturating_pow(attempt.saturating_sub(1)))
+    }
 }
 
 impl ImageFormat {
@@ -54,44 +235,209 @@ impl ImageFormat {
             ImageFormat::Svg => "svg".to_string(),
             // We've returned PNG format because we will convert to WEBP manually
             ImageFormat::Webp => "png".to_string(),
+            // Also PNG, so the image is encoded to JPEG only once, with the desired quality
+            ImageFormat::Jpeg => "png".to_string(),
+            ImageFormat::Avif => "png".to_string(),
         }
     }
 }
 
-pub const FIGMA_FILES_ENDPOINT: &str = "https://api.figma.com/v1/files/";
-pub const FIGMA_IMAGES_ENDPOINT: &str = "https://api.figma.com/v1/images/";
+pub const FIGMA_API_URL: &str = "https://api.figma.com/";
+const FIGMA_FILES_PATH: &str = "v1/files/";
+const FIGMA_IMAGES_PATH: &str = "v1/images/";
+const FIGMA_ME_PATH: &str = "v1/me";
+
+/// Environment variable with the base url of Figma API, overrides `figma.baseUrl`.
+pub const FIGMA_BASE_URL_ENV: &str = "FXN_FIGMA_BASE_URL";
+
+/// How many node ids are sent in one request to the images endpoint,
+/// so the request url doesn't become too long.
+const IMAGES_BATCH_SIZE: usize = 50;
 
 impl FigmaApi {
     /// Create new `FigmaApi` instance to make requests to Figma API endpoints.
     ///
     /// # Arguments
     ///
-    /// * `client` - An instance of [reqwest::blocking::Client] to make requests with.
-    pub fn new(client: Client) -> Self {
-        Self { client }
+    /// * `token` - Figma personal or OAuth access token, sent with every request to Figma API.
+    ///   Its type is detected, unless it is set with [FigmaApi::set_auth_type].
+    ///   Returns [AppError::MalformedToken] if the token can't be sent in the http header.
+    pub fn new(token: &String) -> Result<Self, AppError> {
+        validate_token(token)?;
+        let timeouts = Timeouts::default();
+        let auth_type = resolve_auth_type(token, AuthType::Auto);
+        Ok(Self {
+            token: token.clone(),
+            auth_type,
+            token_header: token_header(token, auth_type)?,
+            client: create_http_client(&None, &HashMap::new(), timeouts.request),
+            retry_policy: RetryPolicy::default(),
+            timeouts,
+            rate_limiter: None,
+            verbose: false,
+            base_url: figma_base_url(&None),
+            download_urls: Mutex::new(HashMap::new()),
+            nodes: Mutex::new(HashMap::new()),
+            stats: Mutex::new(ApiStats::default()),
+        })
+    }
+
+    /// Requests made by this instance so far.
+    pub fn stats(&self) -> ApiStats {
+        *self.stats.lock().unwrap()
+    }
+
+    /// Show every request with its status and timing. Tokens are never shown.
+    pub fn set_verbose(&mut self, verbose: bool) {
+        self.verbose = verbose;
+    }
+
+    /// Replace the [RetryPolicy], e.g. with the one from the app config.
+    pub fn set_retry_policy(&mut self, retry_policy: RetryPolicy) {
+        self.retry_policy = retry_policy;
+    }
+
+    /// Pace the requests to Figma API, so there are no more than `requests_per_minute` of them.
+    /// Downloads of the images aren't limited. If `None`, the requests aren't paced.
+    pub fn set_rate_limit(&mut self, requests_per_minute: Option<u32>) {
+        self.rate_limiter = requests_per_minute.map(RateLimiter::new);
+    }
+
+    /// Send the requests to another Figma API host, e.g. to Figma for enterprise or to a mock
+    /// server in tests. [FIGMA_BASE_URL_ENV] environment variable wins over `base_url`,
+    /// and if neither is set, the requests are sent to [FIGMA_API_URL].
+    /// The images are downloaded from the urls returned by the API, whatever the host is.
+    pub fn set_base_url(&mut self, base_url: &Option<String>) {
+        self.base_url = figma_base_url(base_url);
+    }
+
+    /// Send the token as a personal access token or as an OAuth one.
+    /// With [AuthType::Auto] the type is detected by the prefix of the token.
+    pub fn set_auth_type(&mut self, auth_type: AuthType) -> Result<(), AppError> {
+        self.auth_type = resolve_auth_type(&self.token, auth_type);
+        self.token_header = token_header(&self.token, self.auth_type)?;
+        Ok(())
+    }
+
+    /// Type of the token, which it is sent to Figma API with.
+    pub fn auth_type(&self) -> AuthType {
+        self.auth_type
+    }
+
+    /// Url of Figma file with the given id, e.g. to show it to the user.
+    pub fn file_url(&self, file_id: &String) -> String {
+        format!("{}{}{}", self.base_url, FIGMA_FILES_PATH, file_id)
+    }
+
+    /// Replace the proxy and the [Timeouts] with the ones from the app config.
+    /// All requests, to Figma API and to image storage, are sent through the proxy
+    /// instead of the proxies from environment variables.
+    pub fn set_http_config(&mut self, http_config: &HttpConfig) {
+        self.timeouts = Timeouts::from(http_config);
+        self.client = create_http_client(
+            &http_config.proxy,
+            &http_config.headers,
+            self.timeouts.request,
+        );
+    }
+
+    /// Check that Figma accepts the token, before any long work is done with it.
+    /// Returns [AppError::InvalidToken] if the token is invalid or expired.
+    ///
+    /// Endpoint: `https://api.figma.com/v1/me`
+    pub fn verify_token(&self, renderer: &dyn Render) -> Result<(), AppError> {
+        let url = format!("{}{}", self.base_url, FIGMA_ME_PATH);
+        let response = self.send_with_retries(&url, renderer, || self.api_get(&url));
+        match response {
+            Ok(response)
+                if matches!(
+                    response.status(),
+                    StatusCode::FORBIDDEN | StatusCode::UNAUTHORIZED
+                ) =>
+            {
+                Err(AppError::InvalidToken(response.status()))
+            }
+            response => match_response_internal(response, &url, |_| Ok(())),
+        }
+    }
+
+    /// Check that the Figma file exists and can be accessed with the token, before the whole
+    /// file is downloaded. Returns the current version of the file, to compare it with the cache
+    /// and the lock file, or [AppError::FileNotFound] or [AppError::FileAccessDenied] otherwise.
+    ///
+    /// Endpoint: `https://api.figma.com/v1/files/:file_key?depth=1`
+    ///
+    /// # Arguments
+    ///
+    /// * `file_id` - Figma file identifier.
+    pub fn verify_file(
+        &self,
+        file_id: &String,
+        renderer: &dyn Render,
+    ) -> Result<Option<String>, AppError> {
+        let url = self.file_url(file_id);
+        // Only the pages of the file are returned with depth 1, so the response is small
+        let response = self.send_with_retries(&url, renderer, || {
+            self.api_get(&url).query(&[("depth", "1")])
+        });
+        match response {
+            Ok(response) if response.status() == StatusCode::NOT_FOUND => {
+                Err(AppError::FileNotFound(file_id.clone()))
+            }
+            Ok(response) if response.status() == StatusCode::FORBIDDEN => {
+                Err(AppError::FileAccessDenied(file_id.clone()))
+            }
+            response => {
+                match_response_internal(response, &url, |response| match response
+                    .json::<FigmaGetFileVersionResponse>(
+                ) {
+                    Ok(response) => Ok(response.version),
+                    Err(_) => Err(AppError::FetchDomResponseParsing(url.clone())),
+                })
+            }
+        }
     }
 
-    /// Load Figma [Document] from the cache if the cache is not empty. Otherwise,
-    /// load the document from the remote. Returns a tuple ([Document], bool), where
-    /// bool value is true, if the document have loaded from cache.
+    /// Load Figma file with its [Document] from the cache if the cached file has the current
+    /// version. Otherwise, the cache is dropped and the file is loaded from the remote.
+    /// Returns a tuple ([FigmaGetFileResponse], bool), where bool value is true,
+    /// if the file have loaded from cache.
     ///
     /// Endpoint: `https://api.figma.com/v1/files/:file_key`
     ///
     /// # Arguments
     ///
     /// * `file_id` - Figma file identifier. To obtain a file id, open the file in the browser.
-    /// The file id will be present in the URL after the word file and before the file name.
-    pub fn get_document(&self, file_id: &String) -> Result<(Document, bool), AppError> {
-        load_from_cache::<FigmaGetFileResponse>(&file_id)
-            .map(|response| (response.document, true))
+    ///   The file id will be present in the URL after the word file and before the file name.
+    /// * `version` - Current version of the file from [FigmaApi::verify_file]. If it is unknown,
+    ///   the cached file is used as is.
+    pub fn get_file(
+        &self,
+        file_id: &String,
+        version: &Option<String>,
+        renderer: &dyn Render,
+    ) -> Result<(FigmaGetFileResponse, bool), AppError> {
+        load_from_cache::<FigmaGetFileResponse>(file_id)
+            .and_then(
+                |response| match version.is_none() || &response.version == version {
+                    true => Ok((response, true)),
+                    false => {
+                        // The file has changed in Figma since it was cached
+                        let _ = fs::remove_file(cache_file_name(file_id));
+                        Err(AppError::LoadFromCache)
+                    }
+                },
+            )
             .or_else(|_| {
-                let url = format!("{}{}", FIGMA_FILES_ENDPOINT, &file_id);
-                let response = self.client.get(&url).send();
+                let url = self.file_url(file_id);
+                let response = self.send_with_retries(&url, renderer, || {
+                    self.api_get(&url).timeout(self.timeouts.download)
+                });
                 match_response_internal(response, &url, |response| {
                     match response.json::<FigmaGetFileResponse>() {
                         Ok(response) => {
-                            save_to_cache(&response, &file_id).unwrap_or_default();
-                            Ok((response.document, false))
+                            save_to_cache(&response, file_id).unwrap_or_default();
+                            Ok((response, false))
                         }
                         Err(_) => Err(AppError::FetchDomResponseParsing(url.clone())),
                     }
@@ -106,36 +452,145 @@ impl FigmaApi {
     /// # Arguments
     ///
     /// * `file_id` - Figma file identifier. To obtain a file id, open the file in the browser.
-    /// The file id will be present in the URL after the word file and before the file name.
+    ///   The file id will be present in the URL after the word file and before the file name.
     /// * `node_id` - node identifier inside Figma file. You can obtain node ids from [Document].
-    /// Learn more about nodes: https://www.figma.com/developers/api#files
+    ///   Learn more about nodes: https://www.figma.com/developers/api#files
     /// * `scale` - The scale of the exported image, from 0.5 to 4.
     /// * `format` - Format of the exported image. Figma API supports only JPEG, PNG, SVG and
-    /// PDF formats.
+    ///   PDF formats.
     pub fn get_image_download_url(
         &self,
         file_id: &String,
         node_id: &String,
         scale: f32,
         format: &ImageFormat,
+        renderer: &dyn Render,
     ) -> Result<String, AppError> {
-        let url = format!("{}{}", FIGMA_IMAGES_ENDPOINT, &file_id);
-        let response = self
-            .client
-            .get(&url)
-            .query(&[("ids", node_id.clone())])
-            .query(&[("scale", scale)])
-            .query(&[("format", format.download_extension())])
-            .send();
-        match_response_internal(response, &url, |response| {
-            match response.json::<FigmaGetImageResponse>() {
-                Ok(response) => Ok(response.images.get(node_id).unwrap().clone()), // todo: unwrap safe
-                Err(_) => Err(AppError::GetImageDownloadUrl(url.clone())),
+        let url = format!("{}{}{}", self.base_url, FIGMA_IMAGES_PATH, &file_id);
+        self.get_image_download_urls(
+            file_id,
+            std::slice::from_ref(node_id),
+            scale,
+            format,
+            renderer,
+        )?
+        .remove(node_id)
+        .ok_or(AppError::GetImageDownloadUrl(url))
+    }
+
+    /// Get urls of many exported Figma frames to download, with one request for every
+    /// [IMAGES_BATCH_SIZE] nodes. Returns urls by node ids. Nodes which Figma has failed
+    /// to render are missing in the result. Urls fetched before by this instance are
+    /// not requested again.
+    ///
+    /// Endpoint: `https://api.figma.com/v1/images/:file_key`
+    ///
+    /// # Arguments
+    ///
+    /// * `file_id` - Figma file identifier.
+    /// * `node_ids` - node identifiers inside Figma file.
+    /// * `scale` - The scale of the exported images, from 0.5 to 4.
+    /// * `format` - Format of the exported images.
+    pub fn get_image_download_urls(
+        &self,
+        file_id: &String,
+        node_ids: &[String],
+        scale: f32,
+        format: &ImageFormat,
+        renderer: &dyn Render,
+    ) -> Result<HashMap<String, String>, AppError> {
+        let url = format!("{}{}{}", self.base_url, FIGMA_IMAGES_PATH, &file_id);
+        let key = |node_id: &String| -> DownloadUrlKey {
+            (
+                file_id.clone(),
+                node_id.clone(),
+                scale.to_bits(),
+                format.download_extension(),
+            )
+        };
+        let mut urls = HashMap::with_capacity(node_ids.len());
+        let mut missing_ids: Vec<String> = Vec::new();
+        {
+            let cache = self.download_urls.lock().unwrap();
+            for node_id in node_ids {
+                match cache.get(&key(node_id)) {
+                    Some(download_url) => {
+                        urls.insert(node_id.clone(), download_url.clone());
+                    }
+                    None => missing_ids.push(node_id.clone()),
+                }
             }
-        })
+        }
+        for chunk in missing_ids.chunks(IMAGES_BATCH_SIZE) {
+            let fetched_urls = self.get_images(&url, &chunk.join(","), scale, format, renderer)?;
+            self.download_urls.lock().unwrap().extend(
+                fetched_urls
+                    .iter()
+                    .map(|(node_id, download_url)| (key(node_id), download_url.clone())),
+            );
+            urls.extend(fetched_urls);
+        }
+        Ok(urls)
+    }
+
+    /// Get hashes of the nodes with all their properties and children, with one request for
+    /// every [IMAGES_BATCH_SIZE] nodes. Hash of the node changes only if the node is changed,
+    /// so the images can be compared with the exported ones without downloading them.
+    /// Returns hashes by node ids. Missing nodes are missing in the result.
+    ///
+    /// Endpoint: `https://api.figma.com/v1/files/:file_key/nodes`
+    ///
+    /// # Arguments
+    ///
+    /// * `file_id` - Figma file identifier.
+    /// * `node_ids` - node identifiers inside Figma file.
+    pub fn get_node_hashes(
+        &self,
+        file_id: &String,
+        node_ids: &[String],
+        renderer: &dyn Render,
+    ) -> Result<HashMap<String, String>, AppError> {
+        let nodes = self.get_nodes(file_id, node_ids, renderer)?;
+        Ok(nodes
+            .into_iter()
+            .map(|(id, document)| (id, text_hash(&document.to_string())))
+            .collect())
     }
 
-    /// Download an image from remote.
+    /// Get colors of the color styles, with one request for every [IMAGES_BATCH_SIZE] styles.
+    /// Returns colors by node ids of the styles. Styles without solid fill, e.g. gradients,
+    /// are missing in the result.
+    ///
+    /// Endpoint: `https://api.figma.com/v1/files/:file_key/nodes`
+    ///
+    /// # Arguments
+    ///
+    /// * `file_id` - Figma file identifier.
+    /// * `node_ids` - node identifiers of the styles from [FigmaGetFileResponse::styles].
+    pub fn get_style_colors(
+        &self,
+        file_id: &String,
+        node_ids: &[String],
+        renderer: &dyn Render,
+    ) -> Result<HashMap<String, Color>, AppError> {
+        let url = format!("{}/nodes", self.file_url(file_id));
+        let nodes = self.get_nodes(file_id, node_ids, renderer)?;
+        let mut colors = HashMap::with_capacity(nodes.len());
+        for (id, document) in nodes {
+            let style_node = serde_json::from_value::<StyleNode>(document)
+                .map_err(|_| AppError::FetchDomResponseParsing(url.clone()))?;
+            if let Some(color) = style_node.solid_color() {
+                colors.insert(id, color);
+            }
+        }
+        Ok(colors)
+    }
+
+    /// Download an image from remote to a temporary file with a unique name.
+    /// If the connection breaks, the download is resumed from the received part, as long as
+    /// the server supports range requests. The part is kept between runs, so the next export
+    /// resumes it too, unless another download uses it, see [claim_partial_file]. Parts which
+    /// can't be resumed are removed. Check the result with [DownloadedImage::verified].
     ///
     /// # Arguments
     ///
@@ -149,22 +604,363 @@ impl FigmaApi {
         image_name: &String,
         image_scale_name: &String,
         image_format: &ImageFormat,
-    ) -> Result<String, AppError> {
-        let response = self.client.get(image_url).send();
-        match_response_internal(response, &image_url, |response| {
-            let bytes = response.bytes().map_err(|_| AppError::GetImageByteStream)?;
-            create_temp_dir().map_err(|_| AppError::CreateTempDir)?;
-            let image_file_name = format!(
-                "{}/{}_{}.{}",
-                TEMP_DIR_PATH,
-                &image_name,
-                &image_scale_name,
-                image_format.download_extension(),
+        renderer: &dyn Render,
+    ) -> Result<DownloadedImage, AppError> {
+        create_temp_files_dir().map_err(|_| AppError::CreateTempDir)?;
+        let extension = image_format.download_extension();
+        let partial = claim_partial_file(image_name, image_scale_name, &extension);
+        let partial_file = &partial.path;
+        let mut attempt = 0u32;
+        let expected_size = loop {
+            let result = self.download_to_partial_file(image_url, partial_file, true, renderer);
+            let downloaded_size = fs::metadata(partial_file).map(|m| m.len()).unwrap_or(0);
+            let result = match result {
+                Ok(Some(expected_size)) if downloaded_size < expected_size => {
+                    Err(AppError::IncompleteDownload(
+                        image_name.clone(),
+                        expected_size,
+                        downloaded_size,
+                    ))
+                }
+                result => result,
+            };
+            match result {
+                Ok(expected_size) => break expected_size,
+                // Only the rest of the image is requested again
+                Err(e)
+                    if is_resumable(&e)
+                        && downloaded_size > 0
+                        && attempt < self.retry_policy.max_retries =>
+                {
+                    attempt += 1;
+                    renderer.render(&View::ResumingDownload {
+                        name: image_name.clone(),
+                        downloaded_bytes: downloaded_size,
+                        attempt,
+                        max_retries: self.retry_policy.max_retries,
+                    });
+                }
+                Err(e) => {
+                    // Only the part of a broken download of the shared file is useful next time
+                    if !partial.resumable || !is_resumable(&e) {
+                        remove_partial_file(partial_file);
+                    }
+                    return Err(e);
+                }
+            }
+        };
+        let image_file_name = temp_file_name(image_name, image_scale_name, &extension);
+        let moved = move_file(partial_file, &image_file_name);
+        remove_partial_file(partial_file);
+        moved.map_err(|_| AppError::GetImageTemporarySave)?;
+        let mut stats = self.stats.lock().unwrap();
+        stats.image_downloads += 1;
+        stats.downloaded_bytes += fs::metadata(&image_file_name).map(|m| m.len()).unwrap_or(0);
+        Ok(DownloadedImage {
+            file_name: image_file_name,
+            expected_size,
+        })
+    }
+}
+
+impl FigmaApi {
+    /// Download the image to `partial_file`, continuing the previous download if the file exists.
+    /// `If-Range` header with `ETag` of the previous download makes the server send the whole
+    /// image, if it has changed since then. Servers without range requests send the whole image
+    /// anyway. The file is downloaded from the start if `resume` is `false`.
+    /// Returns the size of the whole image, if it is known.
+    fn download_to_partial_file(
+        &self,
+        image_url: &String,
+        partial_file: &String,
+        resume: bool,
+        renderer: &dyn Render,
+    ) -> Result<Option<u64>, AppError> {
+        let etag_file = etag_file_name(partial_file);
+        let downloaded_size = fs::metadata(partial_file).map(|m| m.len()).unwrap_or(0);
+        // Without ETag there is no way to check that the part is from the same image
+        let resumed_etag = match fs::read_to_string(&etag_file) {
+            Ok(etag) if resume && downloaded_size > 0 => Some(etag),
+            _ => None,
+        };
+        let response = self.send_with_retries(image_url, renderer, || {
+            let request = self.client.get(image_url).timeout(self.timeouts.download);
+            match &resumed_etag {
+                Some(etag) => request
+                    .header(RANGE, format!("bytes={}-", downloaded_size))
+                    .header(IF_RANGE, etag),
+                None => request,
+            }
+        });
+        match response {
+            Ok(response)
+                if resumed_etag.is_some() && response.status() == StatusCode::PARTIAL_CONTENT =>
+            {
+                let expected_size = response.content_length().map(|l| downloaded_size + l);
+                let file = OpenOptions::new().append(true).open(partial_file);
+                self.write_body(response, file, image_url)
+                    .map(|_| expected_size)
+            }
+            // The part is larger than the image, so the image has changed
+            Ok(response)
+                if resumed_etag.is_some()
+                    && response.status() == StatusCode::RANGE_NOT_SATISFIABLE =>
+            {
+                remove_partial_file(partial_file);
+                self.download_to_partial_file(image_url, partial_file, false, renderer)
+            }
+            response => match_response_internal(response, image_url, |response| {
+                match response.headers().get(ETAG).and_then(|v| v.to_str().ok()) {
+                    Some(etag) => fs::write(&etag_file, etag).unwrap_or_default(),
+                    None => fs::remove_file(&etag_file).unwrap_or_default(),
+                }
+                let expected_size = response.content_length();
+                self.write_body(response, File::create(partial_file), image_url)
+                    .map(|_| expected_size)
+            }),
+        }
+    }
+
+    /// Write the body of the response to the file. What has been received before the connection
+    /// breaks stays in the file, so the download can be resumed.
+    fn write_body(
+        &self,
+        mut response: Response,
+        file: std::io::Result<File>,
+        url: &String,
+    ) -> Result<(), AppError> {
+        let mut file = file.map_err(|_| AppError::GetImageTemporarySave)?;
+        let started_at = Instant::now();
+        let result = response.copy_to(&mut file);
+        self.stats.lock().unwrap().network_time += started_at.elapsed();
+        result.map(|_| ()).map_err(|e| {
+            if e.is_timeout() {
+                AppError::RequestTimeout(url.clone())
+            } else {
+                AppError::GetImageByteStream
+            }
+        })
+    }
+
+    /// Request the images endpoint for comma separated `ids`, skipping nodes without url.
+    fn get_images(
+        &self,
+        url: &String,
+        ids: &String,
+        scale: f32,
+        format: &ImageFormat,
+        renderer: &dyn Render,
+    ) -> Result<HashMap<String, String>, AppError> {
+        self.stats.lock().unwrap().download_url_requests += 1;
+        let response = self.send_with_retries(url, renderer, || {
+            self.api_get(url)
+                .query(&[("ids", ids.clone())])
+                .query(&[("scale", scale)])
+                .query(&[("format", format.download_extension())])
+        });
+        match_response_internal(response, url, |response| {
+            match response.json::<FigmaGetImageResponse>() {
+                Ok(response) => Ok(response
+                    .images
+                    .into_iter()
+                    .filter_map(|(id, url)| url.map(|url| (id, url)))
+                    .collect()),
+                Err(_) => Err(AppError::GetImageDownloadUrl(url.clone())),
+            }
+        })
+    }
+
+    /// Get the nodes with all their properties and children, with one request for every
+    /// [IMAGES_BATCH_SIZE] nodes. Missing nodes are missing in the result.
+    /// Nodes fetched before by this instance are not requested again.
+    fn get_nodes(
+        &self,
+        file_id: &String,
+        node_ids: &[String],
+        renderer: &dyn Render,
+    ) -> Result<HashMap<String, serde_json::Value>, AppError> {
+        let url = format!("{}/nodes", self.file_url(file_id));
+        let key = |node_id: &String| (file_id.clone(), node_id.clone());
+        let mut documents = HashMap::with_capacity(node_ids.len());
+        let mut missing_ids: Vec<String> = Vec::new();
+        {
+            let cache = self.nodes.lock().unwrap();
+            for node_id in node_ids {
+                match cache.get(&key(node_id)) {
+                    Some(document) => {
+                        documents.insert(node_id.clone(), document.clone());
+                    }
+                    None => missing_ids.push(node_id.clone()),
+                }
+            }
+        }
+        for chunk in missing_ids.chunks(IMAGES_BATCH_SIZE) {
+            let ids = chunk.join(",");
+            let response = self.send_with_retries(&url, renderer, || {
+                self.api_get(&url).query(&[("ids", ids.clone())])
+            });
+            let nodes = match_response_internal(response, &url, |response| {
+                response
+                    .json::<FigmaGetNodesResponse>()
+                    .map_err(|_| AppError::FetchDomResponseParsing(url.clone()))
+            })?;
+            let fetched_documents = nodes
+                .nodes
+                .into_iter()
+                .filter_map(|(id, node)| node.map(|node| (id, node.document)))
+                .collect::<HashMap<String, serde_json::Value>>();
+            self.nodes.lock().unwrap().extend(
+                fetched_documents
+                    .iter()
+                    .map(|(id, document)| (key(id), document.clone())),
             );
-            fs::write(&image_file_name, bytes)
-                .map_err(|_| AppError::GetImageTemporarySave)
-                .map(|_| image_file_name)
+            documents.extend(fetched_documents);
+        }
+        Ok(documents)
+    }
+
+    /// Build GET request to Figma API with the token header.
+    fn api_get(&self, url: &str) -> RequestBuilder {
+        let (name, value) = &self.token_header;
+        self.client.get(url).header(name, value)
+    }
+
+    /// Send the request and repeat it according to the [RetryPolicy] while it fails
+    /// with a transient error. Every retry is shown to the user.
+    ///
+    /// # Arguments
+    ///
+    /// * `url` - Url of the request, to show to the user.
+    /// * `renderer` - Renderer of the caller, to show the retries.
+    /// * `build_request` - Function to build the request for every attempt.
+    fn send_with_retries<B>(
+        &self,
+        url: &String,
+        renderer: &dyn Render,
+        build_request: B,
+    ) -> Result<Response, Error>
+    where
+        B: Fn() -> RequestBuilder,
+    {
+        let mut attempt = 0u32;
+        loop {
+            self.wait_for_rate_limit(url, renderer);
+            let response = self.send(build_request(), renderer);
+            if attempt >= self.retry_policy.max_retries {
+                return response;
+            }
+            attempt += 1;
+            let delay = match &response {
+                Ok(r) if is_retryable_status(r.status()) => {
+                    retry_after(r).unwrap_or_else(|| self.retry_policy.delay(attempt))
+                }
+                Err(e) if e.is_timeout() => self.retry_policy.delay(attempt),
+                _ => return response,
+            };
+            renderer.render(&View::Retrying {
+                url: url.clone(),
+                attempt,
+                max_retries: self.retry_policy.max_retries,
+                delay_ms: delay.as_millis(),
+            });
+            thread::sleep(delay);
+        }
+    }
+}
+
+impl FigmaApi {
+    /// Wait until the request to Figma API fits into the rate limit, if there is one.
+    fn wait_for_rate_limit(&self, url: &String, renderer: &dyn Render) {
+        let rate_limiter = match &self.rate_limiter {
+            Some(rate_limiter) if url.starts_with(&self.base_url) => rate_limiter,
+            _ => return,
+        };
+        let delay = rate_limiter.acquire();
+        if delay.is_zero() {
+            return;
+        }
+        renderer.render(&View::RateLimited {
+            requests_per_minute: rate_limiter.requests_per_minute(),
+            delay_ms: delay.as_millis(),
+        });
+        thread::sleep(delay);
+    }
+
+    /// Send the request once. In verbose mode, the request is shown with its status and timing.
+    fn send(
+        &self,
+        request_builder: RequestBuilder,
+        renderer: &dyn Render,
+    ) -> Result<Response, Error> {
+        let request = request_builder.build()?;
+        let (method, url) = (request.method().to_string(), redacted_url(request.url()));
+        let started_at = Instant::now();
+        let response = self.client.execute(request);
+        self.stats.lock().unwrap().network_time += started_at.elapsed();
+        if !self.verbose {
+            return response;
+        }
+        let status = match &response {
+            Ok(r) => r.status().to_string(),
+            Err(e) if e.is_timeout() => "timeout".to_string(),
+            Err(_) => "connection error".to_string(),
+        };
+        renderer.render_line(&View::Debug {
+            method,
+            url,
+            status,
+            elapsed_ms: started_at.elapsed().as_millis(),
+        });
+        response
+    }
+}
+
+/// Returns the url with values of token-like query parameters replaced with `***`.
+/// Figma token itself is sent in the header, but download urls may contain other credentials.
+fn redacted_url(url: &Url) -> String {
+    if url.query().is_none() {
+        return url.to_string();
+    }
+    let pairs = url
+        .query_pairs()
+        .map(|(key, value)| {
+            let key_lowercase = key.to_lowercase();
+            let is_secret = ["token", "signature", "credential"]
+                .iter()
+                .any(|secret| key_lowercase.contains(secret));
+            let value = if is_secret { "***".into() } else { value };
+            (key.into_owned(), value.into_owned())
         })
+        .collect::<Vec<(String, String)>>();
+    let mut url = url.clone();
+    url.query_pairs_mut().clear().extend_pairs(pairs);
+    url.to_string()
+}
+
+fn is_retryable_status(status: StatusCode) -> bool {
+    status == StatusCode::TOO_MANY_REQUESTS || status.is_server_error()
+}
+
+/// Returns the delay from `Retry-After` header, if it is specified in seconds.
+fn retry_after(response: &Response) -> Option<Duration> {
+    response
+        .headers()
+        .get(RETRY_AFTER)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.trim().parse::<u64>().ok())
+        .map(Duration::from_secs)
+}
+
+/// Returns `true` if the download has failed because of the connection, so the rest of the
+/// image can be requested again. Other errors fail the same way on every attempt.
+fn is_resumable(error: &AppError) -> bool {
+    match error {
+        AppError::IncompleteDownload(..)
+        | AppError::RequestTimeout(_)
+        | AppError::GetImageByteStream
+        | AppError::RequestMaybeVPN(_) => true,
+        AppError::RequestHttpStatus(_, status) => is_retryable_status(*status),
+        _ => false,
     }
 }
 
@@ -182,20 +978,41 @@ where
             StatusCode::FORBIDDEN => Err(AppError::RequestUnauthorized(response.status())),
             _ => Err(AppError::RequestHttpStatus(url.clone(), response.status())),
         },
+        Err(e) if e.is_timeout() => Err(AppError::RequestTimeout(url.clone())),
         Err(_) => Err(AppError::RequestMaybeVPN(url.clone())),
     }
 }
 
+/// Base url of Figma API with the trailing slash, see [FigmaApi::set_base_url].
+fn figma_base_url(base_url: &Option<String>) -> String {
+    figma_base_url_with_env(base_url, env::var(FIGMA_BASE_URL_ENV).ok())
+}
+
+fn figma_base_url_with_env(base_url: &Option<String>, env_base_url: Option<String>) -> String {
+    let base_url = env_base_url
+        .filter(|url| !url.trim().is_empty())
+        .or_else(|| base_url.clone())
+        .unwrap_or_else(|| FIGMA_API_URL.to_string());
+    match base_url.ends_with('/') {
+        true => base_url,
+        false => format!("{}/", base_url),
+    }
+}
+
+fn cache_file_name(id: &String) -> String {
+    format!("{}/cache_{}.json", TEMP_DIR_PATH, &id)
+}
+
 fn load_from_cache<T: DeserializeOwned>(id: &String) -> Result<T, AppError> {
-    let file_name = format!("{}/cache_{}.json", TEMP_DIR_PATH, &id);
+    let file_name = cache_file_name(id);
     File::open(&file_name)
         .map_err(|_| AppError::LoadFromCache)
-        .map(|file| BufReader::new(file))
+        .map(BufReader::new)
         .map(|it| serde_json::from_reader(it).unwrap())
 }
 
 fn save_to_cache<T: Serialize>(value: T, id: &String) -> Result<(), AppError> {
-    let file_name = format!("{}/cache_{}.json", TEMP_DIR_PATH, &id);
+    let file_name = cache_file_name(id);
     match create_temp_dir() {
         Ok(()) => match File::create(&file_name).map(|it| serde_json::to_writer(&it, &value)) {
             Ok(_) => Ok(()),
@@ -204,3 +1021,86 @@ fn save_to_cache<T: Serialize>(value: T, id: &String) -> Result<(), AppError> {
         Err(_) => Err(AppError::CreateTempDir),
     }
 }
+
+#[test]
+fn test_retry_delay() {
+    let policy = RetryPolicy {
+        max_retries: 3,
+        base_delay: Duration::from_millis(500),
+    };
+    assert_eq!(policy.delay(1), Duration::from_millis(500));
+    assert_eq!(policy.delay(2), Duration::from_millis(1000));
+    assert_eq!(policy.delay(3), Duration::from_millis(2000));
+}
+
+#[test]
+fn test_retryable_statuses() {
+    assert!(is_retryable_status(StatusCode::TOO_MANY_REQUESTS));
+    assert!(is_retryable_status(StatusCode::BAD_GATEWAY));
+    assert!(!is_retryable_status(StatusCode::NOT_FOUND));
+    assert!(!is_retryable_status(StatusCode::FORBIDDEN));
+}
+
+#[test]
+fn test_figma_base_url() {
+    assert_eq!(figma_base_url_with_env(&None, None), FIGMA_API_URL);
+    let config_url = Some("https://figma.example.com/api".to_string());
+    assert_eq!(
+        figma_base_url_with_env(&config_url, None),
+        "https://figma.example.com/api/"
+    );
+    let env_url = Some("http://localhost:8080/".to_string());
+    assert_eq!(
+        figma_base_url_with_env(&config_url, env_url),
+        "http://localhost:8080/"
+    );
+    assert_eq!(
+        figma_base_url_with_env(&config_url, Some(" ".to_string())),
+        "https://figma.example.com/api/"
+    );
+}
+
+#[test]
+fn test_redacted_url() {
+    let url =
+        Url::parse("https://s3.example.com/img?X-Amz-Signature=abc&token=def&scale=2").unwrap();
+    assert_eq!(
+        redacted_url(&url),
+        "https://s3.example.com/img?X-Amz-Signature=***&token=***&scale=2"
+    );
+    let url = Url::parse("https://api.figma.com/v1/files/abc").unwrap();
+    assert_eq!(redacted_url(&url), "https://api.figma.com/v1/files/abc");
+}
+
+#[test]
+fn test_resumable_errors() {
+    let url = "https://s3.example.com/img".to_string();
+    assert!(is_resumable(&AppError::IncompleteDownload(
+        url.clone(),
+        10,
+        5
+    )));
+    assert!(is_resumable(&AppError::RequestTimeout(url.clone())));
+    let status = StatusCode::SERVICE_UNAVAILABLE;
+    assert!(is_resumable(&AppError::RequestHttpStatus(
+        url.clone(),
+        status
+    )));
+    let status = StatusCode::NOT_FOUND;
+    assert!(!is_resumable(&AppError::RequestHttpStatus(url, status)));
+    assert!(!is_resumable(&AppError::GetImageTemporarySave));
+}
+
+#[test]
+fn test_malformed_token_is_rejected() {
+    for token in ["figd_aBcD\n1234", "figd_токен"] {
+        assert!(matches!(
+            FigmaApi::new(&token.to_string()),
+            Err(AppError::MalformedToken)
+        ));
+    }
+    let mut api = FigmaApi::new(&"figu_aBcD1234".to_string()).unwrap();
+    assert_eq!(api.token_header.0, reqwest::header::AUTHORIZATION);
+    api.set_auth_type(AuthType::Personal).unwrap();
+    assert_eq!(api.token_header.0.as_str(), "x-figma-token");
+}
@@ -1 +1,3 @@
 pub mod figma;
+pub mod rate_limiter;
+mod view;
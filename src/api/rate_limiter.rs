@@ -0,0 +1,84 @@
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Client-side limit of the requests rate, so the requests are paced before Figma API
+/// starts to reject them with `429 Too Many Requests`.
+///
+/// It is a token bucket: every request takes a token, and the tokens are refilled evenly
+/// during a minute. The bucket holds at most one second worth of tokens, so the requests
+/// are never sent in a large burst. The bucket is behind a lock, so it is shared by all
+/// threads making the requests.
+pub struct RateLimiter {
+    requests_per_minute: u32,
+    bucket: Mutex<Bucket>,
+}
+
+struct Bucket {
+    tokens: f64,
+    updated_at: Instant,
+}
+
+impl RateLimiter {
+    /// Create new `RateLimiter` with a full bucket.
+    ///
+    /// # Arguments
+    ///
+    /// * `requests_per_minute` - Maximum rate of the requests, must be positive.
+    pub fn new(requests_per_minute: u32) -> Self {
+        let limiter = Self {
+            requests_per_minute,
+            bucket: Mutex::new(Bucket {
+                tokens: 0f64,
+                updated_at: Instant::now(),
+            }),
+        };
+        limiter.bucket.lock().unwrap().tokens = limiter.capacity();
+        limiter
+    }
+
+    pub fn requests_per_minute(&self) -> u32 {
+        self.requests_per_minute
+    }
+
+    /// Take a token for the request. Returns how long to wait before sending the request,
+    /// zero if the request can be sent right now. The token is reserved anyway, so the
+    /// requests from other threads wait for their own tokens.
+    pub fn acquire(&self) -> Duration {
+        self.acquire_at(Instant::now())
+    }
+
+    fn acquire_at(&self, now: Instant) -> Duration {
+        let mut bucket = self.bucket.lock().unwrap();
+        let elapsed = now.saturating_duration_since(bucket.updated_at);
+        bucket.tokens = (bucket.tokens + elapsed.as_secs_f64() * self.rate()).min(self.capacity());
+        bucket.updated_at = now;
+        bucket.tokens -= 1f64;
+        if bucket.tokens >= 0f64 {
+            Duration::ZERO
+        } else {
+            Duration::from_secs_f64(-bucket.tokens / self.rate())
+        }
+    }
+
+    /// Tokens refilled per second.
+    fn rate(&self) -> f64 {
+        self.requests_per_minute as f64 / 60f64
+    }
+
+    fn capacity(&self) -> f64 {
+        self.rate().max(1f64)
+    }
+}
+
+#[test]
+fn test_rate_limiter_paces_requests() {
+    let limiter = RateLimiter::new(60);
+    let start = limiter.bucket.lock().unwrap().updated_at;
+    assert_eq!(limiter.acquire_at(start), Duration::ZERO);
+    assert_eq!(limiter.acquire_at(start), Duration::from_secs(1));
+    assert_eq!(limiter.acquire_at(start), Duration::from_secs(2));
+    // Both reserved tokens are refilled, but not more than the capacity
+    let later = start + Duration::from_secs(10);
+    assert_eq!(limiter.acquire_at(later), Duration::ZERO);
+    assert_eq!(limiter.acquire_at(later), Duration::from_secs(1));
+}
@@ -0,0 +1,86 @@
+use crossterm::style::Stylize;
+
+use crate::common::renderer::{Indentable, Renderable};
+
+pub enum View {
+    Retrying {
+        url: String,
+        attempt: u32,
+        max_retries: u32,
+        delay_ms: u128,
+    },
+    /// The download of the image is resumed after the connection has broken.
+    ResumingDownload {
+        name: String,
+        downloaded_bytes: u64,
+        attempt: u32,
+        max_retries: u32,
+    },
+    /// The request is delayed to stay within the rate limit.
+    RateLimited {
+        requests_per_minute: u32,
+        delay_ms: u128,
+    },
+    /// Request and its result, shown only in verbose mode.
+    Debug {
+        method: String,
+        url: String,
+        status: String,
+        elapsed_ms: u128,
+    },
+}
+
+impl Renderable for View {
+    fn render(&self) -> String {
+        match self {
+            View::Retrying {
+                url,
+                attempt,
+                max_retries,
+                delay_ms,
+            } => format!(
+                "{} request to {} in {} ms (attempt {}/{})",
+                "Retrying".indent().bold().yellow(),
+                &url,
+                &delay_ms,
+                &attempt,
+                &max_retries,
+            ),
+            View::ResumingDownload {
+                name,
+                downloaded_bytes,
+                attempt,
+                max_retries,
+            } => format!(
+                "{} download of image {} from {} bytes (attempt {}/{})",
+                "Resuming".indent().bold().yellow(),
+                &name,
+                &downloaded_bytes,
+                &attempt,
+                &max_retries,
+            ),
+            View::RateLimited {
+                requests_per_minute,
+                delay_ms,
+            } => format!(
+                "{} {} ms to stay within {} requests per minute",
+                "Waiting".indent().bold().yellow(),
+                &delay_ms,
+                &requests_per_minute,
+            ),
+            View::Debug {
+                method,
+                url,
+                status,
+                elapsed_ms,
+            } => format!(
+                "{} {} {} -> {} in {} ms",
+                "Debug".indent().bold().dark_grey(),
+                &method,
+                &url,
+                &status,
+                &elapsed_ms,
+            ),
+        }
+    }
+}
@@ -0,0 +1,27 @@
+/// Directory of adaptive icons, they are supported since Android 8.0 (API level 26).
+pub const ADAPTIVE_ICON_DIR: &str = "mipmap-anydpi-v26";
+
+/// Generate the source of the adaptive icon XML with the layers from the resources.
+///
+/// # Arguments
+///
+/// * `foreground` - Reference to the foreground layer, e.g. `@mipmap/ic_launcher_foreground`
+/// * `background` - Reference to the background layer, e.g. `@mipmap/ic_launcher_background`
+pub fn adaptive_icon_source(foreground: &str, background: &str) -> String {
+    format!(
+        r#"<?xml version="1.0" encoding="utf-8"?>
+<adaptive-icon xmlns:android="http://schemas.android.com/apk/res/android">
+    <background android:drawable="{}" />
+    <foreground android:drawable="{}" />
+</adaptive-icon>
+"#,
+        background, foreground
+    )
+}
+
+#[test]
+fn test_adaptive_icon_source() {
+    let source = adaptive_icon_source("@mipmap/ic_fg", "@drawable/ic_bg");
+    assert!(source.contains(r#"<background android:drawable="@drawable/ic_bg" />"#));
+    assert!(source.contains(r#"<foreground android:drawable="@mipmap/ic_fg" />"#));
+}
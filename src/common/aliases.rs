@@ -0,0 +1,76 @@
+use std::collections::BTreeMap;
+use std::fs;
+
+/// Name of the file in `values` directory with the aliases of the duplicate images.
+pub const ALIASES_FILE_NAME: &str = "fxn_aliases.xml";
+
+/// Resource, which points to another resource of the same type instead of having its own file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Alias {
+    /// Type of the resources, e.g. `drawable` or `mipmap`.
+    pub res_type: String,
+    /// Name of the resource the alias points to.
+    pub target: String,
+}
+
+/// Read the aliases by their names from the file generated with [aliases_source].
+/// Returns no aliases if there is no such file.
+pub fn read_aliases(path: &String) -> BTreeMap<String, Alias> {
+    parse_aliases(&fs::read_to_string(path).unwrap_or_default())
+}
+
+fn parse_aliases(source: &str) -> BTreeMap<String, Alias> {
+    source
+        .lines()
+        .filter_map(|line| {
+            // Every alias is on its own line: `<item name="..." type="...">@type/target</item>`
+            let rest = line.trim().strip_prefix("<item name=\"")?;
+            let (name, rest) = rest.split_once("\" type=\"")?;
+            let (res_type, rest) = rest.split_once("\">@")?;
+            let (_, rest) = rest.split_once('/')?;
+            let target = rest.strip_suffix("</item>")?;
+            let alias = Alias {
+                res_type: res_type.to_string(),
+                target: target.to_string(),
+            };
+            Some((name.to_string(), alias))
+        })
+        .collect()
+}
+
+/// Generate the source of values XML with the aliases, sorted by their names.
+pub fn aliases_source(aliases: &BTreeMap<String, Alias>) -> String {
+    let items = aliases
+        .iter()
+        .map(|(name, alias)| {
+            format!(
+                "    <item name=\"{}\" type=\"{}\">@{}/{}</item>\n",
+                name, alias.res_type, alias.res_type, alias.target
+            )
+        })
+        .collect::<String>();
+    format!(
+        r#"<?xml version="1.0" encoding="utf-8"?>
+<!-- Generated by fxa, images identical to other images -->
+<resources>
+{}</resources>
+"#,
+        items
+    )
+}
+
+#[test]
+fn test_aliases_source_is_parsed_back() {
+    let aliases = BTreeMap::from([(
+        "img_placeholder_2".to_string(),
+        Alias {
+            res_type: "drawable".to_string(),
+            target: "img_placeholder".to_string(),
+        },
+    )]);
+    let source = aliases_source(&aliases);
+    assert!(source.contains(
+        r#"<item name="img_placeholder_2" type="drawable">@drawable/img_placeholder</item>"#
+    ));
+    assert_eq!(parse_aliases(&source), aliases);
+}
@@ -0,0 +1,43 @@
+use image::codecs::avif::AvifEncoder;
+use image::io::Reader as ImageReader;
+use image::ColorType;
+
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::Path;
+
+use super::error::AppError;
+
+/// Encoding speed from 0 (the slowest) to 10 (the fastest). The slowest speeds give only
+/// a little smaller files, but take too much time for the export of many images.
+const AVIF_ENCODING_SPEED: u8 = 6;
+
+/// Converts PNG image to AVIF. Returns the path to the AVIF image, or an error with a description.
+/// In case of successful conversion, the function will create an AVIF file next to the original PNG file.
+///
+/// # Arguments
+///
+/// * `file_path` - Path to PNG image
+/// * `quality` - Encoding quality in percents (from 0 to 100)
+pub fn image_to_avif(file_path: &String, quality: u8) -> Result<String, AppError> {
+    let image = ImageReader::open(file_path)
+        .map_err(|_| AppError::SourceNotFound(file_path.clone()))?
+        .with_guessed_format()
+        .map_err(|_| AppError::UnderlyingReader(file_path.clone()))?
+        .decode()
+        .map_err(|_| AppError::CannotDecode(file_path.clone()))?
+        .to_rgba8();
+
+    // Put avif-image in the location of the original image
+    let avif_image_path = Path::new(file_path)
+        .with_extension("avif")
+        .to_string_lossy()
+        .to_string();
+    let file = File::create(&avif_image_path)
+        .map_err(|e| AppError::WriteAvifTemporarySave(format!("{}", e)))?;
+    AvifEncoder::new_with_speed_quality(BufWriter::new(file), AVIF_ENCODING_SPEED, quality)
+        .write_image(&image, image.width(), image.height(), ColorType::Rgba8)
+        .map_err(|_| AppError::CannotEncode(file_path.clone()))?;
+
+    Ok(avif_image_path)
+}
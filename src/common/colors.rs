@@ -0,0 +1,52 @@
+use crate::models::figma::Color;
+
+/// Generate Android resources file with every color:
+/// ```xml
+/// <?xml version="1.0" encoding="utf-8"?>
+/// <resources>
+///     <color name="primary">#6200EE</color>
+/// </resources>
+/// ```
+///
+/// # Arguments
+///
+/// * `colors` - Resource names and values of the colors.
+pub fn colors_xml_source(colors: &[(String, Color)]) -> String {
+    let mut source = String::from("<?xml version=\"1.0\" encoding=\"utf-8\"?>\n<resources>\n");
+    for (res_name, color) in colors {
+        source.push_str(&format!(
+            "    <color name=\"{}\">{}</color>\n",
+            res_name,
+            to_hex_color(color)
+        ));
+    }
+    source.push_str("</resources>\n");
+    source
+}
+
+/// Returns the color in `#RRGGBB` form, or in `#AARRGGBB` form if the color is translucent.
+pub fn to_hex_color(color: &Color) -> String {
+    let [a, r, g, b] = to_argb(color);
+    if a == u8::MAX {
+        format!("#{:02X}{:02X}{:02X}", r, g, b)
+    } else {
+        format!("#{:02X}{:02X}{:02X}{:02X}", a, r, g, b)
+    }
+}
+
+/// Returns alpha, red, green and blue components of the color from 0 to 255.
+pub fn to_argb(color: &Color) -> [u8; 4] {
+    [color.a, color.r, color.g, color.b].map(|c| (c.clamp(0f32, 1f32) * 255f32).round() as u8)
+}
+
+#[test]
+fn test_to_hex_color() {
+    let color = Color {
+        r: 0.38431373,
+        g: 0f32,
+        b: 0.93333334,
+        a: 1f32,
+    };
+    assert_eq!(to_hex_color(&color), "#6200EE");
+    assert_eq!(to_hex_color(&Color { a: 0.5, ..color }), "#806200EE");
+}
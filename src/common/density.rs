@@ -0,0 +1,70 @@
+/// Density qualifiers, which don't depend on the scale of the image.
+const SPECIAL_QUALIFIERS: [&str; 3] = ["nodpi", "anydpi", "tvdpi"];
+
+/// Returns the android density qualifier for the scale multiplier, or `None` if there is no
+/// qualifier for the scale. Scales without the named qualifier, e.g. `2.5`, get the qualifier
+/// with their dpi value, if it is a whole number.
+///
+/// # Example
+/// ```ignore
+/// assert_eq!(density_qualifier(2.0), Some("xhdpi".to_string()));
+/// assert_eq!(density_qualifier(2.5), Some("400dpi".to_string()));
+/// assert_eq!(density_qualifier(1.337), None);
+/// ```
+pub fn density_qualifier(scale: f32) -> Option<String> {
+    let qualifier = match scale {
+        0.75 => "ldpi",
+        1.0 => "mdpi",
+        1.5 => "hdpi",
+        2.0 => "xhdpi",
+        3.0 => "xxhdpi",
+        4.0 => "xxxhdpi",
+        _ => {
+            // mdpi is the baseline density of 160 dpi
+            let dpi = scale * 160f32;
+            if dpi > 0f32 && (dpi - dpi.round()).abs() < 0.001f32 {
+                return Some(format!("{}dpi", dpi.round() as u32));
+            }
+            return None;
+        }
+    };
+    Some(qualifier.to_string())
+}
+
+/// Check if the name is an android density qualifier, e.g. `hdpi` or `400dpi`,
+/// so it can be used in the name of the res directory.
+pub fn is_density_qualifier(name: &str) -> bool {
+    if SPECIAL_QUALIFIERS.contains(&name) {
+        return true;
+    }
+    let scales = [0.75f32, 1.0, 1.5, 2.0, 3.0, 4.0];
+    if scales
+        .iter()
+        .any(|&scale| density_qualifier(scale).as_deref() == Some(name))
+    {
+        return true;
+    }
+    match name.strip_suffix("dpi") {
+        Some(dpi) => !dpi.is_empty() && dpi.chars().all(|c| c.is_ascii_digit()),
+        None => false,
+    }
+}
+
+#[test]
+fn test_density_qualifier() {
+    assert_eq!(density_qualifier(1.0), Some("mdpi".to_string()));
+    assert_eq!(density_qualifier(1.5), Some("hdpi".to_string()));
+    assert_eq!(density_qualifier(4.0), Some("xxxhdpi".to_string()));
+    assert_eq!(density_qualifier(2.5), Some("400dpi".to_string()));
+    assert_eq!(density_qualifier(1.337), None);
+}
+
+#[test]
+fn test_is_density_qualifier() {
+    assert!(is_density_qualifier("hdpi"));
+    assert!(is_density_qualifier("400dpi"));
+    assert!(is_density_qualifier("nodpi"));
+    assert!(!is_density_qualifier("1.5"));
+    assert!(!is_density_qualifier("dpi"));
+    assert!(!is_density_qualifier("1.5x"));
+}
@@ -0,0 +1,151 @@
+use image::imageops::FilterType;
+use image::io::Reader as ImageReader;
+use image::RgbaImage;
+
+use super::error::AppError;
+
+/// Returns the width and height of the raster image, reading only its header.
+///
+/// # Arguments
+///
+/// * `file_path` - Path to PNG or JPEG image
+pub fn image_dimensions(file_path: &String) -> Result<(u32, u32), AppError> {
+    image::image_dimensions(file_path)
+        .map_err(|e| AppError::CannotDownscaleImage(file_path.clone(), e.to_string()))
+}
+
+/// Downscales the raster image in place, so its larger side is `max_dimension`, keeping
+/// its aspect ratio and format. Returns the new width and height of the image.
+///
+/// # Arguments
+///
+/// * `file_path` - Path to PNG or JPEG image
+/// * `max_dimension` - Maximum width and height of the image in pixels
+pub fn downscale_image(file_path: &String, max_dimension: u32) -> Result<(u32, u32), AppError> {
+    let map_err =
+        |e: image::ImageError| AppError::CannotDownscaleImage(file_path.clone(), e.to_string());
+    let reader = ImageReader::open(file_path)
+        .map_err(|e| AppError::CannotDownscaleImage(file_path.clone(), e.to_string()))?
+        .with_guessed_format()
+        .map_err(|e| AppError::CannotDownscaleImage(file_path.clone(), e.to_string()))?;
+    let format = reader.format().ok_or_else(|| {
+        AppError::CannotDownscaleImage(file_path.clone(), "unknown format".to_string())
+    })?;
+    let image = reader.decode().map_err(map_err)?;
+    let (width, height) = fit_dimensions(image.width(), image.height(), max_dimension);
+    image
+        .resize_exact(width, height, FilterType::Lanczos3)
+        .save_with_format(file_path, format)
+        .map_err(map_err)?;
+    Ok((width, height))
+}
+
+/// Widths of the fully transparent borders of the image in pixels.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Insets {
+    pub left: u32,
+    pub top: u32,
+    pub right: u32,
+    pub bottom: u32,
+}
+
+impl Insets {
+    pub fn is_empty(&self) -> bool {
+        *self == Insets::default()
+    }
+
+    /// Insets as `[left, top, right, bottom]`, e.g. to compare them between the scales.
+    pub fn to_array(&self) -> [u32; 4] {
+        [self.left, self.top, self.right, self.bottom]
+    }
+}
+
+/// Crops the fully transparent borders of the raster image in place, keeping its format.
+/// Returns the cropped insets, which are empty if the image has no transparent borders
+/// or is fully transparent, in this case the image is left as is.
+///
+/// # Arguments
+///
+/// * `file_path` - Path to PNG or JPEG image
+pub fn trim_image(file_path: &String) -> Result<Insets, AppError> {
+    let map_err =
+        |e: image::ImageError| AppError::CannotTrimImage(file_path.clone(), e.to_string());
+    let reader = ImageReader::open(file_path)
+        .map_err(|e| AppError::CannotTrimImage(file_path.clone(), e.to_string()))?
+        .with_guessed_format()
+        .map_err(|e| AppError::CannotTrimImage(file_path.clone(), e.to_string()))?;
+    let format = reader.format().ok_or_else(|| {
+        AppError::CannotTrimImage(file_path.clone(), "unknown format".to_string())
+    })?;
+    let image = reader.decode().map_err(map_err)?;
+    let insets = match transparent_insets(&image.to_rgba8()) {
+        Some(insets) if !insets.is_empty() => insets,
+        _ => return Ok(Insets::default()),
+    };
+    let width = image.width() - insets.left - insets.right;
+    let height = image.height() - insets.top - insets.bottom;
+    image
+        .crop_imm(insets.left, insets.top, width, height)
+        .save_with_format(file_path, format)
+        .map_err(map_err)?;
+    Ok(insets)
+}
+
+/// Insets of the smallest area with all the visible pixels of the image.
+/// Returns `None` if the image is fully transparent.
+fn transparent_insets(image: &RgbaImage) -> Option<Insets> {
+    let (width, height) = image.dimensions();
+    let is_visible = |x: u32, y: u32| image.get_pixel(x, y)[3] > 0;
+    let row_is_visible = |y: u32| (0..width).any(|x| is_visible(x, y));
+    let column_is_visible = |x: u32| (0..height).any(|y| is_visible(x, y));
+    let top = (0..height).find(|&y| row_is_visible(y))?;
+    let bottom = (0..height).rev().find(|&y| row_is_visible(y))?;
+    let left = (0..width).find(|&x| column_is_visible(x))?;
+    let right = (0..width).rev().find(|&x| column_is_visible(x))?;
+    Some(Insets {
+        left,
+        top,
+        right: width - 1 - right,
+        bottom: height - 1 - bottom,
+    })
+}
+
+/// Width and height which fit into the square with the side of `max_dimension`,
+/// with the same aspect ratio. Every side is at least one pixel.
+fn fit_dimensions(width: u32, height: u32, max_dimension: u32) -> (u32, u32) {
+    let larger_side = width.max(height);
+    if larger_side <= max_dimension {
+        return (width, height);
+    }
+    let fit = |side: u32| {
+        let fitted =
+            (side as u64 * max_dimension as u64 + larger_side as u64 / 2) / larger_side as u64;
+        (fitted as u32).max(1)
+    };
+    (fit(width), fit(height))
+}
+
+#[test]
+fn test_fit_dimensions() {
+    assert_eq!(fit_dimensions(1000, 500, 2000), (1000, 500));
+    assert_eq!(fit_dimensions(8000, 2000, 4096), (4096, 1024));
+    assert_eq!(fit_dimensions(3000, 9000, 4096), (1365, 4096));
+    assert_eq!(fit_dimensions(10000, 1, 100), (100, 1));
+}
+
+#[test]
+fn test_transparent_insets() {
+    let mut image = RgbaImage::new(6, 4);
+    assert_eq!(transparent_insets(&image), None);
+    image.put_pixel(1, 1, image::Rgba([0, 0, 0, 255]));
+    image.put_pixel(3, 2, image::Rgba([0, 0, 0, 1]));
+    let expected = Insets {
+        left: 1,
+        top: 1,
+        right: 2,
+        bottom: 1,
+    };
+    assert_eq!(transparent_insets(&image), Some(expected));
+    let image = RgbaImage::from_pixel(2, 2, image::Rgba([255, 255, 255, 255]));
+    assert!(transparent_insets(&image).unwrap().is_empty());
+}
@@ -0,0 +1,71 @@
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use crate::common::error::AppError;
+use crate::common::report::format_bytes;
+
+/// Make sure that there is enough free space on the volumes of the paths. Required bytes of the
+/// paths on the same volume are added up. Volumes with unknown free space are not checked.
+///
+/// # Arguments
+///
+/// * `requirements` - Paths, which may not exist yet, and bytes required to be written there.
+pub fn check_disk_space(requirements: &[(String, u64)]) -> Result<(), AppError> {
+    // First path on the volume, required and available bytes by ids of the volumes
+    let mut volumes: BTreeMap<u64, (&String, u64, u64)> = BTreeMap::new();
+    for (path, bytes) in requirements {
+        if let Some((volume_id, available)) = volume_space(Path::new(path)) {
+            volumes.entry(volume_id).or_insert((path, 0, available)).1 += bytes;
+        }
+    }
+    match volumes
+        .into_values()
+        .find(|(_, required, available)| required > available)
+    {
+        Some((path, required, available)) => Err(AppError::InsufficientDiskSpace(
+            path.clone(),
+            format_bytes(required),
+            format_bytes(available),
+        )),
+        None => Ok(()),
+    }
+}
+
+/// Id of the volume with the path and free space on it available to the user.
+/// If the path doesn't exist yet, its closest existing parent is checked.
+#[cfg(unix)]
+fn volume_space(path: &Path) -> Option<(u64, u64)> {
+    use std::ffi::CString;
+    use std::os::unix::ffi::OsStrExt;
+    use std::os::unix::fs::MetadataExt;
+
+    let path = path
+        .ancestors()
+        .find(|p| p.exists() && !p.as_os_str().is_empty())?;
+    let volume_id = path.metadata().ok()?.dev();
+    let c_path = CString::new(path.as_os_str().as_bytes()).ok()?;
+    let mut stat: libc::statvfs = unsafe { std::mem::zeroed() };
+    // SAFETY: the path is a valid C string and `stat` is a valid buffer for the result
+    if unsafe { libc::statvfs(c_path.as_ptr(), &mut stat) } != 0 {
+        return None;
+    }
+    Some((volume_id, stat.f_bavail as u64 * stat.f_frsize as u64))
+}
+
+#[cfg(not(unix))]
+fn volume_space(_path: &Path) -> Option<(u64, u64)> {
+    None
+}
+
+#[cfg(unix)]
+#[test]
+fn test_check_disk_space() {
+    let temp_dir = std::env::temp_dir().to_string_lossy().to_string();
+    let missing_dir = format!("{}/fxa_missing_dir/drawable-xhdpi", temp_dir);
+    assert!(check_disk_space(&[(temp_dir.clone(), 1024), (missing_dir.clone(), 1024)]).is_ok());
+    let result = check_disk_space(&[(temp_dir, u64::MAX / 2), (missing_dir, u64::MAX / 2)]);
+    assert!(matches!(
+        result,
+        Err(AppError::InsufficientDiskSpace(_, _, _))
+    ));
+}
@@ -1,3 +1,4 @@
+use crate::common::report::ExportSummary;
 use crate::common::vdtool::error::VectorDrawableError;
 use reqwest::StatusCode;
 use thiserror::Error;
@@ -5,6 +6,25 @@ use thiserror::Error;
 /// Common struct for all errors in this app.
 #[derive(Debug, Error)]
 pub enum AppError {
+    ///
+    /// Token Errors
+    ///
+
+    #[error("Figma personal access token is not specified. Use `--token` option, `FXN_FIGMA_TOKEN` environment variable or `--token-file` option")]
+    TokenNotFound,
+
+    #[error("Can't read Figma personal access token from file {0}. Cause: {1}")]
+    CannotReadTokenFile(String, String),
+
+    #[error("Figma personal access token contains spaces or control characters, which can't be sent to Figma API. Make sure the token is copied completely and without line breaks")]
+    MalformedToken,
+
+    #[error("Can't read image names from file {0}. Cause: {1}")]
+    CannotReadNamesFile(String, String),
+
+    #[error("There are no image names in file {0}. Use `--all` flag to export all images")]
+    EmptyNamesFile(String),
+
     ///
     /// Fetcher Errors
     ///
@@ -12,6 +32,45 @@ pub enum AppError {
     #[error("Cannot read app config. Cause: {0}")]
     AppConfigOpen(String),
 
+    #[error("Found {} problems in app config {0}:{}", .1.len(), bulleted_list(.1))]
+    AppConfigInvalid(String, Vec<AppError>),
+
+    #[error("Unknown profile `{1}` in {0}. Available profiles: {2}")]
+    AppConfigUnknownProfile(String, String, String),
+
+    #[error("`{1}` must not be empty in {0}")]
+    AppConfigEmptyField(String, String),
+
+    #[error("`{1}` must contain at least one scale in {0}")]
+    AppConfigNoScales(String, String),
+
+    #[error("Scale `{1}: {2}` must be positive in {0}")]
+    AppConfigInvalidScale(String, String, f32),
+
+    #[error("Scale name `{1}` in `android.images.scales` is not an android density qualifier like `hdpi` or `400dpi`, so Android would ignore `drawable-{1}` directory, in {0}. Rename the scale or use `scaleNaming: density`")]
+    AppConfigInvalidScaleName(String, String),
+
+    #[error("`android.images.concurrency` must be at least 1 in {0}")]
+    AppConfigInvalidConcurrency(String),
+
+    #[error("`android.images.maxDimension` must be at least 1 in {0}")]
+    AppConfigInvalidMaxDimension(String),
+
+    #[error("`figma.requestsPerMinute` must be at least 1 in {0}")]
+    AppConfigInvalidRateLimit(String),
+
+    #[error("Invalid url `figma.baseUrl` in {0}, it must be an absolute http or https url, but it is `{1}`")]
+    AppConfigInvalidBaseUrl(String, String),
+
+    #[error("Invalid proxy url `http.proxy` in {0}. Cause: {1}")]
+    AppConfigInvalidProxy(String, String),
+
+    #[error("Invalid header `{1}` in `http.headers` in {0}. Cause: {2}")]
+    AppConfigInvalidHeader(String, String, String),
+
+    #[error("`http.{1}` must be at least 1 second in {0}")]
+    AppConfigInvalidTimeout(String, String),
+
     #[error("Cannot parse app config. Cause: {0}")]
     AppConfigParse(#[from] serde_yaml::Error),
 
@@ -30,6 +89,78 @@ pub enum AppError {
     )]
     AppConfigInvalidMainResImages(String),
 
+    #[error(
+        "To export colors, you must specify `android.colors.mainRes` or `android.mainRes` in {0}"
+    )]
+    AppConfigInvalidMainResColors(String),
+
+    #[error("Output directory {1} from {0} can't be created, because its parent directory doesn't exist. Relative paths are resolved against the directory of the config")]
+    AppConfigOutputParentMissing(String, String),
+
+    #[error("To export images for iOS, you must specify `ios.xcassets` in {0}")]
+    AppConfigMissingIos(String),
+
+    #[error(
+        "Unsupported iOS image scale `{1}` in {0}. Asset catalogs support only scales 1, 2 and 3"
+    )]
+    AppConfigInvalidIosScale(String, f32),
+
+    #[error("Asset catalogs don't support webp and avif images, use `png`, `jpeg` or `svg` for `ios.images.format` in {0}")]
+    AppConfigInvalidIosFormat(String),
+
+    #[error("To export images for web, you must specify `web.assetsDir` in {0}")]
+    AppConfigMissingWeb(String),
+
+    #[error("Browsers don't support avif images everywhere yet, use `png`, `webp`, `jpeg` or `svg` for `web.images.format` in {0}")]
+    AppConfigInvalidWebFormat(String),
+
+    #[error("PNG optimization level `android.images.pngOptions.level` must be from 0 to 6 in {0}")]
+    AppConfigInvalidPngLevel(String),
+
+    #[error(
+        "`android.images.{1}` must contain only lowercase letters, digits and underscores in {0}"
+    )]
+    AppConfigInvalidResNameAffix(String, String),
+
+    #[error("JPEG quality `{1}.jpegOptions.quality` must be from 1 to 100 in {0}")]
+    AppConfigInvalidJpegQuality(String, String),
+
+    #[error("WEBP quality `{1}.webpOptions.quality` must be from 0 to 100 in {0}, but it is {2}")]
+    AppConfigInvalidWebpQuality(String, String, f32),
+
+    #[error("AVIF quality `android.images.avifOptions.quality` must be from 0 to 100 in {0}")]
+    AppConfigInvalidAvifQuality(String),
+
+    #[error("Locale `{1}` in `common.images.figmaLocaleFrameNames` must be an android locale qualifier like `de` or `pt-rBR` in {0}")]
+    AppConfigInvalidLocale(String, String),
+
+    #[error("Adaptive icon name `android.images.adaptiveIcon.name` must be a valid resource name in {0}")]
+    AppConfigInvalidAdaptiveIconName(String),
+
+    #[error("Tint `android.images.svgOptions.tint` must be a hex color like `#1C1B1F` in {0}, but it is `{1}`")]
+    AppConfigInvalidSvgTint(String, String),
+
+    #[error("Scale `{1}` in `android.images.svgOptions.rasterFallback` has no android density qualifier like `xhdpi` or `400dpi` in {0}")]
+    AppConfigInvalidRasterFallback(String, f32),
+
+    #[error("This build of fxa doesn't support avif images, build it with `avif` feature or change `android.images.format` in {0}")]
+    AppConfigAvifNotSupported(String),
+
+    #[error(
+        "Scale {2} of image {1} in `android.images.scaleOverrides` must be one of `android.images.scales` in {0}"
+    )]
+    AppConfigInvalidScaleOverride(String, String, f32),
+
+    #[error(
+        "To generate Kotlin object with images, you must specify `android.images.compose.packageName` and `android.images.compose.outputPath` in {0}"
+    )]
+    AppConfigInvalidCompose(String),
+
+    #[error(
+        "To generate Kotlin object with colors, you must specify `android.colors.compose.packageName` and `android.colors.compose.outputPath` in {0}"
+    )]
+    AppConfigInvalidColorsCompose(String),
+
     #[error("Cannot parse json response from Figma API ({0}).")]
     FetchDomResponseParsing(String),
 
@@ -42,6 +173,9 @@ pub enum AppError {
     #[error("Can't save downloaded image into temp directory. Maybe something is wrong with the app permissions?")]
     GetImageTemporarySave,
 
+    #[error("Image {0} is downloaded incompletely: {2} of {1} bytes. Try to export it again")]
+    IncompleteDownload(String, u64, u64),
+
     #[error(
         "Can't create temporary dirictory. Maybe something is wrong with the app permissions?"
     )]
@@ -50,9 +184,15 @@ pub enum AppError {
     #[error("Can't access remote source {0}, {1}")]
     RequestHttpStatus(String, StatusCode),
 
+    #[error("Request to {0} has timed out. Check your internet connection or increase `http.timeoutSecs` and `http.downloadTimeoutSecs` in the config")]
+    RequestTimeout(String),
+
     #[error("Invalid Figma personal access token: {0}")]
     RequestUnauthorized(StatusCode),
 
+    #[error("Figma doesn't accept the token: {0}. Make sure the token is copied completely and hasn't expired, or create a new personal access token with read access to files: https://www.figma.com/developers/api#access-tokens. If it is an OAuth access token, set `figma.authType: oauth` in the config")]
+    InvalidToken(StatusCode),
+
     #[error(
         "Can't access remote source: {0}. Check your internet connection, VPN settings and make sure the \
     address is reachable through your network."
@@ -65,11 +205,38 @@ pub enum AppError {
     #[error("Can't save to cache")]
     SaveToCache,
 
+    #[error("Not enough free space for the export on the volume with {0}: about {1} is needed, but only {2} is available. Free some space, or move `common.tempDir` to another volume")]
+    InsufficientDiskSpace(String, String, String),
+
+    #[error("Can't work offline, there is no result of the previous fetch {0}. Run the same command without `--offline` flag first")]
+    NoFetchedFile(String),
+
+    #[error("Can't work offline, the previous fetch {0} has been done with other Figma frames or pages in the config. Run the same command without `--offline` flag first")]
+    FetchedFileOutdated(String),
+
+    #[error("Can't find Figma file `{0}`. Check the file id in the config: it is the part of the file link after `/file/` or `/design/` and before the file name, e.g. `https://www.figma.com/file/<fileId>/Resources`")]
+    FileNotFound(String),
+
+    #[error("Figma file `{0}` exists, but the personal access token has no access to it. Ask the owner of the file to share it with your Figma account, or use the token of the account which has access")]
+    FileAccessDenied(String),
+
     #[error("Can't find find frame with name `{0}`. Make sure such a frame exists.")]
     FindDesiredFrame(String),
 
-    #[error("Desired frame `{0}` has no child frames. Add some pictures there :)")]
-    DesiredFrameIsEmpty(String),
+    #[error("Can't find node `{0}` in the Figma file. Make sure the link points to the file from the config or clear the cache with `fxa cleanup`")]
+    FindNode(String),
+
+    #[error("Link or id of the node `{0}` is invalid. Copy the link to the node in Figma, it must contain `node-id` parameter")]
+    InvalidNodeRef(String),
+
+    #[error("To export the node, specify exactly one name of the image after `--node` option")]
+    NodeNeedsOneName,
+
+    #[error("Frame `{0}` is found, but there are no images in it. Add some images there, or check that the frame name in the config is of the frame with the images, not of its parent")]
+    EmptyFrame(String),
+
+    #[error("Can't find any color styles in the Figma file. If they have been added recently, clear the cache with `fxa cleanup`")]
+    NoColorStyles,
 
     ///
     /// WebP Converter
@@ -87,19 +254,127 @@ pub enum AppError {
     #[error("Can't encode temporary image file {0} because the image has unsupported color model (8-bit RGB and RGBA are supported now)")]
     CannotEncode(String),
 
+    #[error("Can't encode temporary image file {0} to webp. Cause: {1}")]
+    CannotEncodeWebp(String, String),
+
     #[error("Can't write data to temporary webp image file. Cause: {0}")]
     WriteWebpTemporarySave(String),
 
+    #[error("Can't write data to temporary jpeg image file. Cause: {0}")]
+    WriteJpegTemporarySave(String),
+
+    #[cfg(feature = "avif")]
+    #[error("Can't write data to temporary avif image file. Cause: {0}")]
+    WriteAvifTemporarySave(String),
+
+    ///
+    /// PNG Optimizer
+    ///
+
+    #[error("Can't optimize temporary image file {0}. Cause: {1}")]
+    CannotOptimizePng(String, String),
+
+    #[error("Can't strip metadata from temporary image file {0}. Cause: {1}")]
+    CannotStripMetadata(String, String),
+
+    #[error("Can't downscale temporary image file {0}. Cause: {1}")]
+    CannotDownscaleImage(String, String),
+
+    #[error("Can't trim transparent borders of temporary image file {0}. Cause: {1}")]
+    CannotTrimImage(String, String),
+
+    #[error("Image {0} ({1}) is {2}x{3} px, which is larger than `android.images.maxDimension: {4}`. Export it with smaller scales or set `android.images.downscaleOversized: true`")]
+    ImageTooLarge(String, String, u32, u32, u32),
+
+    ///
+    /// SVG Optimizer
+    ///
+
+    #[error("Can't optimize temporary image file {0}. Cause: {1}")]
+    CannotOptimizeSvg(String, String),
+
+    #[error("Can't tint temporary image file {0}. Cause: {1}")]
+    CannotTintSvg(String, String),
+
     ///
     /// Export Common
     ///
 
+    #[error("Nothing matches pattern {0} in {1}")]
+    NothingMatchesPattern(String, String),
+
+    #[error("Images {1} have the same resource name {0}, rename them in Figma")]
+    ResNameCollision(String, String),
+
+    #[error("Resource name {1} of image {0} must start with a lowercase letter and contain only lowercase letters, digits and underscores")]
+    InvalidResName(String, String),
+
     #[error("Can't create res/drawable directory. Cause: {0}")]
     CannotCreateDrawableDir(String),
 
     #[error("Can't move image {0} from temporary directory to drawable directory. Cause: {1}")]
     CannotMoveToDrawableDir(String, String),
 
-    #[error("Can't convert svg to android vector drawable xml. Cause: {0}")]
-    CannotConvertToXml(#[from] VectorDrawableError),
+    #[error("Can't write colors file {0}. Cause: {1}")]
+    CannotWriteColorsFile(String, String),
+
+    #[error("Can't write generated Kotlin file {0}. Cause: {1}")]
+    CannotWriteKotlinFile(String, String),
+
+    #[error("Can't generate adaptive icon {0}, because its {1} layer `{2}` hasn't been exported")]
+    AdaptiveIconLayerMissing(String, String, String),
+
+    #[error("Can't write adaptive icon {0}. Cause: {1}")]
+    CannotWriteAdaptiveIcon(String, String),
+
+    #[error("Can't write aliases of duplicate images {0}. Cause: {1}")]
+    CannotWriteAliases(String, String),
+
+    #[error("Can't write report {0}. Cause: {1}")]
+    CannotWriteReport(String, String),
+
+    #[error("Can't write manifest {0}. Cause: {1}")]
+    CannotWriteManifest(String, String),
+
+    #[error("Can't run hook `{0}`. Cause: {1}")]
+    CannotRunHook(String, String),
+
+    #[error("Hook `{0}` has failed with exit code {1}")]
+    HookFailed(String, String),
+
+    #[error("Transform `{1}` has failed for image {0}. Output: {2}")]
+    TransformFailed(String, String, String),
+
+    #[error("Can't write transformed image {0}. Cause: {1}")]
+    CannotWriteTransformedImage(String, String),
+
+    /// The summary has the images exported before the stop.
+    #[error("The export has been stopped because of `--fail-fast` flag. Cause: {0}")]
+    ExportStopped(Box<AppError>, Box<ExportSummary>),
+
+    #[error("Can't write lock file {0}. Cause: {1}")]
+    CannotWriteLockFile(String, String),
+
+    #[error("Can't watch config file {0} for changes. Cause: {1}")]
+    CannotWatchConfig(String, String),
+
+    #[error("Can't write Contents.json of the asset catalog {0}. Cause: {1}")]
+    CannotWriteContentsJson(String, String),
+
+    #[error("Can't convert {0} to android vector drawable xml. Cause: {1}")]
+    CannotConvertToXml(String, VectorDrawableError),
+
+    #[error("Can't write files into directory {0}. Cause: {1}")]
+    CannotWriteToDir(String, String),
+
+    #[error("Can't encode a test image to WEBP, so images can't be exported in webp format")]
+    WebpEncoderUnavailable,
+}
+
+/// Put every error on its own line, so many errors can be shown at once.
+fn bulleted_list(errors: &[AppError]) -> String {
+    errors
+        .iter()
+        .map(|e| format!("\n{:indent$}- {}", "", e, indent = 13))
+        .collect()
 }
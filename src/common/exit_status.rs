@@ -0,0 +1,23 @@
+/// How the command has finished. The process exits with the corresponding code,
+/// so CI can tell whether all resources have been exported.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExitStatus {
+    /// Every requested resource has been exported or is up to date.
+    Success,
+    /// Some resources are missing in Figma or have failed to export,
+    /// or the temporary directory can't be deleted.
+    PartialFailure,
+    /// Nothing has been exported because of the invalid config, token or Figma file,
+    /// or the config file can't be created.
+    ConfigError,
+}
+
+impl ExitStatus {
+    pub fn code(&self) -> i32 {
+        match self {
+            ExitStatus::Success => 0,
+            ExitStatus::PartialFailure => 1,
+            ExitStatus::ConfigError => 2,
+        }
+    }
+}
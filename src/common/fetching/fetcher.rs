@@ -1,72 +1,349 @@
-use std::collections::HashMap;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::fs::File;
+use std::io::{BufReader, BufWriter};
 
-use crate::api::figma::{FigmaApi, FIGMA_FILES_ENDPOINT};
-use crate::models::figma::Frame;
+use crate::api::figma::{FigmaApi, FigmaGetFileResponse, RetryPolicy};
+use crate::models::config::Platform;
+use crate::models::figma::{Frame, COMPONENT_SET_TYPE};
 use crate::models::{config::AppConfig, figma::Document};
 
 use crate::common::error::AppError;
+use crate::common::fetching::validation::validate_config;
 use crate::common::fetching::view::View;
-use crate::common::renderer::Renderer;
+use crate::common::fileutils::{create_temp_dir, TEMP_DIR_PATH};
+use crate::common::renderer::Render;
 
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
 pub struct FetcherEntry {
     pub app_config: AppConfig,
-    pub document: Document,
-    pub from_cache: bool,
+    /// Names and ids of the images, icons or color styles, depending on [FetcherTarget].
     pub image_names_to_ids: HashMap<String, String>,
+    /// Names and ids of the dark variants of images, if there is a frame with them.
+    pub dark_image_names_to_ids: Option<HashMap<String, String>>,
+    /// Names and ids of the localized variants of images by locales, for Android images only.
+    pub locale_image_names_to_ids: BTreeMap<String, HashMap<String, String>>,
+    /// Ids of Figma files of the images from `common.images.figmaSources` by their names.
+    /// Other images are from the file of `figma.fileId`.
+    pub image_names_to_file_ids: HashMap<String, String>,
+    /// Versions of Figma files from `common.images.figmaSources` by their ids, if they are known.
+    pub source_file_versions: HashMap<String, String>,
+    /// Version of the Figma file, if it is known.
+    pub file_version: Option<String>,
 }
 
 pub enum FetcherTarget {
-    Images,
+    Images(Platform),
+    /// The only image from the node with `node_id`, exported with `name` wherever it is.
+    Node {
+        platform: Platform,
+        name: String,
+        node_id: String,
+    },
     Icons,
+    Colors,
 }
 
-pub fn fetch(
-    api: &FigmaApi,
+/// Read the app config from YAML file at `yaml_config_path`, with the `profile` from its
+/// `profiles` section overlaid on it, if the profile is specified.
+pub fn read_config(
     yaml_config_path: &String,
-    fetcher_target: FetcherTarget,
-    renderer: &Renderer,
-) -> Result<FetcherEntry, AppError> {
+    profile: &Option<String>,
+    renderer: &dyn Render,
+) -> Result<AppConfig, AppError> {
     renderer.new_line();
-    renderer.render(View::ReadingConfig {
+    renderer.render(&View::ReadingConfig {
         path: yaml_config_path.clone(),
     });
-    let app_config = AppConfig::from_file(yaml_config_path)?;
-    validate_app_config(&app_config, &yaml_config_path)?;
-    renderer.render(View::ReceivedConfig {
+    AppConfig::from_file_with_profile(yaml_config_path, profile)
+}
+
+/// Validate the app config for the target and load Figma file from the config
+/// to find the frames with resources.
+///
+/// # Arguments
+///
+/// * `api` - Figma API, which gets the retry policy and http settings from the config.
+/// * `app_config` - App config, e.g. from [read_config].
+/// * `yaml_config_path` - Path the config is read from, to show it in the errors.
+/// * `fetcher_target` - Kind of exported resources.
+/// * `offline` - Take everything found in Figma file from the result of the previous fetch
+///   with the same target instead of loading the file. Every fetch saves its result for that.
+/// * `verify_token` - Check that Figma accepts the token before loading the file, with the
+///   settings of the API from the config. The token isn't checked in `offline` mode.
+/// * `renderer` - Renderer to show the progress with.
+pub fn fetch(
+    api: &mut FigmaApi,
+    app_config: AppConfig,
+    yaml_config_path: &String,
+    fetcher_target: FetcherTarget,
+    offline: bool,
+    verify_token: bool,
+    renderer: &dyn Render,
+) -> Result<FetcherEntry, AppError> {
+    let mut errors = validate_config(&app_config, yaml_config_path, &fetcher_target);
+    match errors.len() {
+        0 => (),
+        1 => return Err(errors.remove(0)),
+        _ => return Err(AppError::AppConfigInvalid(yaml_config_path.clone(), errors)),
+    }
+    // Images are downloaded even in offline mode, so the API gets the settings anyway
+    api.set_retry_policy(RetryPolicy::from(&app_config.figma.retry));
+    api.set_http_config(&app_config.http);
+    api.set_rate_limit(app_config.figma.requests_per_minute);
+    api.set_base_url(&app_config.figma.base_url);
+    api.set_auth_type(app_config.figma.auth_type)?;
+    let fetched_path = fetched_file_name(&app_config, &fetcher_target);
+    renderer.render(&View::ReceivedConfig {
         path: yaml_config_path.clone(),
     });
+    if offline {
+        return load_fetched(app_config, &fetcher_target, &fetched_path, renderer);
+    }
+    if verify_token {
+        renderer.render(&View::CheckingToken);
+        api.verify_token(renderer)?;
+    }
 
-    let document_url = format!("{}{}", FIGMA_FILES_ENDPOINT, &app_config.figma.file_id);
-    renderer.render(View::FetchingDom {
+    let document_url = api.file_url(&app_config.figma.file_id);
+    renderer.render(&View::FetchingDom {
         url: document_url.clone(),
     });
-    let (document, from_cache) = fetch_dom(&api, &app_config)?;
-    renderer.render(View::DomFetched {
+    // The version is requested every time, the cached file may be outdated
+    let file_version = api.verify_file(&app_config.figma.file_id, renderer)?;
+    let (file, from_cache) = fetch_dom(api, &app_config, &file_version, renderer)?;
+    renderer.render(&View::DomFetched {
         url: document_url.clone(),
         from_cache,
     });
 
-    renderer.render(View::ProcessingDom);
-    let desired_frame_name = match fetcher_target {
-        FetcherTarget::Images => &app_config.common.images.figma_frame_name,
-        FetcherTarget::Icons => &app_config.common.icons.figma_frame_name,
+    renderer.render(&View::ProcessingDom);
+    let mut names_to_ids = match &fetcher_target {
+        FetcherTarget::Images(_) => find_images_frames(
+            &file.document,
+            &app_config,
+            &app_config.common.images.figma_frame_names,
+            renderer,
+        )?,
+        FetcherTarget::Node { name, node_id, .. } => {
+            if !contains_node(&file.document, node_id) {
+                return Err(AppError::FindNode(node_id.clone()));
+            }
+            HashMap::from([(name.clone(), node_id.clone())])
+        }
+        FetcherTarget::Icons => find_images_frames(
+            &file.document,
+            &app_config,
+            std::slice::from_ref(&app_config.common.icons.figma_frame_name),
+            renderer,
+        )?,
+        FetcherTarget::Colors => {
+            let names_to_ids = find_color_styles(&file)?;
+            renderer.render(&View::FoundColorStyles {
+                count: names_to_ids.len(),
+            });
+            names_to_ids
+        }
     };
-    let names_to_ids = find_images_frame(&document, &app_config, desired_frame_name)?;
 
-    renderer.render(View::FoundImages {
-        frame_name: desired_frame_name.clone(),
-    });
-    Ok(FetcherEntry {
+    let dark_frame_name = match fetcher_target {
+        FetcherTarget::Images(_) => &app_config.common.images.figma_dark_frame_name,
+        FetcherTarget::Node { .. } | FetcherTarget::Icons | FetcherTarget::Colors => &None,
+    };
+    let dark_names_to_ids = match dark_frame_name {
+        Some(dark_frame_name) => {
+            let dark_names_to_ids =
+                find_images_frame(&file.document, &app_config, dark_frame_name)?;
+            renderer.render(&View::FoundImages {
+                frame_name: dark_frame_name.clone(),
+            });
+            Some(dark_names_to_ids)
+        }
+        None => None,
+    };
+
+    let mut locale_names_to_ids = BTreeMap::new();
+    if let FetcherTarget::Images(Platform::Android) = fetcher_target {
+        for (locale, frame_name) in &app_config.common.images.figma_locale_frame_names {
+            let names_to_ids = find_images_frame(&file.document, &app_config, frame_name)?;
+            renderer.render(&View::FoundImages {
+                frame_name: frame_name.clone(),
+            });
+            locale_names_to_ids.insert(locale.clone(), names_to_ids);
+        }
+    }
+
+    let mut names_to_file_ids = HashMap::new();
+    let mut source_file_versions = HashMap::new();
+    if let FetcherTarget::Images(_) = fetcher_target {
+        for source in &app_config.common.images.figma_sources {
+            let source_url = api.file_url(&source.file_id);
+            renderer.render(&View::FetchingDom {
+                url: source_url.clone(),
+            });
+            let source_version = api.verify_file(&source.file_id, renderer)?;
+            let (source_file, from_cache) =
+                api.get_file(&source.file_id, &source_version, renderer)?;
+            renderer.render(&View::DomFetched {
+                url: source_url,
+                from_cache,
+            });
+            let source_names_to_ids =
+                find_images_frame(&source_file.document, &app_config, &source.frame_name)?;
+            renderer.render(&View::FoundImages {
+                frame_name: source.frame_name.clone(),
+            });
+            let mut source_names = source_names_to_ids.keys().collect::<Vec<&String>>();
+            source_names.sort();
+            for name in source_names {
+                if names_to_ids.contains_key(name) {
+                    renderer.render_line(&View::DuplicateImageInSource {
+                        name: name.clone(),
+                        file_id: source.file_id.clone(),
+                        frame_name: source.frame_name.clone(),
+                    });
+                    continue;
+                }
+                names_to_ids.insert(name.clone(), source_names_to_ids[name].clone());
+                names_to_file_ids.insert(name.clone(), source.file_id.clone());
+            }
+            if let Some(version) = source_version.or(source_file.version) {
+                source_file_versions.insert(source.file_id.clone(), version);
+            }
+        }
+    }
+
+    let fetcher_entry = FetcherEntry {
         app_config,
-        document,
-        from_cache,
         image_names_to_ids: names_to_ids,
-    })
+        dark_image_names_to_ids: dark_names_to_ids,
+        locale_image_names_to_ids: locale_names_to_ids,
+        image_names_to_file_ids: names_to_file_ids,
+        source_file_versions,
+        file_version: file_version.or(file.version),
+    };
+    // Like the cache of Figma file, it is only an optimization, so it may fail silently
+    save_fetched(&fetcher_entry, &fetched_path).unwrap_or_default();
+    Ok(fetcher_entry)
 }
 
-fn fetch_dom(api: &FigmaApi, app_config: &AppConfig) -> Result<(Document, bool), AppError> {
+/// Path of the saved result of the fetch, different for every Figma file and target.
+fn fetched_file_name(app_config: &AppConfig, fetcher_target: &FetcherTarget) -> String {
+    let platform_name = |platform: &Platform| match platform {
+        Platform::Android => "android",
+        Platform::Ios => "ios",
+        Platform::Web => "web",
+    };
+    let target_name = match fetcher_target {
+        FetcherTarget::Images(platform) => format!("images_{}", platform_name(platform)),
+        FetcherTarget::Node {
+            platform, node_id, ..
+        } => format!(
+            "node_{}_{}",
+            platform_name(platform),
+            node_id.replace(':', "-")
+        ),
+        FetcherTarget::Icons => "icons".to_string(),
+        FetcherTarget::Colors => "colors".to_string(),
+    };
+    format!(
+        "{}/fetched_{}_{}.json",
+        TEMP_DIR_PATH, &app_config.figma.file_id, target_name
+    )
+}
+
+/// Settings of the config, which affect what is found in Figma file.
+fn figma_settings(app_config: &AppConfig) -> serde_json::Value {
+    let images = &app_config.common.images;
+    serde_json::json!([
+        &app_config.figma.page_name,
+        &images.figma_frame_names,
+        &images.figma_dark_frame_name,
+        &images.figma_locale_frame_names,
+        &images.figma_sources,
+        &images.expand_variants,
+        &app_config.common.icons.figma_frame_name,
+    ])
+}
+
+fn save_fetched(fetcher_entry: &FetcherEntry, path: &String) -> Result<(), std::io::Error> {
+    create_temp_dir()?;
+    let file = File::create(path)?;
+    serde_json::to_writer(BufWriter::new(file), fetcher_entry)?;
+    Ok(())
+}
+
+/// Load the result of the previous fetch, which has been done with the same [figma_settings],
+/// and replace the config in it with the current one.
+fn load_fetched(
+    app_config: AppConfig,
+    fetcher_target: &FetcherTarget,
+    path: &String,
+    renderer: &dyn Render,
+) -> Result<FetcherEntry, AppError> {
+    let fetched = File::open(path)
+        .ok()
+        .and_then(|file| serde_json::from_reader::<_, FetcherEntry>(BufReader::new(file)).ok())
+        .ok_or_else(|| AppError::NoFetchedFile(path.clone()))?;
+    if figma_settings(&fetched.app_config) != figma_settings(&app_config) {
+        return Err(AppError::FetchedFileOutdated(path.clone()));
+    }
+    let mut fetcher_entry = FetcherEntry {
+        app_config,
+        ..fetched
+    };
+    // The same node could have been exported with another name
+    if let FetcherTarget::Node { name, node_id, .. } = fetcher_target {
+        fetcher_entry.image_names_to_ids = HashMap::from([(name.clone(), node_id.clone())]);
+    }
+    renderer.render(&View::LoadedFetched { path: path.clone() });
+    Ok(fetcher_entry)
+}
+
+fn fetch_dom(
+    api: &FigmaApi,
+    app_config: &AppConfig,
+    file_version: &Option<String>,
+    renderer: &dyn Render,
+) -> Result<(FigmaGetFileResponse, bool), AppError> {
     let file_id = &app_config.figma.file_id;
-    api.get_document(&file_id)
+    api.get_file(file_id, file_version, renderer)
+}
+
+/// Merge images from all the frames. If there are images with the same name in several frames,
+/// the image from the first of them is used.
+fn find_images_frames(
+    document: &Document,
+    app_config: &AppConfig,
+    desired_frame_names: &[String],
+    renderer: &dyn Render,
+) -> Result<HashMap<String, String>, AppError> {
+    let mut names_to_ids: HashMap<String, String> = HashMap::new();
+    // Name of the frame where every image has been found
+    let mut names_to_frames: HashMap<String, &String> = HashMap::new();
+    for frame_name in desired_frame_names {
+        let frame_names_to_ids = find_images_frame(document, app_config, frame_name)?;
+        renderer.render(&View::FoundImages {
+            frame_name: frame_name.clone(),
+        });
+        let mut frame_names = frame_names_to_ids.keys().cloned().collect::<Vec<String>>();
+        frame_names.sort();
+        for name in frame_names {
+            match names_to_frames.get(&name) {
+                Some(&first_frame_name) => renderer.render_line(&View::DuplicateImage {
+                    name,
+                    frame_name: frame_name.clone(),
+                    first_frame_name: first_frame_name.clone(),
+                }),
+                None => {
+                    names_to_ids.insert(name.clone(), frame_names_to_ids[&name].clone());
+                    names_to_frames.insert(name, frame_name);
+                }
+            }
+        }
+    }
+    Ok(names_to_ids)
 }
 
 fn find_images_frame(
@@ -88,9 +365,9 @@ fn find_images_frame(
         .find(|frame| &frame.name == desired_frame_name);
 
     if let Some(frame) = frame {
-        let names_to_ids = collect_names_to_ids(&frame);
+        let names_to_ids = collect_names_to_ids(frame, app_config.common.images.expand_variants);
         if names_to_ids.is_empty() {
-            Err(AppError::DesiredFrameIsEmpty(desired_frame_name.clone()))
+            Err(AppError::EmptyFrame(desired_frame_name.clone()))
         } else {
             Ok(names_to_ids)
         }
@@ -99,37 +376,122 @@ fn find_images_frame(
     }
 }
 
-fn collect_names_to_ids(frame: &Frame) -> HashMap<String, String> {
+fn contains_node(document: &Document, node_id: &String) -> bool {
+    fn frame_contains_node(frame: &Frame, node_id: &String) -> bool {
+        &frame.id == node_id
+            || frame
+                .children
+                .iter()
+                .flatten()
+                .any(|child| frame_contains_node(child, node_id))
+    }
+    document.children.iter().any(|canvas| {
+        &canvas.id == node_id
+            || canvas
+                .children
+                .iter()
+                .any(|frame| frame_contains_node(frame, node_id))
+    })
+}
+
+/// Find names and ids of the color styles of the file.
+fn find_color_styles(file: &FigmaGetFileResponse) -> Result<HashMap<String, String>, AppError> {
+    let names_to_ids = file
+        .styles
+        .iter()
+        .filter(|(_, style)| style.style_type == "FILL")
+        .map(|(id, style)| (style.name.clone(), id.clone()))
+        .collect::<HashMap<String, String>>();
+    if names_to_ids.is_empty() {
+        Err(AppError::NoColorStyles)
+    } else {
+        Ok(names_to_ids)
+    }
+}
+
+/// Names and ids of the images in the frame. With `expand_variants`, every variant of
+/// the component sets is a separate image instead of the component set itself.
+fn collect_names_to_ids(frame: &Frame, expand_variants: bool) -> HashMap<String, String> {
     let mut hash_map: HashMap<String, String> = HashMap::new();
     if let Some(children) = &frame.children {
         for frame in children {
-            hash_map.insert(frame.name.clone(), frame.id.clone());
+            match (&frame.children, expand_variants) {
+                (Some(variants), true) if frame.node_type == COMPONENT_SET_TYPE => {
+                    for variant in variants {
+                        hash_map.insert(variant.variant_name(&frame.name), variant.id.clone());
+                    }
+                }
+                _ => {
+                    hash_map.insert(frame.name.clone(), frame.id.clone());
+                }
+            }
         }
     }
     hash_map
 }
 
-fn validate_app_config(app_config: &AppConfig, yaml_config_path: &String) -> Result<(), AppError> {
-    let common_main_res = app_config.android.main_res.clone();
-    let images_main_res = app_config.android.images.main_res.clone();
-    let icons_main_res = app_config.android.icons.main_res.clone();
-
-    match (common_main_res, images_main_res, icons_main_res) {
-        // There are no mainRes
-        (None, None, None) => Err(AppError::AppConfigInvalidMainResCommon(
-            yaml_config_path.clone(),
-        )),
+#[test]
+fn test_empty_frame_is_reported() {
+    let frame = |name: &str, children: Option<Vec<Frame>>| Frame {
+        id: format!("id_{}", name),
+        name: name.to_string(),
+        node_type: "FRAME".to_string(),
+        children,
+    };
+    let document = Document {
+        id: "0:0".to_string(),
+        name: "Document".to_string(),
+        children: vec![crate::models::figma::Canvas {
+            id: "0:1".to_string(),
+            name: "Page".to_string(),
+            children: vec![
+                frame("Images", Some(vec![frame("img_logo", None)])),
+                frame("Icons", Some(vec![])),
+            ],
+        }],
+    };
+    let app_config: AppConfig = serde_yaml::from_str("figma:\n  fileId: abc\n").unwrap();
 
-        // There is a mainRes for images, but not for icons
-        (None, Some(_), None) => Err(AppError::AppConfigInvalidMainResIcons(
-            yaml_config_path.clone(),
-        )),
+    let names_to_ids = find_images_frame(&document, &app_config, &"Images".to_string()).unwrap();
+    assert_eq!(names_to_ids["img_logo"], "id_img_logo".to_string());
+    let result = find_images_frame(&document, &app_config, &"Icons".to_string());
+    assert!(matches!(result, Err(AppError::EmptyFrame(name)) if name == "Icons"));
+    let result = find_images_frame(&document, &app_config, &"Banners".to_string());
+    assert!(matches!(result, Err(AppError::FindDesiredFrame(_))));
+}
 
-        // There is a mainRes for icons, but not for images
-        (None, None, Some(_)) => Err(AppError::AppConfigInvalidMainResImages(
-            yaml_config_path.clone(),
-        )),
+#[test]
+fn test_component_set_variants_are_expanded() {
+    let node = |name: &str, node_type: &str, children: Option<Vec<Frame>>| Frame {
+        id: format!("id_{}", name),
+        name: name.to_string(),
+        node_type: node_type.to_string(),
+        children,
+    };
+    let frame = node(
+        "Images",
+        "FRAME",
+        Some(vec![
+            node("img_logo", "COMPONENT", None),
+            node(
+                "ic_button",
+                COMPONENT_SET_TYPE,
+                Some(vec![
+                    node("state=default, size=large", "COMPONENT", None),
+                    node("state=pressed, size=large", "COMPONENT", None),
+                ]),
+            ),
+        ]),
+    );
 
-        _ => Ok(()),
-    }
+    let names_to_ids = collect_names_to_ids(&frame, false);
+    assert_eq!(names_to_ids.len(), 2);
+    assert!(names_to_ids.contains_key("ic_button"));
+    let names_to_ids = collect_names_to_ids(&frame, true);
+    assert_eq!(names_to_ids.len(), 3);
+    assert_eq!(
+        names_to_ids["ic_button_pressed_large"],
+        "id_state=pressed, size=large".to_string()
+    );
+    assert!(names_to_ids.contains_key("img_logo"));
 }
@@ -1,4 +1,6 @@
 mod fetcher;
+mod validation;
 mod view;
-pub use fetcher::fetch;
 pub use fetcher::FetcherTarget;
+pub use fetcher::{fetch, read_config};
+pub use validation::validate_config;
@@ -0,0 +1,645 @@
+use crate::common::density::{density_qualifier, is_density_qualifier};
+use crate::common::error::AppError;
+use crate::common::fetching::FetcherTarget;
+use crate::common::http_client::TOKEN_HEADER;
+use crate::common::paths::canonical_parent;
+use crate::common::res_name::{is_valid_locale_qualifier, is_valid_res_name};
+use crate::common::svg::is_valid_tint;
+use crate::common::xcassets::ios_scale_name;
+use crate::models::config::{
+    AndroidImagesWebpConfig, AppConfig, ImageFormat, ImageSizing, ImagesLayout, Platform,
+    ScaleNaming,
+};
+use reqwest::header::{HeaderName, HeaderValue, AUTHORIZATION};
+use reqwest::{Proxy, Url};
+
+/// Check the whole app config for the target before any request to Figma API is made.
+/// Returns all found problems, so they can be fixed at once.
+pub fn validate_config(
+    app_config: &AppConfig,
+    yaml_config_path: &String,
+    fetcher_target: &FetcherTarget,
+) -> Vec<AppError> {
+    let mut errors = Vec::new();
+    validate_common_config(app_config, yaml_config_path, fetcher_target, &mut errors);
+    match fetcher_target {
+        FetcherTarget::Images(Platform::Ios)
+        | FetcherTarget::Node {
+            platform: Platform::Ios,
+            ..
+        } => validate_ios_config(app_config, yaml_config_path, &mut errors),
+        FetcherTarget::Images(Platform::Web)
+        | FetcherTarget::Node {
+            platform: Platform::Web,
+            ..
+        } => validate_web_config(app_config, yaml_config_path, &mut errors),
+        FetcherTarget::Colors => {
+            validate_android_colors_config(app_config, yaml_config_path, &mut errors)
+        }
+        _ => validate_android_config(app_config, yaml_config_path, &mut errors),
+    }
+    validate_output_dir(app_config, yaml_config_path, fetcher_target, &mut errors);
+    errors
+}
+
+/// Check that the directory with exported resources can be created, when the rest of config is valid.
+fn validate_output_dir(
+    app_config: &AppConfig,
+    yaml_config_path: &String,
+    fetcher_target: &FetcherTarget,
+    errors: &mut Vec<AppError>,
+) {
+    if !errors.is_empty() {
+        return;
+    }
+    let output_dir = match fetcher_target {
+        FetcherTarget::Images(Platform::Ios)
+        | FetcherTarget::Node {
+            platform: Platform::Ios,
+            ..
+        } => app_config.ios.as_ref().map(|ios| ios.xcassets.clone()),
+        FetcherTarget::Images(Platform::Web)
+        | FetcherTarget::Node {
+            platform: Platform::Web,
+            ..
+        } => app_config.web.as_ref().map(|web| web.assets_dir.clone()),
+        FetcherTarget::Images(Platform::Android)
+        | FetcherTarget::Node {
+            platform: Platform::Android,
+            ..
+        } => app_config.main_res_images(),
+        FetcherTarget::Icons => app_config.main_res_icons(),
+        FetcherTarget::Colors => app_config.main_res_colors(),
+    };
+    if let Some(output_dir) = output_dir {
+        if canonical_parent(&output_dir).is_none() {
+            errors.push(AppError::AppConfigOutputParentMissing(
+                yaml_config_path.clone(),
+                output_dir,
+            ));
+        }
+    }
+}
+
+fn validate_common_config(
+    app_config: &AppConfig,
+    yaml_config_path: &String,
+    fetcher_target: &FetcherTarget,
+    errors: &mut Vec<AppError>,
+) {
+    let empty_field =
+        |field: &str| AppError::AppConfigEmptyField(yaml_config_path.clone(), field.to_string());
+    if app_config.figma.file_id.trim().is_empty() {
+        errors.push(empty_field("figma.fileId"));
+    }
+    if app_config.figma.requests_per_minute == Some(0) {
+        errors.push(AppError::AppConfigInvalidRateLimit(
+            yaml_config_path.clone(),
+        ));
+    }
+    if let Some(base_url) = &app_config.figma.base_url {
+        let is_http_url = Url::parse(base_url)
+            .map(|url| matches!(url.scheme(), "http" | "https") && url.has_host())
+            .unwrap_or(false);
+        if !is_http_url {
+            errors.push(AppError::AppConfigInvalidBaseUrl(
+                yaml_config_path.clone(),
+                base_url.clone(),
+            ));
+        }
+    }
+    if let Some(proxy) = &app_config.http.proxy {
+        if let Err(e) = Proxy::all(proxy) {
+            errors.push(AppError::AppConfigInvalidProxy(
+                yaml_config_path.clone(),
+                e.to_string(),
+            ));
+        }
+    }
+    let mut header_names = app_config.http.headers.keys().collect::<Vec<&String>>();
+    header_names.sort();
+    for name in header_names {
+        let value = &app_config.http.headers[name];
+        // OAuth access tokens are sent in `Authorization` header
+        let is_token_header = [TOKEN_HEADER, AUTHORIZATION.as_str()]
+            .iter()
+            .any(|header| name.eq_ignore_ascii_case(header));
+        let cause = if is_token_header {
+            Some(
+                "the token can't be passed in the headers, use `--token` option instead"
+                    .to_string(),
+            )
+        } else {
+            HeaderName::try_from(name)
+                .err()
+                .map(|e| e.to_string())
+                .or_else(|| HeaderValue::try_from(value).err().map(|e| e.to_string()))
+        };
+        if let Some(cause) = cause {
+            errors.push(AppError::AppConfigInvalidHeader(
+                yaml_config_path.clone(),
+                name.clone(),
+                cause,
+            ));
+        }
+    }
+    let timeouts = [
+        ("timeoutSecs", app_config.http.timeout_secs),
+        ("downloadTimeoutSecs", app_config.http.download_timeout_secs),
+    ];
+    for (field, timeout_secs) in timeouts {
+        if timeout_secs == 0 {
+            errors.push(AppError::AppConfigInvalidTimeout(
+                yaml_config_path.clone(),
+                field.to_string(),
+            ));
+        }
+    }
+    match fetcher_target {
+        FetcherTarget::Images(_) => {
+            let images_config = &app_config.common.images;
+            let frame_names = &images_config.figma_frame_names;
+            if frame_names.is_empty() || frame_names.iter().any(|n| n.trim().is_empty()) {
+                errors.push(empty_field("common.images.figmaFrameName"));
+            }
+            if let Some(dark_frame_name) = &images_config.figma_dark_frame_name {
+                if dark_frame_name.trim().is_empty() {
+                    errors.push(empty_field("common.images.figmaDarkFrameName"));
+                }
+            }
+            for (index, source) in images_config.figma_sources.iter().enumerate() {
+                if source.file_id.trim().is_empty() {
+                    errors.push(empty_field(&format!(
+                        "common.images.figmaSources[{}].fileId",
+                        index
+                    )));
+                }
+                if source.frame_name.trim().is_empty() {
+                    errors.push(empty_field(&format!(
+                        "common.images.figmaSources[{}].frameName",
+                        index
+                    )));
+                }
+            }
+            for (index, transform) in app_config.hooks.transforms.iter().enumerate() {
+                if transform.pattern.trim().is_empty() {
+                    errors.push(empty_field(&format!("hooks.transforms[{}].match", index)));
+                }
+                if transform.command.trim().is_empty() {
+                    errors.push(empty_field(&format!("hooks.transforms[{}].command", index)));
+                }
+            }
+            for (locale, frame_name) in &images_config.figma_locale_frame_names {
+                if !is_valid_locale_qualifier(locale) {
+                    errors.push(AppError::AppConfigInvalidLocale(
+                        yaml_config_path.clone(),
+                        locale.clone(),
+                    ));
+                }
+                if frame_name.trim().is_empty() {
+                    errors.push(empty_field(&format!(
+                        "common.images.figmaLocaleFrameNames.{}",
+                        locale
+                    )));
+                }
+            }
+        }
+        FetcherTarget::Icons => {
+            if app_config.common.icons.figma_frame_name.trim().is_empty() {
+                errors.push(empty_field("common.icons.figmaFrameName"));
+            }
+        }
+        FetcherTarget::Node { .. } | FetcherTarget::Colors => (),
+    }
+}
+
+fn validate_android_config(
+    app_config: &AppConfig,
+    yaml_config_path: &String,
+    errors: &mut Vec<AppError>,
+) {
+    let images_config = &app_config.android.images;
+    if images_config.scales.is_empty() {
+        errors.push(AppError::AppConfigNoScales(
+            yaml_config_path.clone(),
+            "android.images.scales".to_string(),
+        ));
+    }
+    let mut scale_names = images_config.scales.keys().collect::<Vec<&String>>();
+    scale_names.sort();
+    for scale_name in scale_names {
+        let scale = images_config.scales[scale_name];
+        if scale <= 0.0 || scale.is_nan() {
+            errors.push(AppError::AppConfigInvalidScale(
+                yaml_config_path.clone(),
+                format!("android.images.scales.{}", scale_name),
+                scale,
+            ));
+        }
+        // Names of the scales are used as they are only in the names of res directories
+        let raw_dir_names = images_config.scale_naming == ScaleNaming::Raw
+            && images_config.layout == ImagesLayout::DrawableDirs
+            && images_config.sizing == ImageSizing::Density;
+        if raw_dir_names && !is_density_qualifier(scale_name) {
+            errors.push(AppError::AppConfigInvalidScaleName(
+                yaml_config_path.clone(),
+                scale_name.clone(),
+            ));
+        }
+    }
+    if images_config.concurrency == 0 {
+        errors.push(AppError::AppConfigInvalidConcurrency(
+            yaml_config_path.clone(),
+        ));
+    }
+    if images_config.max_dimension == Some(0) {
+        errors.push(AppError::AppConfigInvalidMaxDimension(
+            yaml_config_path.clone(),
+        ));
+    }
+    if images_config.png_options.level > 6 {
+        errors.push(AppError::AppConfigInvalidPngLevel(yaml_config_path.clone()));
+    }
+    validate_webp_quality(
+        &images_config.webp_options,
+        yaml_config_path,
+        "android.images",
+        errors,
+    );
+    if images_config.avif_options.quality > 100 {
+        errors.push(AppError::AppConfigInvalidAvifQuality(
+            yaml_config_path.clone(),
+        ));
+    }
+    if let Some(tint) = &images_config.svg_options.tint {
+        if !is_valid_tint(tint) {
+            errors.push(AppError::AppConfigInvalidSvgTint(
+                yaml_config_path.clone(),
+                tint.clone(),
+            ));
+        }
+    }
+    for &scale in &images_config.svg_options.raster_fallback {
+        if density_qualifier(scale).is_none() {
+            errors.push(AppError::AppConfigInvalidRasterFallback(
+                yaml_config_path.clone(),
+                scale,
+            ));
+        }
+    }
+    let mut formats = images_config.format_overrides.values();
+    let avif_requested = matches!(images_config.format, ImageFormat::Avif)
+        || formats.any(|f| matches!(f, ImageFormat::Avif));
+    if !cfg!(feature = "avif") && avif_requested {
+        errors.push(AppError::AppConfigAvifNotSupported(
+            yaml_config_path.clone(),
+        ));
+    }
+    if !(1..=100).contains(&images_config.jpeg_options.quality) {
+        errors.push(AppError::AppConfigInvalidJpegQuality(
+            yaml_config_path.clone(),
+            "android.images".to_string(),
+        ));
+    }
+    let affixes = [
+        ("resNamePrefix", &images_config.res_name_prefix),
+        ("resNameSuffix", &images_config.res_name_suffix),
+    ];
+    for (field, affix) in affixes {
+        // Validate the affix as a part of resource name
+        if !affix.is_empty() && !is_valid_res_name(&format!("a{}", affix)) {
+            errors.push(AppError::AppConfigInvalidResNameAffix(
+                yaml_config_path.clone(),
+                field.to_string(),
+            ));
+        }
+    }
+    let compose = &images_config.compose;
+    if compose.generate && (compose.package_name.is_none() || compose.output_path.is_none()) {
+        errors.push(AppError::AppConfigInvalidCompose(yaml_config_path.clone()));
+    }
+    if let Some(adaptive_icon) = &images_config.adaptive_icon {
+        if !is_valid_res_name(&adaptive_icon.name) {
+            errors.push(AppError::AppConfigInvalidAdaptiveIconName(
+                yaml_config_path.clone(),
+            ));
+        }
+        let layers = [
+            ("foreground", &adaptive_icon.foreground),
+            ("background", &adaptive_icon.background),
+        ];
+        for (field, name) in layers {
+            if name.trim().is_empty() {
+                errors.push(AppError::AppConfigEmptyField(
+                    yaml_config_path.clone(),
+                    format!("android.images.adaptiveIcon.{}", field),
+                ));
+            }
+        }
+    }
+    let mut image_names = images_config
+        .scale_overrides
+        .keys()
+        .collect::<Vec<&String>>();
+    image_names.sort();
+    for image_name in image_names {
+        let scales = &images_config.scale_overrides[image_name];
+        if let Some(&scale) = scales
+            .iter()
+            .find(|&s| !images_config.scales.values().any(|v| v == s))
+        {
+            errors.push(AppError::AppConfigInvalidScaleOverride(
+                yaml_config_path.clone(),
+                image_name.clone(),
+                scale,
+            ));
+        }
+    }
+
+    let common_main_res = &app_config.android.main_res;
+    let images_main_res = &app_config.android.images.main_res;
+    let icons_main_res = &app_config.android.icons.main_res;
+
+    match (common_main_res, images_main_res, icons_main_res) {
+        // There are no mainRes
+        (None, None, None) => errors.push(AppError::AppConfigInvalidMainResCommon(
+            yaml_config_path.clone(),
+        )),
+
+        // There is a mainRes for images, but not for icons
+        (None, Some(_), None) => errors.push(AppError::AppConfigInvalidMainResIcons(
+            yaml_config_path.clone(),
+        )),
+
+        // There is a mainRes for icons, but not for images
+        (None, None, Some(_)) => errors.push(AppError::AppConfigInvalidMainResImages(
+            yaml_config_path.clone(),
+        )),
+
+        _ => (),
+    }
+}
+
+/// Check the quality of WEBP images, which may be different for every scale.
+fn validate_webp_quality(
+    webp_options: &AndroidImagesWebpConfig,
+    yaml_config_path: &String,
+    section: &str,
+    errors: &mut Vec<AppError>,
+) {
+    let entries = match &webp_options.quality {
+        Some(quality) => quality.entries(),
+        None => return,
+    };
+    for (scale, quality) in entries {
+        if let Some(scale) = scale.filter(|&scale| scale <= 0.0 || scale.is_nan()) {
+            errors.push(AppError::AppConfigInvalidScale(
+                yaml_config_path.clone(),
+                format!("{}.webpOptions.quality", section),
+                scale,
+            ));
+        }
+        if !(0f32..=100f32).contains(&quality) {
+            errors.push(AppError::AppConfigInvalidWebpQuality(
+                yaml_config_path.clone(),
+                section.to_string(),
+                quality,
+            ));
+        }
+    }
+}
+
+fn validate_android_colors_config(
+    app_config: &AppConfig,
+    yaml_config_path: &String,
+    errors: &mut Vec<AppError>,
+) {
+    let colors_config = &app_config.android.colors;
+    if app_config.main_res_colors().is_none() {
+        errors.push(AppError::AppConfigInvalidMainResColors(
+            yaml_config_path.clone(),
+        ));
+    }
+    if colors_config.file_name.trim().is_empty() {
+        errors.push(AppError::AppConfigEmptyField(
+            yaml_config_path.clone(),
+            "android.colors.fileName".to_string(),
+        ));
+    }
+    let compose = &colors_config.compose;
+    if compose.generate && (compose.package_name.is_none() || compose.output_path.is_none()) {
+        errors.push(AppError::AppConfigInvalidColorsCompose(
+            yaml_config_path.clone(),
+        ));
+    }
+}
+
+fn validate_ios_config(
+    app_config: &AppConfig,
+    yaml_config_path: &String,
+    errors: &mut Vec<AppError>,
+) {
+    let ios = match &app_config.ios {
+        Some(ios) => ios,
+        None => {
+            errors.push(AppError::AppConfigMissingIos(yaml_config_path.clone()));
+            return;
+        }
+    };
+    if ios.xcassets.trim().is_empty() {
+        errors.push(AppError::AppConfigEmptyField(
+            yaml_config_path.clone(),
+            "ios.xcassets".to_string(),
+        ));
+    }
+    if let ImageFormat::Webp | ImageFormat::Avif = ios.images.format {
+        errors.push(AppError::AppConfigInvalidIosFormat(
+            yaml_config_path.clone(),
+        ));
+    }
+    if !(1..=100).contains(&ios.images.jpeg_options.quality) {
+        errors.push(AppError::AppConfigInvalidJpegQuality(
+            yaml_config_path.clone(),
+            "ios.images".to_string(),
+        ));
+    }
+    if ios.images.scales.is_empty() {
+        errors.push(AppError::AppConfigNoScales(
+            yaml_config_path.clone(),
+            "ios.images.scales".to_string(),
+        ));
+    }
+    for &scale in &ios.images.scales {
+        if ios_scale_name(scale).is_none() {
+            errors.push(AppError::AppConfigInvalidIosScale(
+                yaml_config_path.clone(),
+                scale,
+            ));
+        }
+    }
+}
+
+fn validate_web_config(
+    app_config: &AppConfig,
+    yaml_config_path: &String,
+    errors: &mut Vec<AppError>,
+) {
+    let web = match &app_config.web {
+        Some(web) => web,
+        None => {
+            errors.push(AppError::AppConfigMissingWeb(yaml_config_path.clone()));
+            return;
+        }
+    };
+    if web.assets_dir.trim().is_empty() {
+        errors.push(AppError::AppConfigEmptyField(
+            yaml_config_path.clone(),
+            "web.assetsDir".to_string(),
+        ));
+    }
+    if let ImageFormat::Avif = web.images.format {
+        errors.push(AppError::AppConfigInvalidWebFormat(
+            yaml_config_path.clone(),
+        ));
+    }
+    if !(1..=100).contains(&web.images.jpeg_options.quality) {
+        errors.push(AppError::AppConfigInvalidJpegQuality(
+            yaml_config_path.clone(),
+            "web.images".to_string(),
+        ));
+    }
+    validate_webp_quality(
+        &web.images.webp_options,
+        yaml_config_path,
+        "web.images",
+        errors,
+    );
+    if web.images.scales.is_empty() {
+        errors.push(AppError::AppConfigNoScales(
+            yaml_config_path.clone(),
+            "web.images.scales".to_string(),
+        ));
+    }
+    for &scale in &web.images.scales {
+        if scale <= 0.0 || scale.is_nan() {
+            errors.push(AppError::AppConfigInvalidScale(
+                yaml_config_path.clone(),
+                "web.images.scales".to_string(),
+                scale,
+            ));
+        }
+    }
+}
+
+#[test]
+fn test_all_errors_are_reported() {
+    let app_config: AppConfig = serde_yaml::from_str(
+        r#"
+figma:
+  fileId: ""
+http:
+  proxy: "http://[::1"
+common:
+  images:
+    figmaFrameName: Images
+  icons:
+    figmaFrameName: Icons
+android:
+  images:
+    scales:
+      mdpi: 0
+    webpOptions:
+      quality: 150
+    pngOptions:
+      level: 7
+    svgOptions:
+      rasterFallback: [2, 1.337]
+"#,
+    )
+    .unwrap();
+    let target = FetcherTarget::Images(Platform::Android);
+    let errors = validate_config(&app_config, &"config.yaml".to_string(), &target);
+    assert!(matches!(errors[0], AppError::AppConfigEmptyField(_, _)));
+    assert!(matches!(errors[1], AppError::AppConfigInvalidProxy(_, _)));
+    assert!(matches!(
+        errors[2],
+        AppError::AppConfigInvalidScale(_, _, _)
+    ));
+    assert!(matches!(errors[3], AppError::AppConfigInvalidPngLevel(_)));
+    assert!(matches!(
+        errors[4],
+        AppError::AppConfigInvalidWebpQuality(_, _, _)
+    ));
+    assert!(matches!(
+        errors[5],
+        AppError::AppConfigInvalidRasterFallback(_, _)
+    ));
+    assert!(matches!(
+        errors[6],
+        AppError::AppConfigInvalidMainResCommon(_)
+    ));
+    assert_eq!(errors.len(), 7);
+}
+
+#[test]
+fn test_token_headers_are_rejected() {
+    let app_config: AppConfig = serde_yaml::from_str(
+        r#"
+figma:
+  fileId: shPilWnVdJfo10YF12345
+http:
+  headers:
+    authorization: Bearer figu_aBcD1234
+    X-Figma-Token: figd_aBcD1234
+    X-Gateway-Key: key
+common:
+  images:
+    figmaFrameName: Images
+  icons:
+    figmaFrameName: Icons
+android:
+  mainRes: ./main/res
+"#,
+    )
+    .unwrap();
+    let target = FetcherTarget::Images(Platform::Android);
+    let errors = validate_config(&app_config, &"config.yaml".to_string(), &target);
+    let names = errors
+        .iter()
+        .filter_map(|e| match e {
+            AppError::AppConfigInvalidHeader(_, name, _) => Some(name.as_str()),
+            _ => None,
+        })
+        .collect::<Vec<&str>>();
+    assert_eq!(names, ["X-Figma-Token", "authorization"]);
+}
+
+#[test]
+fn test_web_config_is_validated() {
+    let app_config: AppConfig = serde_yaml::from_str(
+        r#"
+figma:
+  fileId: shPilWnVdJfo10YF12345
+common:
+  images:
+    figmaFrameName: Images
+  icons:
+    figmaFrameName: Icons
+android: {}
+web:
+  assetsDir: ./assets
+  images:
+    scales: [1, 0]
+    format: avif
+"#,
+    )
+    .unwrap();
+    let target = FetcherTarget::Images(Platform::Web);
+    let errors = validate_config(&app_config, &"config.yaml".to_string(), &target);
+    assert!(matches!(errors[0], AppError::AppConfigInvalidWebFormat(_)));
+    assert!(matches!(
+        errors[1],
+        AppError::AppConfigInvalidScale(_, _, _)
+    ));
+    assert_eq!(errors.len(), 2);
+}
@@ -3,15 +3,59 @@ use crossterm::style::Stylize;
 use crate::common::renderer::{Indentable, Renderable};
 
 pub enum View {
-    ReadingConfig { path: String },
-    ReceivedConfig { path: String },
-    FetchingDom { url: String },
-    DomFetched { url: String, from_cache: bool },
+    ReadingConfig {
+        path: String,
+    },
+    ReceivedConfig {
+        path: String,
+    },
+    CheckingToken,
+    FetchingDom {
+        url: String,
+    },
+    DomFetched {
+        url: String,
+        from_cache: bool,
+    },
     ProcessingDom,
-    FoundImages { frame_name: String },
+    /// Result of the previous fetch, used instead of Figma file with `--offline` flag.
+    LoadedFetched {
+        path: String,
+    },
+    FoundImages {
+        frame_name: String,
+    },
+    FoundColorStyles {
+        count: usize,
+    },
+    DuplicateImage {
+        name: String,
+        frame_name: String,
+        first_frame_name: String,
+    },
+    /// Image from `common.images.figmaSources` with the name, which has been found in another file.
+    DuplicateImageInSource {
+        name: String,
+        file_id: String,
+        frame_name: String,
+    },
 }
 
 impl Renderable for View {
+    fn is_important(&self) -> bool {
+        matches!(
+            self,
+            View::DuplicateImage { .. } | View::DuplicateImageInSource { .. }
+        )
+    }
+
+    fn is_warning(&self) -> bool {
+        matches!(
+            self,
+            View::DuplicateImage { .. } | View::DuplicateImageInSource { .. }
+        )
+    }
+
     fn render(&self) -> String {
         match self {
             View::ReadingConfig { path } => format!(
@@ -24,6 +68,9 @@ impl Renderable for View {
                 "Loaded".indent().bold().green(),
                 &path,
             ),
+            View::CheckingToken => {
+                format!("{} Figma access token", "Checking".indent().bold().cyan())
+            }
             View::FetchingDom { url } => format!(
                 "{} figma file nodes from {}",
                 "Fetching".indent().bold().cyan(),
@@ -49,11 +96,44 @@ impl Renderable for View {
                 "Processing".indent().bold().cyan(),
                 "figma file nodes..."
             ),
+            View::LoadedFetched { path } => format!(
+                "{} figma file nodes {} {}\n",
+                "Loaded".indent().bold().green(),
+                "offline".bold().white(),
+                &path,
+            ),
             View::FoundImages { frame_name } => format!(
                 "{} figma frame `{}` with images\n",
                 "Found".indent().bold().green(),
                 &frame_name,
             ),
+            View::FoundColorStyles { count } => format!(
+                "{} {} color styles\n",
+                "Found".indent().bold().green(),
+                count,
+            ),
+            View::DuplicateImageInSource {
+                name,
+                file_id,
+                frame_name,
+            } => format!(
+                "{} image `{}` from frame `{}` of file `{}` is ignored, because it has already been found in another file",
+                "Warning".indent().bold().yellow(),
+                &name,
+                &frame_name,
+                &file_id,
+            ),
+            View::DuplicateImage {
+                name,
+                frame_name,
+                first_frame_name,
+            } => format!(
+                "{} image `{}` from frame `{}` is ignored, because it is also in frame `{}`",
+                "Warning".indent().bold().yellow(),
+                &name,
+                &frame_name,
+                &first_frame_name,
+            ),
         }
     }
 }
@@ -1,7 +1,16 @@
-use std::{fs, path::Path};
+use std::fs::{File, OpenOptions};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::{env, fs, path::Path, process};
 
 pub const TEMP_DIR_PATH: &str = ".fxa";
 
+/// Environment variable with the directory for temporary files, overrides `common.tempDir`.
+pub const TEMP_DIR_ENV: &str = "FXN_TMPDIR";
+
+/// Directory for temporary files of the exported resources, see [set_temp_files_dir].
+static TEMP_FILES_DIR: Mutex<Option<String>> = Mutex::new(None);
+
 /// Create temporary directory `.fxn` at the root of the working directory
 /// (where the app was launched from).
 pub fn create_temp_dir() -> Result<(), std::io::Error> {
@@ -18,6 +27,169 @@ pub fn remove_temp_dir() -> Result<(), std::io::Error> {
     }
 }
 
+/// Put temporary files of the exported resources, e.g. downloaded and converted images,
+/// into `FXN_TMPDIR` directory, or into `temp_dir` from config if there is no such env variable.
+/// If neither is set, the files are put into `.fxa` directory, like the cache.
+pub fn set_temp_files_dir(temp_dir: &Option<String>) {
+    let env_dir = env::var(TEMP_DIR_ENV)
+        .ok()
+        .filter(|dir| !dir.trim().is_empty());
+    *TEMP_FILES_DIR.lock().unwrap() = env_dir.or_else(|| temp_dir.clone());
+}
+
+/// Create the directory for temporary files of the exported resources if it is missing.
+pub fn create_temp_files_dir() -> Result<(), std::io::Error> {
+    fs::create_dir_all(temp_files_dir())
+}
+
+/// Directory for temporary files of the exported resources, see [set_temp_files_dir].
+pub fn temp_files_dir() -> String {
+    TEMP_FILES_DIR
+        .lock()
+        .unwrap()
+        .clone()
+        .unwrap_or_else(|| TEMP_DIR_PATH.to_string())
+}
+
+/// Number of temporary files named by this process, makes their names unique.
+static TEMP_FILES_COUNTER: AtomicU64 = AtomicU64::new(0);
+
+/// Returns a unique path of the temporary file in the temporary directory, so several
+/// exports of the same resource at once, in this or other process, don't overwrite each other.
+///
+/// # Arguments
+///
+/// * `name` - Name of the resource, the beginning of the file name
+/// * `suffix` - Suffix of the file name, e.g. the scale name
+/// * `extension` - Extension of the file
+pub fn temp_file_name(name: &String, suffix: &String, extension: &String) -> String {
+    let index = TEMP_FILES_COUNTER.fetch_add(1, Ordering::Relaxed);
+    format!(
+        "{}/{}_{}_{}_{}.{}",
+        temp_files_dir(),
+        name,
+        suffix,
+        process::id(),
+        index,
+        extension
+    )
+}
+
+/// Paths of the partially downloaded files used by the downloads in this process.
+static CLAIMED_PARTIAL_FILES: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+/// Locked files of the temporary directories by their paths, `None` if another process holds
+/// the lock. The OS releases the locks when the process exits, even if it is killed.
+static PARTIAL_FILES_LOCKS: Mutex<Vec<(String, Option<File>)>> = Mutex::new(Vec::new());
+
+/// Partially downloaded file, used by one download at a time. Released when dropped.
+pub struct PartialFile {
+    pub path: String,
+    /// The path is the same in every run, so the next export of the resource can resume it.
+    pub resumable: bool,
+}
+
+impl Drop for PartialFile {
+    fn drop(&mut self) {
+        CLAIMED_PARTIAL_FILES
+            .lock()
+            .unwrap()
+            .retain(|path| path != &self.path);
+    }
+}
+
+/// Returns the partially downloaded file for the resource in the temporary directory. The path
+/// is the same in every run, so an interrupted download can be resumed by the next export of the
+/// same resource. If the file is used by another download in this process, or another process
+/// uses the same temporary directory, the file gets a unique path and isn't `resumable`.
+///
+/// # Arguments
+///
+/// * `name` - Name of the resource, the beginning of the file name
+/// * `suffix` - Suffix of the file name, e.g. the scale name
+/// * `extension` - Extension of the downloaded file
+pub fn claim_partial_file(name: &String, suffix: &String, extension: &String) -> PartialFile {
+    claim_partial_file_in(&temp_files_dir(), name, suffix, extension)
+}
+
+fn claim_partial_file_in(
+    dir: &String,
+    name: &String,
+    suffix: &String,
+    extension: &String,
+) -> PartialFile {
+    let path = format!("{}/{}_{}.{}.part", dir, name, suffix, extension);
+    let mut claimed = CLAIMED_PARTIAL_FILES.lock().unwrap();
+    if owns_partial_files(dir) && !claimed.contains(&path) {
+        claimed.push(path.clone());
+        return PartialFile {
+            path,
+            resumable: true,
+        };
+    }
+    let index = TEMP_FILES_COUNTER.fetch_add(1, Ordering::Relaxed);
+    PartialFile {
+        path: format!("{}.{}_{}", path, process::id(), index),
+        resumable: false,
+    }
+}
+
+/// Returns `true` if this process holds the lock of the partially downloaded files in `dir`.
+/// The lock is taken by the first download in the directory and kept until the process exits.
+fn owns_partial_files(dir: &String) -> bool {
+    let mut locks = PARTIAL_FILES_LOCKS.lock().unwrap();
+    if let Some((_, lock)) = locks.iter().find(|(locked_dir, _)| locked_dir == dir) {
+        return lock.is_some();
+    }
+    let lock = OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(format!("{}/.part.lock", dir))
+        .ok()
+        .filter(|file| file.try_lock().is_ok());
+    let owned = lock.is_some();
+    locks.push((dir.clone(), lock));
+    owned
+}
+
+/// Remove the partially downloaded file with the `ETag` saved next to it.
+pub fn remove_partial_file(path: &String) {
+    fs::remove_file(path).unwrap_or_default();
+    fs::remove_file(etag_file_name(path)).unwrap_or_default();
+}
+
+/// Path of the file with `ETag` of the partially downloaded file.
+pub fn etag_file_name(partial_file: &String) -> String {
+    format!("{}.etag", partial_file)
+}
+
+/// Temporary files of one exported resource, e.g. the downloaded image and the converted one.
+/// The files which are still there are removed when the guard is dropped, both when the
+/// export succeeds and when it fails.
+#[derive(Default)]
+pub struct TempFiles {
+    paths: Vec<String>,
+}
+
+impl TempFiles {
+    /// Remove the file when the guard is dropped. Returns the same path.
+    pub fn track(&mut self, path: String) -> String {
+        self.paths.push(path.clone());
+        path
+    }
+}
+
+impl Drop for TempFiles {
+    fn drop(&mut self) {
+        for path in &self.paths {
+            if Path::new(path).exists() {
+                fs::remove_file(path).unwrap_or_default();
+            }
+        }
+    }
+}
+
 /// Recursively create all directories given in the path.
 ///
 /// # Arguments
@@ -28,7 +200,96 @@ pub fn create_dir(path: &String) -> Result<(), std::io::Error> {
 }
 
 /// Move file from one place to another, replacing the original file if `to` already exists.
-/// This will not work if the new name is on a different mount point.
+/// If the new name is on a different mount point, e.g. the temporary directory is on another
+/// volume, the file is copied next to `to`, renamed to `to` and then removed. Either way `to`
+/// is replaced at once, so it is never left half-written, even if the process is killed.
 pub fn move_file(from: &String, to: &String) -> Result<(), std::io::Error> {
-    fs::rename(from, to)
+    fs::rename(from, to).or_else(|_| {
+        let staged = staged_file_name(to);
+        let result = fs::copy(from, &staged)
+            .and_then(|_| fs::File::open(&staged)?.sync_all())
+            .and_then(|_| fs::rename(&staged, to));
+        if result.is_err() {
+            fs::remove_file(&staged).unwrap_or_default();
+        }
+        result?;
+        fs::remove_file(from)
+    })
+}
+
+/// Returns the path of the hidden file in the same directory as `path`, to copy the file there
+/// before renaming it to `path`. Renaming within one directory never crosses mount points.
+fn staged_file_name(path: &String) -> String {
+    let path = Path::new(path);
+    let file_name = path
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default();
+    path.with_file_name(format!(".{}.{}.part", file_name, process::id()))
+        .to_string_lossy()
+        .to_string()
+}
+
+#[test]
+fn test_temp_files() {
+    let dir = std::env::temp_dir().join(format!("fxa_temp_files_{}", process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let (kept, removed) = (dir.join("kept.png"), dir.join("removed.png"));
+    fs::write(&kept, "").unwrap();
+    fs::write(&removed, "").unwrap();
+    {
+        let mut temp_files = TempFiles::default();
+        temp_files.track(removed.to_string_lossy().to_string());
+        temp_files.track(dir.join("missing.png").to_string_lossy().to_string());
+    }
+    assert!(kept.exists());
+    assert!(!removed.exists());
+    fs::remove_dir_all(&dir).unwrap();
+
+    let name = "img_logo".to_string();
+    let (suffix, extension) = ("xhdpi".to_string(), "png".to_string());
+    assert_ne!(
+        temp_file_name(&name, &suffix, &extension),
+        temp_file_name(&name, &suffix, &extension)
+    );
+}
+
+#[test]
+fn test_move_file_replaces_destination() {
+    let dir = std::env::temp_dir().join(format!("fxa_move_file_{}", process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let path = |name: &str| dir.join(name).to_string_lossy().to_string();
+    fs::write(path("new.png"), "new").unwrap();
+    fs::write(path("img_logo.png"), "old").unwrap();
+
+    move_file(&path("new.png"), &path("img_logo.png")).unwrap();
+    assert_eq!(fs::read_to_string(path("img_logo.png")).unwrap(), "new");
+    assert!(!Path::new(&path("new.png")).exists());
+    assert_eq!(
+        staged_file_name(&path("img_logo.png")),
+        path(&format!(".img_logo.png.{}.part", process::id()))
+    );
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_claim_partial_file() {
+    let dir = std::env::temp_dir().join(format!("fxa_partial_files_{}", process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let dir = dir.to_string_lossy().to_string();
+    let name = "img_logo".to_string();
+    let (suffix, extension) = ("xhdpi".to_string(), "png".to_string());
+    let shared_path = format!("{}/img_logo_xhdpi.png.part", dir);
+    {
+        let first = claim_partial_file_in(&dir, &name, &suffix, &extension);
+        assert_eq!(first.path, shared_path);
+        assert!(first.resumable);
+        // Another download of the same image at the same time doesn't touch the first one
+        let second = claim_partial_file_in(&dir, &name, &suffix, &extension);
+        assert_ne!(second.path, first.path);
+        assert!(!second.resumable);
+    }
+    let third = claim_partial_file_in(&dir, &name, &suffix, &extension);
+    assert_eq!(third.path, shared_path);
+    fs::remove_dir_all(&dir).unwrap();
 }
@@ -1,16 +1,58 @@
 use std::collections::HashMap;
+use std::io::{stdin, IsTerminal};
 
-use crate::{
-    common::{
-        gathering::view::View, renderer::Renderer, suffixes::SuffixExt,
-        suggestions::generate_name_suggestions,
-    },
-    models::config::AppConfig,
+use crate::common::{
+    gathering::view::View, patterns::is_pattern, renderer::Render, suffixes::SuffixExt,
+    suggestions::generate_name_suggestions,
 };
 
+/// How to suggest names similar to the missing ones.
+pub struct SuggestionOptions {
+    /// Minimum similarity of the suggested name, from 0.0 to 1.0.
+    pub threshold: f64,
+    /// Maximum number of suggestions, 0 to disable suggestions.
+    pub limit: usize,
+    /// Ask the user to pick one of the suggestions in the terminal and use it instead of
+    /// the missing name. Nothing is asked if [PromptInput::is_interactive] is `false`.
+    pub interactive: bool,
+}
+
+/// Source of the answers of the user to the prompts.
+pub trait PromptInput {
+    /// Returns `false` if there is nobody to answer, e.g. stdin isn't a terminal on CI.
+    fn is_interactive(&self) -> bool;
+
+    /// Wait for the next line typed by the user. Returns `None` if there is nothing to read.
+    fn read_line(&mut self) -> Option<String>;
+}
+
+/// Answers typed in the terminal, used by the CLI.
+pub struct StdinInput;
+
+impl PromptInput for StdinInput {
+    fn is_interactive(&self) -> bool {
+        stdin().is_terminal()
+    }
+
+    fn read_line(&mut self) -> Option<String> {
+        let mut input = String::new();
+        stdin().read_line(&mut input).ok()?;
+        Some(input)
+    }
+}
+
+impl Default for SuggestionOptions {
+    fn default() -> Self {
+        Self {
+            threshold: 0f64,
+            limit: 5,
+            interactive: false,
+        }
+    }
+}
+
 pub struct InfoGeneratorEntry {
     pub user_name: String,
-    pub figma_name: String,
     pub figma_id: String,
     pub scale_name: String,
     pub scale_value: f32,
@@ -30,42 +72,49 @@ pub struct InfoGeneratorEntry {
 ///
 /// # Arguments
 ///
-/// - `format` - images format
+/// - `frame_names` - names of the frames with images
 /// - `names` - images, requested by users
 /// - `names_to_ids` - map with names and ids of images available in frame
-pub fn gather_names<G, I>(
-    app_config: &AppConfig,
-    frame_name: &String,
+/// - `scales` - names and values of scales to export every image with
+/// - `scale_overrides` - values of `scales` to export the particular images with
+/// - `single_scale_format` - returns true for the images, which ignore `scales` and are exported once
+/// - `suggestion_options` - how to suggest names for the missing images
+/// - `renderer` - renderer to notify the user about found and missing images
+#[allow(clippy::too_many_arguments)]
+pub fn gather_names<S, G, I>(
+    frame_names: &[String],
     names: &[String],
     names_to_ids: &HashMap<String, String>,
-    single_scale_format: bool,
+    scales: &HashMap<String, f32>,
+    scale_overrides: &HashMap<String, Vec<f32>>,
+    single_scale_format: S,
+    suggestion_options: &SuggestionOptions,
+    renderer: &dyn Render,
     info_generator: G,
 ) -> Vec<I>
 where
+    S: Fn(&String) -> bool,
     G: Fn(InfoGeneratorEntry) -> I,
 {
-    let renderer = Renderer();
     // Just for renderer and suggestions generator
-    let available_names = names_to_ids
-        .iter()
-        .map(|(k, _)| k.clone())
-        .collect::<Vec<String>>();
+    let available_names = names_to_ids.keys().cloned().collect::<Vec<String>>();
     let mut queue: Vec<I> = Vec::new();
 
     for user_name in names {
+        let scales = &scales_for_name(user_name, scales, scale_overrides);
+
         // First, look for an images whose names match the names requested by the user
         if names_to_ids.contains_key(user_name) {
             // Notify user
-            renderer.render(View::FoundSimple(user_name.clone()));
+            renderer.render(&View::FoundSimple(user_name.clone()));
             // Add every desired scale to download queue
             for_each_scale(
-                &app_config,
-                single_scale_format,
+                scales,
+                single_scale_format(user_name),
                 &mut |i| queue.push(i),
                 |scale_name, scale_value| {
                     let e = InfoGeneratorEntry {
                         user_name: user_name.clone(),
-                        figma_name: user_name.clone(),
                         figma_id: names_to_ids
                             .get(user_name)
                             .expect("Because map already contains key")
@@ -88,17 +137,16 @@ where
             .all(|n| available_names.contains(n))
         {
             // Notify user
-            renderer.render(View::FoundThemed(user_name.clone()));
+            renderer.render(&View::FoundThemed(user_name.clone()));
             // Add each name (with _light/_dark suffixes) to download queue
             for name in names_with_theme_suffixes {
                 for_each_scale(
-                    &app_config,
-                    single_scale_format,
+                    scales,
+                    single_scale_format(user_name),
                     &mut |i| queue.push(i),
                     |scale_name, scale_value| {
                         let e = InfoGeneratorEntry {
                             user_name: user_name.clone(),
-                            figma_name: name.clone(),
                             figma_id: names_to_ids
                                 .get(&name)
                                 .expect("Because map already contains key")
@@ -117,36 +165,324 @@ where
         // TODO: Tell the user that we will not export only one configuration.
 
         // Third, notify user about resource with desired name is missing in the frame
-        match generate_name_suggestions(&user_name, &available_names) {
-            Some(suggestions) => renderer.render(View::NotFoundButSuggestions(
+        match generate_name_suggestions(
+            user_name,
+            &available_names,
+            suggestion_options.threshold,
+            suggestion_options.limit,
+        ) {
+            Some(suggestions) => renderer.render(&View::NotFoundButSuggestions(
                 user_name.clone(),
-                frame_name.clone(),
+                describe_frames(frame_names),
                 suggestions,
             )),
-            None => renderer.render(View::NotFound(user_name.clone(), frame_name.clone())),
+            None => renderer.render(&View::NotFound(
+                user_name.clone(),
+                describe_frames(frame_names),
+            )),
         };
     }
 
     queue
 }
 
+/// Replace every missing name with the suggestion picked by the user in the terminal,
+/// if [SuggestionOptions::interactive] is enabled and `input` is interactive. Names without a picked suggestion are kept,
+/// so [gather_names] reports them as missing. Picked names which are already requested are
+/// not added again.
+pub fn pick_missing_names(
+    frame_names: &[String],
+    names: Vec<String>,
+    names_to_ids: &HashMap<String, String>,
+    suggestion_options: &SuggestionOptions,
+    input: &mut dyn PromptInput,
+    renderer: &dyn Render,
+) -> Vec<String> {
+    if !suggestion_options.interactive || !input.is_interactive() {
+        return names;
+    }
+    let available_names = names_to_ids.keys().cloned().collect::<Vec<String>>();
+    let mut picked_names: Vec<String> = Vec::new();
+    for name in names {
+        // Patterns without matches have nothing to be replaced with
+        let name = match is_available(&name, &available_names) || is_pattern(&name) {
+            true => name,
+            false => pick_suggestion(
+                &name,
+                &available_names,
+                frame_names,
+                suggestion_options,
+                input,
+                renderer,
+            )
+            .unwrap_or(name),
+        };
+        if !picked_names.contains(&name) {
+            picked_names.push(name);
+        }
+    }
+    picked_names
+}
+
+/// Returns `true` if the image is in the frames by itself or as light and dark variants.
+fn is_available(user_name: &String, available_names: &[String]) -> bool {
+    available_names.contains(user_name)
+        || (available_names.contains(&user_name.with_light_suffix())
+            && available_names.contains(&user_name.with_dark_suffix()))
+}
+
+/// Show the suggestions for the missing name and let the user pick one of them by its number.
+/// Returns `None` if there are no suggestions or the user has skipped the name.
+fn pick_suggestion(
+    user_name: &String,
+    available_names: &Vec<String>,
+    frame_names: &[String],
+    suggestion_options: &SuggestionOptions,
+    input: &mut dyn PromptInput,
+    renderer: &dyn Render,
+) -> Option<String> {
+    let suggestions = generate_name_suggestions(
+        user_name,
+        available_names,
+        suggestion_options.threshold,
+        suggestion_options.limit,
+    )?;
+    // The answer is typed right below the prompt and stays there
+    renderer.render(&View::PickSuggestion(
+        user_name.clone(),
+        describe_frames(frame_names),
+        suggestions.clone(),
+    ));
+    let answer = input.read_line();
+    renderer.new_line();
+    let index = parse_choice(&answer?, suggestions.len())?;
+    suggestions.into_iter().nth(index).map(|(name, _)| name)
+}
+
+/// Index of the suggestion from the number typed by the user, counting from 1.
+/// Returns `None` for anything else, e.g. empty input to skip the name.
+fn parse_choice(input: &str, suggestions_count: usize) -> Option<usize> {
+    match input.trim().parse::<usize>() {
+        Ok(number) if (1..=suggestions_count).contains(&number) => Some(number - 1),
+        _ => None,
+    }
+}
+
+/// Describe the frames for the user, e.g. ``frame `Icons` `` or ``frames `Icons 16`, `Icons 24` ``.
+pub fn describe_frames(frame_names: &[String]) -> String {
+    let quoted_names = frame_names
+        .iter()
+        .map(|name| format!("`{}`", name))
+        .collect::<Vec<String>>()
+        .join(", ");
+    match frame_names.len() {
+        1 => format!("frame {}", quoted_names),
+        _ => format!("frames {}", quoted_names),
+    }
+}
+
+/// Returns the scales to export the image with: from the override if there is one.
+fn scales_for_name(
+    user_name: &String,
+    scales: &HashMap<String, f32>,
+    scale_overrides: &HashMap<String, Vec<f32>>,
+) -> HashMap<String, f32> {
+    match scale_overrides.get(user_name) {
+        Some(override_values) => scales
+            .iter()
+            .filter(|(_, value)| override_values.contains(value))
+            .map(|(name, value)| (name.clone(), *value))
+            .collect(),
+        None => scales.clone(),
+    }
+}
+
 fn for_each_scale<C, G, I>(
-    app_config: &AppConfig,
+    scales: &HashMap<String, f32>,
     single_scale_format: bool,
     consumer: &mut C,
     generator: G,
 ) where
-    C: FnMut(I) -> (),
+    C: FnMut(I),
     G: Fn(String, f32) -> I,
 {
     if single_scale_format {
         // For SVG images and SVG/XML icons always
         consumer(generator(String::new(), 1f32));
     } else {
-        // For PNG, WEBP images only
-        let scales: &HashMap<String, f32> = &app_config.android.images.scales;
+        // For PNG, WEBP images only, from the smallest scale, so the order is the same every time
+        let mut scales = scales.iter().collect::<Vec<(&String, &f32)>>();
+        scales.sort_by(|(a_name, a_value), (b_name, b_value)| {
+            a_value.total_cmp(b_value).then_with(|| a_name.cmp(b_name))
+        });
         for (scale_name, scale_value) in scales {
             consumer(generator(scale_name.clone(), *scale_value));
         }
     }
 }
+
+#[test]
+fn test_scales_for_name() {
+    let scales: HashMap<String, f32> = [("mdpi", 1f32), ("xhdpi", 2f32), ("xxhdpi", 3f32)]
+        .into_iter()
+        .map(|(k, v)| (k.to_string(), v))
+        .collect();
+    let overrides = HashMap::from([("ic_logo".to_string(), vec![1f32, 2f32])]);
+
+    let logo_scales = scales_for_name(&"ic_logo".to_string(), &scales, &overrides);
+    assert_eq!(logo_scales.len(), 2);
+    assert!(!logo_scales.contains_key("xxhdpi"));
+    let other_scales = scales_for_name(&"img_photo".to_string(), &scales, &overrides);
+    assert_eq!(other_scales, scales);
+}
+
+#[test]
+fn test_describe_frames() {
+    assert_eq!(describe_frames(&["Icons".to_string()]), "frame `Icons`");
+    assert_eq!(
+        describe_frames(&["Icons 16".to_string(), "Icons 24".to_string()]),
+        "frames `Icons 16`, `Icons 24`"
+    );
+}
+
+#[test]
+fn test_gather_names_reports_missing_images() {
+    use crate::common::renderer::{BufferingRenderer, Renderable};
+
+    let frame_names = ["Images".to_string()];
+    let names_to_ids = HashMap::from([
+        ("img_photo".to_string(), "1:1".to_string()),
+        ("img_banner".to_string(), "1:2".to_string()),
+    ]);
+    let names = ["img_photo", "img_photos", "unknown"].map(String::from);
+    let renderer = BufferingRenderer::default();
+
+    let queue = gather_names(
+        &frame_names,
+        &names,
+        &names_to_ids,
+        &HashMap::new(),
+        &HashMap::new(),
+        |_| true,
+        &SuggestionOptions {
+            threshold: 0.8f64,
+            limit: 5,
+            interactive: false,
+        },
+        &renderer,
+        |e| e.figma_id,
+    );
+
+    assert_eq!(queue, vec!["1:1".to_string()]);
+    let views = renderer.views();
+    assert_eq!(views[0], View::FoundSimple(names[0].clone()).render());
+    assert_eq!(
+        views[1],
+        View::NotFoundButSuggestions(
+            names[1].clone(),
+            describe_frames(&frame_names),
+            vec![(
+                "img_photo".to_string(),
+                strsim::jaro_winkler("img_photo", "img_photos"),
+            )],
+        )
+        .render()
+    );
+    assert_eq!(
+        views[2],
+        View::NotFound(names[2].clone(), describe_frames(&frame_names)).render()
+    );
+}
+
+#[test]
+fn test_parse_choice() {
+    assert_eq!(parse_choice("2\n", 3), Some(1));
+    assert_eq!(parse_choice(" 1 ", 1), Some(0));
+    assert_eq!(parse_choice("\n", 3), None);
+    assert_eq!(parse_choice("4", 3), None);
+    assert_eq!(parse_choice("0", 3), None);
+    assert_eq!(parse_choice("s", 3), None);
+}
+
+#[test]
+fn test_pick_missing_names() {
+    use crate::common::renderer::BufferingRenderer;
+
+    struct Answers(bool, Vec<&'static str>);
+    impl PromptInput for Answers {
+        fn is_interactive(&self) -> bool {
+            self.0
+        }
+
+        fn read_line(&mut self) -> Option<String> {
+            (!self.1.is_empty()).then(|| self.1.remove(0).to_string())
+        }
+    }
+
+    let names_to_ids = HashMap::from([
+        ("img_photo".to_string(), "1:1".to_string()),
+        ("img_banner".to_string(), "1:2".to_string()),
+    ]);
+    let names = ["img_photos", "img_banners", "img_photo"].map(String::from);
+    let suggestion_options = SuggestionOptions {
+        threshold: 0.8f64,
+        limit: 5,
+        interactive: true,
+    };
+    let pick = |input: &mut Answers| {
+        pick_missing_names(
+            &["Images".to_string()],
+            names.to_vec(),
+            &names_to_ids,
+            &suggestion_options,
+            input,
+            &BufferingRenderer::default(),
+        )
+    };
+
+    // The first name is replaced, the second is skipped, the picked duplicate isn't added again
+    let mut input = Answers(true, vec!["1\n", "\n"]);
+    assert_eq!(pick(&mut input), ["img_photo", "img_banners"]);
+    assert!(input.1.is_empty());
+    // Nothing is asked without the terminal
+    let mut input = Answers(false, vec!["1\n", "1\n"]);
+    assert_eq!(pick(&mut input), names);
+    assert_eq!(input.1.len(), 2);
+}
+
+#[test]
+fn test_gather_names_keeps_order() {
+    use crate::common::renderer::BufferingRenderer;
+
+    let names_to_ids = HashMap::from([
+        ("img_b".to_string(), "1:1".to_string()),
+        ("img_a".to_string(), "1:2".to_string()),
+    ]);
+    let scales: HashMap<String, f32> = [("xxhdpi", 3f32), ("mdpi", 1f32), ("xhdpi", 2f32)]
+        .into_iter()
+        .map(|(k, v)| (k.to_string(), v))
+        .collect();
+    let queue = gather_names(
+        &["Images".to_string()],
+        &["img_b".to_string(), "img_a".to_string()],
+        &names_to_ids,
+        &scales,
+        &HashMap::new(),
+        |_| false,
+        &SuggestionOptions::default(),
+        &BufferingRenderer::default(),
+        |e| format!("{}@{}", e.user_name, e.scale_name),
+    );
+
+    assert_eq!(
+        queue,
+        [
+            "img_b@mdpi",
+            "img_b@xhdpi",
+            "img_b@xxhdpi",
+            "img_a@mdpi",
+            "img_a@xhdpi",
+            "img_a@xxhdpi"
+        ]
+    );
+}
@@ -1,15 +1,26 @@
 use crossterm::style::Stylize;
 
 use crate::common::renderer::{Indentable, Renderable};
+use crate::common::suggestions::differs_only_by_case_or_separators;
 
 pub enum View {
     FoundSimple(String),
     FoundThemed(String),
     NotFound(String, String),
-    NotFoundButSuggestions(String, String, Vec<String>),
+    /// Missing resource name, frame name and suggested names with their similarity scores.
+    NotFoundButSuggestions(String, String, Vec<(String, f64)>),
+    /// Missing resource name, frame name and numbered suggestions to pick one of them.
+    PickSuggestion(String, String, Vec<(String, f64)>),
 }
 
 impl Renderable for View {
+    fn is_important(&self) -> bool {
+        matches!(
+            self,
+            View::NotFound(..) | View::NotFoundButSuggestions(..) | View::PickSuggestion(..)
+        )
+    }
+
     fn render(&self) -> String {
         match self {
             View::FoundSimple(name) => format!(
@@ -24,20 +35,58 @@ impl Renderable for View {
             ),
             View::NotFound(resource_name, frame_name) => {
                 format!(
-                    "{} A resource with name `{}` is missing in frame `{}`\n",
+                    "{} A resource with name `{}` is missing in {}\n",
                     "Error".indent().bold().red(),
                     resource_name,
                     frame_name,
                 )
             }
+            View::PickSuggestion(resource_name, frame_name, suggestions) => {
+                let suggestions = suggestions
+                    .iter()
+                    .enumerate()
+                    .map(|(index, (name, similarity))| {
+                        format!(
+                            "{:i$} {}. `{}` (similarity {:.2})",
+                            "",
+                            index + 1,
+                            name,
+                            similarity,
+                            i = 12
+                        )
+                    })
+                    .collect::<Vec<String>>()
+                    .join("\n");
+                format!(
+                    "{} resource with name `{}` in {}, but there are resources with similar names:\n{}\n{}",
+                    "Missing".indent().bold().yellow(),
+                    resource_name,
+                    frame_name,
+                    suggestions,
+                    "Pick a number or press Enter to skip:".indent(),
+                )
+            }
             View::NotFoundButSuggestions(resource_name, frame_name, suggestions) => {
                 let suggestions = suggestions
                     .iter()
-                    .map(|s| format!("{:i$} `{}`", "", s, i = 12))
+                    .map(|(name, similarity)| {
+                        let typo = match differs_only_by_case_or_separators(name, resource_name) {
+                            true => ", only case or separators differ",
+                            false => "",
+                        };
+                        format!(
+                            "{:i$} `{}` (similarity {:.2}{})",
+                            "",
+                            name,
+                            similarity,
+                            typo,
+                            i = 12
+                        )
+                    })
                     .collect::<Vec<String>>()
                     .join("\n");
                 format!(
-                    "{} resource with name `{}` in frame `{}`,\n {}{}\n{}\n",
+                    "{} resource with name `{}` in {},\n {}{}\n{}\n",
                     "Missing".indent().bold().red(),
                     resource_name,
                     frame_name,
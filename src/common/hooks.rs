@@ -0,0 +1,108 @@
+use std::process::Command;
+
+use crate::common::error::AppError;
+
+/// Environment variable with the path of the file for the transform command.
+pub const TRANSFORM_FILE_ENV: &str = "FXN_FILE";
+
+/// Shell of the platform, which runs the command passed as the next argument.
+fn shell() -> Command {
+    if cfg!(windows) {
+        let mut shell = Command::new("cmd");
+        shell.arg("/C");
+        shell
+    } else {
+        let mut shell = Command::new("sh");
+        shell.arg("-c");
+        shell
+    }
+}
+
+/// Runs the user command from the config with the shell of the platform, the output of the command
+/// is shown to the user as is.
+///
+/// # Arguments
+///
+/// * `command` - Shell command, e.g. `git add app/src/main/res`
+/// * `envs` - Environment variables to pass to the command in addition to the current ones
+pub fn run_hook(command: &String, envs: &[(&str, String)]) -> Result<(), AppError> {
+    let status = shell()
+        .arg(command)
+        .envs(envs.iter().map(|(key, value)| (key, value)))
+        .status()
+        .map_err(|e| AppError::CannotRunHook(command.clone(), e.to_string()))?;
+    match status.code() {
+        Some(0) => Ok(()),
+        Some(code) => Err(AppError::HookFailed(command.clone(), code.to_string())),
+        None => Err(AppError::HookFailed(
+            command.clone(),
+            "none, terminated by signal".to_string(),
+        )),
+    }
+}
+
+/// Runs the transform command from the config for the file. The path of the file is passed
+/// as `$1` (on Unix) and in [TRANSFORM_FILE_ENV] environment variable. Returns the stdout of
+/// the command, which is empty if the command has changed the file in place.
+///
+/// # Arguments
+///
+/// * `command` - Shell command, e.g. `pngquant - < "$1"`
+/// * `file_path` - Path of the file to transform
+/// * `image_name` - Name of the image to show in the errors
+pub fn run_transform(
+    command: &String,
+    file_path: &String,
+    image_name: &String,
+) -> Result<Vec<u8>, AppError> {
+    let mut shell = shell();
+    shell.arg(command);
+    if !cfg!(windows) {
+        // The first argument after the command is `$0`
+        shell.arg("sh").arg(file_path);
+    }
+    let output = shell
+        .env(TRANSFORM_FILE_ENV, file_path)
+        .output()
+        .map_err(|e| AppError::CannotRunHook(command.clone(), e.to_string()))?;
+    if output.status.success() {
+        Ok(output.stdout)
+    } else {
+        Err(AppError::TransformFailed(
+            image_name.clone(),
+            command.clone(),
+            String::from_utf8_lossy(&output.stderr).trim().to_string(),
+        ))
+    }
+}
+
+#[cfg(unix)]
+#[test]
+fn test_run_hook() {
+    let envs = [("FXN_EXPORTED", "2".to_string())];
+    assert!(run_hook(&"test \"$FXN_EXPORTED\" = 2".to_string(), &envs).is_ok());
+    match run_hook(&"exit 3".to_string(), &envs) {
+        Err(AppError::HookFailed(_, code)) => assert_eq!(code, "3"),
+        _ => panic!("hook must fail with exit code 3"),
+    }
+}
+
+#[cfg(unix)]
+#[test]
+fn test_run_transform() {
+    let (file_path, image_name) = ("img.png".to_string(), "img_logo".to_string());
+    let output = run_transform(
+        &"echo \"$1 $FXN_FILE\"".to_string(),
+        &file_path,
+        &image_name,
+    );
+    assert_eq!(output.unwrap(), b"img.png img.png\n".to_vec());
+    match run_transform(
+        &"echo broken >&2; exit 1".to_string(),
+        &file_path,
+        &image_name,
+    ) {
+        Err(AppError::TransformFailed(_, _, stderr)) => assert_eq!(stderr, "broken"),
+        _ => panic!("transform must fail with its stderr"),
+    }
+}
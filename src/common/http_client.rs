@@ -1,21 +1,68 @@
+use std::collections::HashMap;
 use std::time::Duration;
 
 use reqwest::blocking::Client;
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue, AUTHORIZATION};
+use reqwest::Proxy;
 
-/// Create blocking http client to make requests to Figma API.
+use crate::common::error::AppError;
+use crate::common::token::resolve_auth_type;
+use crate::models::config::AuthType;
+
+/// Header with Figma personal access token.
+pub const TOKEN_HEADER: &str = "X-FIGMA-TOKEN";
+
+/// Header of the requests to Figma API with the token: `X-FIGMA-TOKEN` with a personal access
+/// token or `Authorization: Bearer` with an OAuth access token, see [resolve_auth_type].
+/// The header is added to the requests to Figma API only, the image storage doesn't need it.
+/// Returns [AppError::MalformedToken] if the token can't be sent in the header.
+pub fn token_header(
+    token: &String,
+    auth_type: AuthType,
+) -> Result<(HeaderName, HeaderValue), AppError> {
+    let (name, value) = match resolve_auth_type(token, auth_type) {
+        AuthType::OAuth => (AUTHORIZATION, format!("Bearer {}", token)),
+        _ => (HeaderName::try_from(TOKEN_HEADER).unwrap(), token.clone()),
+    };
+    let mut value = HeaderValue::try_from(value).map_err(|_| AppError::MalformedToken)?;
+    // Keep the token out of debug output of the requests
+    value.set_sensitive(true);
+    Ok((name, value))
+}
+
+/// Create blocking http client to make requests to Figma API and to the image storage.
+/// The token is not added to the requests, see [token_header].
+///
+/// The requests have `fxn/<version>` User-Agent, unless another one is in `headers`.
 ///
-/// `X-FIGMA-TOKEN` header with a personal access token
-/// will be added to each request through this client.
+/// If `proxy` is not specified, proxies from `HTTPS_PROXY` and `HTTP_PROXY`
+/// environment variables are used, except for the hosts listed in `NO_PROXY`.
 ///
 /// # Arguments
 ///
-/// * `token` - Figma personal access token. More details: https://www.figma.com/developers/api#authentication
-pub fn create_http_client(token: &String) -> Client {
-    let mut auth_headers = reqwest::header::HeaderMap::new();
-    auth_headers.insert("X-FIGMA-TOKEN", token.parse().unwrap());
-    reqwest::blocking::Client::builder()
-        .timeout(Some(Duration::new(15, 0)))
-        .default_headers(auth_headers)
-        .build()
-        .unwrap()
+/// * `proxy` - Url of the proxy for all requests, overrides the environment variables.
+/// * `headers` - Additional headers of all requests, e.g. for a corporate gateway.
+///   The token header of the requests to Figma API wins over them.
+/// * `timeout` - Timeout to connect to the host and to get the response, can be overridden
+///   for the particular requests.
+pub fn create_http_client(
+    proxy: &Option<String>,
+    headers: &HashMap<String, String>,
+    timeout: Duration,
+) -> Client {
+    let mut default_headers = HeaderMap::new();
+    for (name, value) in headers {
+        let name = HeaderName::try_from(name).expect("Validation is done in fetcher");
+        let value = HeaderValue::try_from(value).expect("Validation is done in fetcher");
+        default_headers.insert(name, value);
+    }
+    let mut builder = reqwest::blocking::Client::builder()
+        .connect_timeout(Some(timeout))
+        .timeout(Some(timeout))
+        .user_agent(format!("fxn/{}", env!("CARGO_PKG_VERSION")))
+        .default_headers(default_headers);
+    if let Some(proxy) = proxy {
+        builder = builder.proxy(Proxy::all(proxy).expect("Validation is done in fetcher"));
+    }
+    builder.build().unwrap()
 }
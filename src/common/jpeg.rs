@@ -0,0 +1,47 @@
+use image::codecs::jpeg::JpegEncoder;
+use image::io::Reader as ImageReader;
+use image::{Rgb, RgbImage};
+
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::Path;
+
+use super::error::AppError;
+
+/// Converts PNG image to JPEG. Returns the path to the JPEG image, or an error with a description.
+/// In case of successful conversion, the function will create a JPEG file next to the original PNG file.
+/// Transparent pixels are blended with the white background, because JPEG has no alpha channel.
+///
+/// # Arguments
+///
+/// * `file_path` - Path to PNG image
+/// * `quality` - Encoding quality in percents (from 1 to 100)
+pub fn image_to_jpeg(file_path: &String, quality: u8) -> Result<String, AppError> {
+    let image = ImageReader::open(file_path)
+        .map_err(|_| AppError::SourceNotFound(file_path.clone()))?
+        .with_guessed_format()
+        .map_err(|_| AppError::UnderlyingReader(file_path.clone()))?
+        .decode()
+        .map_err(|_| AppError::CannotDecode(file_path.clone()))?
+        .to_rgba8();
+
+    let mut rgb_image = RgbImage::new(image.width(), image.height());
+    for (x, y, pixel) in image.enumerate_pixels() {
+        let [r, g, b, a] = pixel.0;
+        let blend = |c: u8| ((c as u32 * a as u32 + 255 * (255 - a as u32)) / 255) as u8;
+        rgb_image.put_pixel(x, y, Rgb([blend(r), blend(g), blend(b)]));
+    }
+
+    // Put jpeg-image in the location of the original image
+    let jpeg_image_path = Path::new(file_path)
+        .with_extension("jpg")
+        .to_string_lossy()
+        .to_string();
+    let file = File::create(&jpeg_image_path)
+        .map_err(|e| AppError::WriteJpegTemporarySave(format!("{}", e)))?;
+    JpegEncoder::new_with_quality(&mut BufWriter::new(file), quality.clamp(1, 100))
+        .encode_image(&rgb_image)
+        .map_err(|e| AppError::WriteJpegTemporarySave(format!("{}", e)))?;
+
+    Ok(jpeg_image_path)
+}
@@ -0,0 +1,156 @@
+use std::fs;
+use std::path::Path;
+
+use crate::common::colors::to_argb;
+use crate::models::figma::Color;
+
+/// Generate Kotlin source with an object exposing every drawable resource as a typed reference:
+/// ```kotlin
+/// package com.example.ui
+///
+/// import androidx.annotation.DrawableRes
+/// import com.example.R
+///
+/// object AppImages {
+///     @DrawableRes val ImgLogo = R.drawable.img_logo
+/// }
+/// ```
+///
+/// # Arguments
+///
+/// * `package_name` - Package of the generated file.
+/// * `r_package_name` - Package of the `R` class. If `None`, `R` is expected in `package_name`.
+/// * `object_name` - Name of the generated object.
+/// * `res_names` - Names of the drawable resources.
+pub fn drawables_object_source(
+    package_name: &String,
+    r_package_name: &Option<String>,
+    object_name: &String,
+    res_names: &[String],
+) -> String {
+    let mut source = format!("package {}\n\n", package_name);
+    source.push_str("import androidx.annotation.DrawableRes\n");
+    if let Some(r_package_name) = r_package_name {
+        source.push_str(&format!("import {}.R\n", r_package_name));
+    }
+    source.push_str(&format!("\nobject {} {{\n", object_name));
+    for res_name in res_names {
+        source.push_str(&format!(
+            "    @DrawableRes val {} = R.drawable.{}\n",
+            to_property_name(res_name),
+            res_name
+        ));
+    }
+    source.push_str("}\n");
+    source
+}
+
+/// Generate Kotlin source with an object exposing every color for Jetpack Compose:
+/// ```kotlin
+/// package com.example.ui
+///
+/// import androidx.compose.ui.graphics.Color
+///
+/// object AppColors {
+///     val Primary = Color(0xFF6200EE)
+/// }
+/// ```
+///
+/// # Arguments
+///
+/// * `package_name` - Package of the generated file.
+/// * `object_name` - Name of the generated object.
+/// * `colors` - Resource names and values of the colors.
+pub fn colors_object_source(
+    package_name: &String,
+    object_name: &String,
+    colors: &[(String, Color)],
+) -> String {
+    let mut source = format!("package {}\n\n", package_name);
+    source.push_str("import androidx.compose.ui.graphics.Color\n");
+    source.push_str(&format!("\nobject {} {{\n", object_name));
+    for (res_name, color) in colors {
+        let [a, r, g, b] = to_argb(color);
+        source.push_str(&format!(
+            "    val {} = Color(0x{:02X}{:02X}{:02X}{:02X})\n",
+            to_property_name(res_name),
+            a,
+            r,
+            g,
+            b
+        ));
+    }
+    source.push_str("}\n");
+    source
+}
+
+/// Write the generated source to the file, creating parent directories if necessary.
+pub fn write_source(file_path: &String, source: &String) -> Result<(), std::io::Error> {
+    if let Some(parent) = Path::new(file_path).parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(file_path, source)
+}
+
+/// Turn snake_case resource name into PascalCase property name, e.g. `ic_24_logo` to `Ic24Logo`.
+fn to_property_name(res_name: &str) -> String {
+    res_name
+        .split('_')
+        .filter(|part| !part.is_empty())
+        .map(|part| {
+            let mut chars = part.chars();
+            match chars.next() {
+                Some(first) => first.to_ascii_uppercase().to_string() + chars.as_str(),
+                None => String::new(),
+            }
+        })
+        .collect()
+}
+
+#[test]
+fn test_to_property_name() {
+    assert_eq!(to_property_name("ic_logo"), "IcLogo");
+    assert_eq!(to_property_name("ic_24_paper_id_"), "Ic24PaperId");
+}
+
+#[test]
+fn test_drawables_object_source() {
+    let source = drawables_object_source(
+        &"com.example.ui".to_string(),
+        &Some("com.example".to_string()),
+        &"AppImages".to_string(),
+        &["img_logo".to_string()],
+    );
+    assert_eq!(
+        source,
+        "package com.example.ui\n\n\
+        import androidx.annotation.DrawableRes\n\
+        import com.example.R\n\n\
+        object AppImages {\n    \
+        @DrawableRes val ImgLogo = R.drawable.img_logo\n\
+        }\n"
+    );
+}
+
+#[test]
+fn test_colors_object_source() {
+    let color = Color {
+        r: 1f32,
+        g: 0f32,
+        b: 0f32,
+        a: 0.5,
+    };
+    let source = colors_object_source(
+        &"com.example.ui".to_string(),
+        &"AppColors".to_string(),
+        &[("brand_red".to_string(), color)],
+    );
+    assert_eq!(
+        source,
+        "package com.example.ui\n\n\
+        import androidx.compose.ui.graphics.Color\n\n\
+        object AppColors {\n    \
+        val BrandRed = Color(0x80FF0000)\n\
+        }\n"
+    );
+}
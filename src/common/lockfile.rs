@@ -0,0 +1,192 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs::{self, File};
+use std::io::BufWriter;
+use std::path::Path;
+
+pub const LOCK_FILE_NAME: &str = ".fxn.lock";
+
+/// Content of the `.fxn.lock` file, which is stored next to the YAML config and remembers
+/// exported resources, so they are not exported again while nothing has changed.
+///
+/// Entries are sorted by the output path, so the file diffs cleanly in git:
+/// ```json
+/// {
+///   "version": 1,
+///   "entries": {
+///     "./main/res/drawable-xhdpi/img_logo.webp": {
+///       "nodeId": "12:34",
+///       "fileVersion": "1234567890",
+///       "settings": "webp;scale=2;quality=85",
+///       "hash": "af63bd4c8601b7be",
+///       "nodeHash": "5d1b8e0cc2b04eb3"
+///     }
+///   }
+/// }
+/// ```
+#[derive(Debug, Default, Deserialize, Serialize)]
+pub struct LockFile {
+    #[serde(skip)]
+    path: String,
+    version: u32,
+    entries: BTreeMap<String, LockEntry>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LockEntry {
+    /// Figma node the resource was exported from.
+    pub node_id: String,
+    /// Version of the Figma file at the moment of export.
+    pub file_version: String,
+    /// Export settings of the resource, such as format and scale.
+    pub settings: String,
+    /// Hash of the exported file content.
+    pub hash: String,
+    /// Hash of the Figma node at the moment of export, if it has been fetched.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub node_hash: Option<String>,
+}
+
+const LOCK_FILE_VERSION: u32 = 1;
+
+impl LockFile {
+    /// Load the lock file located next to the YAML config. Missing or unreadable
+    /// lock file is treated as empty, so everything will be exported again.
+    pub fn load(yaml_config_path: &String) -> Self {
+        let path = Path::new(yaml_config_path)
+            .with_file_name(LOCK_FILE_NAME)
+            .to_string_lossy()
+            .to_string();
+        let lock_file = fs::read_to_string(&path)
+            .ok()
+            .and_then(|content| serde_json::from_str::<LockFile>(&content).ok())
+            .filter(|lock_file| lock_file.version == LOCK_FILE_VERSION)
+            .unwrap_or_default();
+        Self {
+            path,
+            version: LOCK_FILE_VERSION,
+            ..lock_file
+        }
+    }
+
+    /// Returns the path of the lock file.
+    pub fn path(&self) -> &String {
+        &self.path
+    }
+
+    /// Returns `true` if the file at `output_path` was exported from the same node with the same
+    /// settings, and it has not been changed since then. The node is unchanged if its hash is
+    /// the same. Only if the hash isn't known now or at the moment of export, the node is
+    /// unchanged while the version of Figma file is the same.
+    ///
+    /// # Arguments
+    ///
+    /// * `output_path` - Final path of the exported resource.
+    /// * `node_id` - Figma node to export the resource from.
+    /// * `file_version` - Current version of the Figma file.
+    /// * `node_hash` - Current hash of the Figma node, if it is known.
+    /// * `settings` - Current export settings of the resource.
+    pub fn is_up_to_date(
+        &self,
+        output_path: &String,
+        node_id: &String,
+        file_version: &String,
+        node_hash: Option<&String>,
+        settings: &String,
+    ) -> bool {
+        match self.entries.get(output_path) {
+            Some(entry) => {
+                let node_unchanged = match (node_hash, &entry.node_hash) {
+                    (Some(node_hash), Some(exported_hash)) => node_hash == exported_hash,
+                    _ => &entry.file_version == file_version,
+                };
+                &entry.node_id == node_id
+                    && node_unchanged
+                    && &entry.settings == settings
+                    && content_hash(output_path).ok().as_ref() == Some(&entry.hash)
+            }
+            None => false,
+        }
+    }
+
+    /// Remember the exported resource.
+    pub fn insert(&mut self, output_path: String, entry: LockEntry) {
+        self.entries.insert(output_path, entry);
+    }
+
+    /// Forget the resource, which file has been removed after the export.
+    pub fn remove(&mut self, output_path: &String) {
+        self.entries.remove(output_path);
+    }
+
+    /// Write the lock file. Existing file will be replaced.
+    pub fn save(&self) -> Result<(), std::io::Error> {
+        let file = File::create(&self.path)?;
+        serde_json::to_writer_pretty(BufWriter::new(file), self)?;
+        Ok(())
+    }
+}
+
+/// Returns a stable hash of the file content: 64-bit FNV-1a in hex.
+pub fn content_hash(file_path: &String) -> Result<String, std::io::Error> {
+    let bytes = fs::read(file_path)?;
+    Ok(format!("{:016x}", fnv1a(&bytes)))
+}
+
+/// Returns a stable hash of the text, the same as [content_hash] of the file with this text.
+pub fn text_hash(text: &str) -> String {
+    format!("{:016x}", fnv1a(text.as_bytes()))
+}
+
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf29ce484222325u64, |hash, &b| {
+        (hash ^ b as u64).wrapping_mul(0x100000001b3)
+    })
+}
+
+#[test]
+fn test_fnv1a() {
+    assert_eq!(fnv1a(b""), 0xcbf29ce484222325);
+    assert_eq!(fnv1a(b"a"), 0xaf63dc4c8601ec8c);
+    assert_eq!(fnv1a(b"foobar"), 0x85944171f73967e8);
+}
+
+#[test]
+fn test_unchanged_node_is_up_to_date() {
+    let output_path = std::env::temp_dir()
+        .join("fxn_test_unchanged_node.png")
+        .to_string_lossy()
+        .to_string();
+    fs::write(&output_path, b"image").unwrap();
+    let mut lock_file = LockFile::default();
+    lock_file.insert(
+        output_path.clone(),
+        LockEntry {
+            node_id: "1:2".to_string(),
+            file_version: "1".to_string(),
+            settings: "png;scale=1".to_string(),
+            hash: content_hash(&output_path).unwrap(),
+            node_hash: Some(text_hash("{}")),
+        },
+    );
+    let (node_id, settings) = ("1:2".to_string(), "png;scale=1".to_string());
+    let (old_version, new_version) = ("1".to_string(), "2".to_string());
+    let (same_hash, new_hash) = (text_hash("{}"), text_hash("{\"a\":1}"));
+
+    assert!(lock_file.is_up_to_date(&output_path, &node_id, &old_version, None, &settings));
+    assert!(!lock_file.is_up_to_date(&output_path, &node_id, &new_version, None, &settings));
+    let up_to_date =
+        |hash| lock_file.is_up_to_date(&output_path, &node_id, &new_version, Some(hash), &settings);
+    assert!(up_to_date(&same_hash));
+    assert!(!up_to_date(&new_hash));
+    // The node has changed, even though the version is the same
+    assert!(!lock_file.is_up_to_date(
+        &output_path,
+        &node_id,
+        &old_version,
+        Some(&new_hash),
+        &settings
+    ));
+    fs::remove_file(&output_path).unwrap();
+}
@@ -0,0 +1,101 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs::{self, File};
+use std::io::BufWriter;
+use std::path::Path;
+
+pub const MANIFEST_FILE_NAME: &str = "fxn-manifest.json";
+
+/// Content of the `fxn-manifest.json` file, which is stored next to the YAML config and maps
+/// every exported resource to the Figma image it was exported from, because the resource name
+/// doesn't always tell the original name.
+///
+/// Entries are sorted by the output path, so the file diffs cleanly in git:
+/// ```json
+/// {
+///   "./main/res/drawable-xhdpi/img_logo.webp": {
+///     "figmaName": "Img/Logo",
+///     "resName": "img_logo",
+///     "nodeId": "12:34"
+///   }
+/// }
+/// ```
+#[derive(Debug, Default, Deserialize, Serialize)]
+pub struct Manifest {
+    #[serde(skip)]
+    path: String,
+    #[serde(flatten)]
+    entries: BTreeMap<String, ManifestEntry>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ManifestEntry {
+    /// Name of the image in Figma.
+    pub figma_name: String,
+    /// Name of the android resource or of the iOS image set.
+    pub res_name: String,
+    /// Figma node the resource was exported from.
+    pub node_id: String,
+}
+
+impl Manifest {
+    /// Load the manifest located next to the YAML config. Entries of the resources, which
+    /// haven't been exported this time, are kept. Missing or unreadable manifest is treated
+    /// as empty.
+    pub fn load(yaml_config_path: &String) -> Self {
+        let path = Path::new(yaml_config_path)
+            .with_file_name(MANIFEST_FILE_NAME)
+            .to_string_lossy()
+            .to_string();
+        let manifest = fs::read_to_string(&path)
+            .ok()
+            .and_then(|content| serde_json::from_str::<Manifest>(&content).ok())
+            .unwrap_or_default();
+        Self { path, ..manifest }
+    }
+
+    /// Returns the path of the manifest.
+    pub fn path(&self) -> &String {
+        &self.path
+    }
+
+    /// Remember the exported resource.
+    pub fn insert(&mut self, output_path: String, entry: ManifestEntry) {
+        self.entries.insert(output_path, entry);
+    }
+
+    /// Write the manifest. Existing file will be replaced.
+    pub fn save(&self) -> Result<(), std::io::Error> {
+        let file = File::create(&self.path)?;
+        serde_json::to_writer_pretty(BufWriter::new(file), self)?;
+        Ok(())
+    }
+}
+
+#[test]
+fn test_manifest_keeps_previous_entries() {
+    let config_path = std::env::temp_dir()
+        .join("fxn_test_manifest")
+        .join("fxn.yaml")
+        .to_string_lossy()
+        .to_string();
+    fs::create_dir_all(Path::new(&config_path).parent().unwrap()).unwrap();
+    let entry = |name: &str| ManifestEntry {
+        figma_name: name.to_string(),
+        res_name: name.to_lowercase(),
+        node_id: "1:2".to_string(),
+    };
+
+    let mut manifest = Manifest::load(&config_path);
+    manifest.insert("res/drawable/logo.xml".to_string(), entry("Logo"));
+    manifest.save().unwrap();
+    let mut manifest = Manifest::load(&config_path);
+    manifest.insert("res/drawable/banner.xml".to_string(), entry("Banner"));
+    manifest.save().unwrap();
+
+    let manifest = Manifest::load(&config_path);
+    assert_eq!(manifest.entries.len(), 2);
+    assert_eq!(manifest.entries["res/drawable/logo.xml"], entry("Logo"));
+    fs::remove_file(manifest.path()).unwrap();
+}
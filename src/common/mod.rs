@@ -1,11 +1,35 @@
+pub mod adaptive_icon;
+pub mod aliases;
+#[cfg(feature = "avif")]
+pub mod avif;
+pub mod colors;
+pub mod density;
+pub mod dimensions;
+pub mod disk_space;
 pub mod error;
+pub mod exit_status;
 pub mod fetching;
 pub mod fileutils;
 pub mod gathering;
+pub mod hooks;
 pub mod http_client;
+pub mod jpeg;
+pub mod kotlin;
+pub mod lockfile;
+pub mod manifest;
+pub mod names_file;
+pub mod node_url;
+pub mod paths;
+pub mod patterns;
+pub mod png;
 pub mod renderer;
+pub mod report;
 pub mod res_name;
 pub mod suffixes;
 pub mod suggestions;
+pub mod svg;
+pub mod token;
 pub mod vdtool;
 pub mod webp;
+pub mod workers;
+pub mod xcassets;
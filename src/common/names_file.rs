@@ -0,0 +1,38 @@
+use std::fs;
+
+use super::error::AppError;
+
+/// Read the names of the resources from the file, one name per line.
+/// Blank lines and lines starting with `#` are ignored, e.g. to comment the list.
+/// The file without names is an error, because no names would mean all resources.
+pub fn read_names_file(path: &String) -> Result<Vec<String>, AppError> {
+    let content = fs::read_to_string(path)
+        .map_err(|e| AppError::CannotReadNamesFile(path.clone(), e.to_string()))?;
+    let names = parse_names(&content);
+    if names.is_empty() {
+        return Err(AppError::EmptyNamesFile(path.clone()));
+    }
+    Ok(names)
+}
+
+fn parse_names(content: &str) -> Vec<String> {
+    content
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(str::to_string)
+        .collect()
+}
+
+#[test]
+fn test_parse_names() {
+    let content = "# Onboarding\nimg_welcome\n\n  img_done  \r\nic_button_*\n";
+    assert_eq!(
+        parse_names(content),
+        vec![
+            "img_welcome".to_string(),
+            "img_done".to_string(),
+            "ic_button_*".to_string()
+        ]
+    );
+}
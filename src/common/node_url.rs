@@ -0,0 +1,74 @@
+use reqwest::Url;
+
+/// Figma node to export, parsed from the link to it or from its id.
+#[derive(Debug, PartialEq, Eq)]
+pub struct NodeRef {
+    /// Key of the file from the link. `None` if only the node id is specified.
+    pub file_id: Option<String>,
+    /// Id of the node in the form which is used by Figma API, e.g. `12:345`.
+    pub node_id: String,
+}
+
+/// Parse the link to the node, copied from Figma, e.g.
+/// `https://www.figma.com/file/KEY/Name?node-id=12-345`, or just the node id, e.g. `12:345`
+/// or `12-345`. Returns `None` if there is no node id.
+pub fn parse_node_ref(value: &str) -> Option<NodeRef> {
+    let url = match Url::parse(value) {
+        Ok(url) => url,
+        Err(_) => {
+            return normalized_node_id(value).map(|node_id| NodeRef {
+                file_id: None,
+                node_id,
+            })
+        }
+    };
+    // Path is `/file/KEY/Name` or `/design/KEY/Name`
+    let mut segments = url.path_segments()?;
+    let file_id = match segments.next() {
+        Some("file" | "design" | "proto") => segments.next().map(String::from),
+        _ => None,
+    }
+    .filter(|key| !key.is_empty());
+    let node_id = url
+        .query_pairs()
+        .find(|(key, _)| key == "node-id")
+        .and_then(|(_, value)| normalized_node_id(&value))?;
+    Some(NodeRef { file_id, node_id })
+}
+
+/// Links contain node ids with `-` instead of `:`.
+fn normalized_node_id(value: &str) -> Option<String> {
+    let node_id = value.trim().replace('-', ":");
+    let parts = node_id.split(':').collect::<Vec<&str>>();
+    let valid = parts.len() == 2
+        && parts
+            .iter()
+            .all(|part| !part.is_empty() && part.chars().all(|c| c.is_ascii_digit()));
+    if valid {
+        Some(node_id)
+    } else {
+        None
+    }
+}
+
+#[test]
+fn test_parse_node_ref() {
+    let node_ref = |file_id: Option<&str>, node_id: &str| NodeRef {
+        file_id: file_id.map(String::from),
+        node_id: node_id.to_string(),
+    };
+    assert_eq!(
+        parse_node_ref("https://www.figma.com/file/AbC123/Design-System?node-id=12-345"),
+        Some(node_ref(Some("AbC123"), "12:345"))
+    );
+    assert_eq!(
+        parse_node_ref("https://figma.com/design/AbC123/Name?type=design&node-id=12%3A345"),
+        Some(node_ref(Some("AbC123"), "12:345"))
+    );
+    assert_eq!(parse_node_ref("12-345"), Some(node_ref(None, "12:345")));
+    assert_eq!(
+        parse_node_ref("https://www.figma.com/file/AbC123/Name"),
+        None
+    );
+    assert_eq!(parse_node_ref("img_logo"), None);
+}
@@ -0,0 +1,78 @@
+use std::env;
+use std::path::{Component, Path, PathBuf};
+
+/// Resolve the path from the config: `~` is expanded to the home directory, and relative paths
+/// are resolved against the directory of the config, not against the working directory.
+/// Relative paths stay relative, so the paths stored in the lock file don't depend on
+/// where the project is. If the config is read from stdin, there is no directory of the config,
+/// and relative paths are resolved against the working directory.
+///
+/// # Arguments
+///
+/// * `path` - Path from the config, e.g. `~/projects/app/src/main/res` or `./src/main/res`
+/// * `yaml_config_path` - Path to the config the path is from
+pub fn resolve_config_path(path: &String, yaml_config_path: &String) -> String {
+    if path.trim().is_empty() {
+        return path.clone();
+    }
+    let path = expand_home(path);
+    if path.is_absolute() {
+        return normalize(&path);
+    }
+    let config_dir = Path::new(yaml_config_path)
+        .parent()
+        .unwrap_or_else(|| Path::new(""));
+    normalize(&config_dir.join(path))
+}
+
+/// Returns the absolute path to the parent of the directory with symlinks resolved,
+/// or `None` if the parent doesn't exist and the directory can't be created.
+pub fn canonical_parent(path: &String) -> Option<PathBuf> {
+    let parent = match Path::new(path).parent() {
+        Some(parent) if parent.as_os_str().is_empty() => Path::new("."),
+        Some(parent) => parent,
+        None => return None,
+    };
+    parent.canonicalize().ok()
+}
+
+fn expand_home(path: &String) -> PathBuf {
+    let home = env::var_os("HOME").or_else(|| env::var_os("USERPROFILE"));
+    match (path.strip_prefix('~'), home) {
+        (Some(""), Some(home)) => PathBuf::from(home),
+        (Some(rest), Some(home)) if rest.starts_with(['/', '\\']) => {
+            PathBuf::from(home).join(&rest[1..])
+        }
+        _ => PathBuf::from(path),
+    }
+}
+
+/// Remove `.` components, e.g. `config/./main/res` becomes `config/main/res`.
+fn normalize(path: &Path) -> String {
+    let path = path
+        .components()
+        .filter(|component| component != &Component::CurDir)
+        .collect::<PathBuf>();
+    match path.as_os_str().is_empty() {
+        true => ".".to_string(),
+        false => path.to_string_lossy().to_string(),
+    }
+}
+
+#[cfg(unix)]
+#[test]
+fn test_resolve_config_path() {
+    let resolve =
+        |path: &str, config: &str| resolve_config_path(&path.to_string(), &config.to_string());
+    assert_eq!(resolve("./main/res", "fxn.yaml"), "main/res");
+    assert_eq!(resolve("./main/res", "app/fxn.yaml"), "app/main/res");
+    assert_eq!(resolve("../lib/res", "app/fxn.yaml"), "app/../lib/res");
+    assert_eq!(resolve("/abs/res", "app/fxn.yaml"), "/abs/res");
+    assert_eq!(resolve(".", "fxn.yaml"), ".");
+    assert_eq!(resolve("./main/res", "-"), "main/res");
+    let home = env::var("HOME").unwrap();
+    assert_eq!(
+        resolve("~/app/res", "app/fxn.yaml"),
+        format!("{}/app/res", home)
+    );
+}
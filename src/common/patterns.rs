@@ -0,0 +1,63 @@
+/// Returns `true` if the name contains glob wildcards `*` or `?`.
+pub fn is_pattern(name: &String) -> bool {
+    name.contains(['*', '?'])
+}
+
+/// Match the name against the glob pattern, where `*` matches any sequence of characters
+/// (including empty one) and `?` matches exactly one character.
+///
+/// # Example
+/// ```ignore
+/// assert!(matches_pattern("ic_button_*", "ic_button_close"));
+/// assert!(!matches_pattern("ic_?", "ic_button"));
+/// ```
+pub fn matches_pattern(pattern: &str, name: &str) -> bool {
+    let pattern = pattern.chars().collect::<Vec<char>>();
+    let name = name.chars().collect::<Vec<char>>();
+    let (mut p, mut n) = (0usize, 0usize);
+    // Position of the last `*` in pattern and position in name where it started to match
+    let mut backtrack: Option<(usize, usize)> = None;
+
+    while n < name.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                backtrack = Some((p, n));
+                p += 1;
+            }
+            Some(&c) if c == '?' || c == name[n] => {
+                p += 1;
+                n += 1;
+            }
+            _ => match backtrack {
+                // Let the last `*` match one more character
+                Some((star_p, star_n)) => {
+                    backtrack = Some((star_p, star_n + 1));
+                    p = star_p + 1;
+                    n = star_n + 1;
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+#[test]
+fn test_is_pattern() {
+    assert!(is_pattern(&"ic_*".to_string()));
+    assert!(is_pattern(&"ic_?".to_string()));
+    assert!(!is_pattern(&"ic_close".to_string()));
+}
+
+#[test]
+fn test_matches_pattern() {
+    assert!(matches_pattern("ic_button_*", "ic_button_close"));
+    assert!(matches_pattern("ic_button_*", "ic_button_"));
+    assert!(matches_pattern("*_dark", "img_logo_dark"));
+    assert!(matches_pattern("img_*_*", "img_logo_dark"));
+    assert!(matches_pattern("ic_??", "ic_ok"));
+    assert!(matches_pattern("*", ""));
+    assert!(!matches_pattern("ic_?", "ic_ok"));
+    assert!(!matches_pattern("ic_*", "img_logo"));
+    assert!(!matches_pattern("*_dark", "img_logo_light"));
+}
@@ -0,0 +1,38 @@
+use oxipng::{InFile, Options, OutFile, StripChunks};
+use std::path::PathBuf;
+
+use super::error::AppError;
+
+/// Losslessly optimizes PNG image in place. Returns an error with a description if
+/// the image can't be optimized, the original image stays untouched in that case.
+///
+/// # Arguments
+///
+/// * `file_path` - Path to PNG image
+/// * `level` - Optimization level (from 0 to 6). Higher levels are slower, but produce smaller files
+pub fn optimize_png(file_path: &String, level: u8) -> Result<(), AppError> {
+    let input = InFile::Path(PathBuf::from(file_path));
+    let output = OutFile::from_path(PathBuf::from(file_path));
+    oxipng::optimize(&input, &output, &Options::from_preset(level))
+        .map(|_| ())
+        .map_err(|e| AppError::CannotOptimizePng(file_path.clone(), e.to_string()))
+}
+
+/// Removes metadata chunks from PNG image in place, keeping only the chunks which affect
+/// the rendering, e.g. color profile and gamma. Returns an error with a description if
+/// the chunks can't be removed, the original image stays untouched in that case.
+///
+/// # Arguments
+///
+/// * `file_path` - Path to PNG image
+pub fn strip_png_metadata(file_path: &String) -> Result<(), AppError> {
+    let input = InFile::Path(PathBuf::from(file_path));
+    let output = OutFile::from_path(PathBuf::from(file_path));
+    let options = Options {
+        strip: StripChunks::Safe,
+        ..Options::from_preset(0)
+    };
+    oxipng::optimize(&input, &output, &options)
+        .map(|_| ())
+        .map_err(|e| AppError::CannotStripMetadata(file_path.clone(), e.to_string()))
+}
@@ -1,7 +1,10 @@
-use std::io::{stdout, Write};
+use std::io::{stdout, IsTerminal, Write};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Mutex;
 
 use crossterm::{
     cursor,
+    style::Stylize,
     terminal::{self, ClearType},
     QueueableCommand,
 };
@@ -9,6 +12,26 @@ use crossterm::{
 /// Because rustc output indent is 12
 const INDENT_SIZE: usize = 12usize;
 
+/// Width of the progress bar in characters.
+const PROGRESS_BAR_WIDTH: usize = 30usize;
+
+/// State of the progress bar, shared by all renderers.
+static PROGRESS: Mutex<Option<Progress>> = Mutex::new(None);
+
+/// Quiet mode of all renderers, see [Renderer::set_quiet].
+static QUIET: AtomicBool = AtomicBool::new(false);
+
+#[derive(Debug, Clone, Copy)]
+struct Progress {
+    images_done: usize,
+    images_total: usize,
+    scales_done: usize,
+    scales_total: usize,
+    /// If true, the progress is shown as the bar at the bottom of the output.
+    /// Otherwise, it is shown as plain lines, e.g. when stdout isn't a terminal.
+    bar: bool,
+}
+
 pub trait Indentable {
     fn indent(&self) -> String;
 }
@@ -17,16 +40,12 @@ impl Indentable for &str {
     /// Add space indentation for the string if string len is lower than [INDENT_SIZE].
     ///
     /// # Example
-    /// ```rust
+    /// ```ignore
     /// asserteq!("Done".indent(), "        Done".to_string())
     /// ```
     fn indent(&self) -> String {
         let len = self.len();
-        let indent = if len <= INDENT_SIZE {
-            INDENT_SIZE - len
-        } else {
-            0usize
-        };
+        let indent = INDENT_SIZE.saturating_sub(len);
         format!("{:indent$}{}", "", &self, indent = indent)
     }
 }
@@ -34,14 +53,67 @@ impl Indentable for &str {
 /// An interface for types that can be converted into a formatted color output.
 pub trait Renderable {
     fn render(&self) -> String;
+
+    /// Important views, such as errors, warnings and the final summary, are shown even in quiet mode.
+    fn is_important(&self) -> bool {
+        false
+    }
+
+    /// Warnings are counted by [WarningCounter], e.g. for the final summary.
+    fn is_warning(&self) -> bool {
+        false
+    }
+}
+
+/// An interface for the output of the app, e.g. terminal output of the CLI.
+///
+/// The exporting functions take `&dyn Render`, so any frontend can show the views.
+/// Also see [Renderer] and [BufferingRenderer].
+pub trait Render: Sync {
+    /// Render the view in place of the previous line.
+    fn render(&self, view: &dyn Renderable);
+
+    fn new_line(&self);
+
+    /// Render the view in place of the previous line and move to a new line, so that the
+    /// view will not be overwritten by the next [Render::render] call.
+    fn render_line(&self, view: &dyn Renderable) {
+        self.render(view);
+        self.new_line();
+    }
+
+    /// Render the views line by line, like [Render::render_line], without the lines of other
+    /// threads between them.
+    fn render_lines(&self, views: &[&dyn Renderable]) {
+        for view in views {
+            self.render_line(*view);
+        }
+    }
+
+    /// Show the progress of the export.
+    ///
+    /// # Arguments
+    ///
+    /// * `images_total` - Number of images to export.
+    /// * `scales_total` - Number of all scales of all images to export.
+    fn start_progress(&self, _images_total: usize, _scales_total: usize) {}
+
+    /// Mark some images and scales as done and update the progress.
+    fn advance_progress(&self, _images_done: usize, _scales_done: usize) {}
+
+    /// Remove the progress from the output.
+    fn finish_progress(&self) {}
 }
 
 /// `Renderer` uses terminal for beautyful formatted color output.
 ///
-/// Maybe in a future will handle some view state but now it's stateless.
+/// The only view state is the progress bar, which is shared by all renderers,
+/// see [Renderer::start_progress].
+/// Every call holds the stdout lock until the output is flushed, so the `Renderer`
+/// can be safely shared between threads.
 ///
 /// Use default constructor to create `Renderer`.
-/// ```rust
+/// ```ignore
 /// let renderer = Renderer();
 /// ```
 ///
@@ -49,23 +121,346 @@ pub trait Renderable {
 pub struct Renderer();
 
 impl Renderer {
-    pub fn render<V>(&self, view: V)
-    where
-        V: Renderable,
-    {
-        let mut stdout = stdout();
-        stdout.queue(cursor::MoveToPreviousLine(1u16)).unwrap();
+    /// Show only important views, see [Renderable::is_important], for all renderers.
+    /// They are printed line by line without the progress bar, e.g. for CI logs.
+    pub fn set_quiet(quiet: bool) {
+        QUIET.store(quiet, Ordering::Relaxed);
+    }
+
+    fn is_quiet() -> bool {
+        QUIET.load(Ordering::Relaxed)
+    }
+}
+
+impl Render for Renderer {
+    fn render(&self, view: &dyn Renderable) {
+        let mut stdout = stdout().lock();
+        if Renderer::is_quiet() {
+            if view.is_important() {
+                stdout.write_all(view.render().as_bytes()).unwrap();
+                stdout.write_all(b"\n").unwrap();
+                stdout.flush().unwrap();
+            }
+            return;
+        }
+        let progress = *PROGRESS.lock().unwrap();
+        let bar = progress.filter(|p| p.bar);
+        // The progress bar is always the last line, so the previous line is above it
+        let lines_up = if bar.is_some() { 2u16 } else { 1u16 };
+        stdout.queue(cursor::MoveToPreviousLine(lines_up)).unwrap();
         stdout
             .queue(terminal::Clear(ClearType::CurrentLine))
             .unwrap();
-        stdout.write(view.render().as_bytes()).unwrap();
-        stdout.write(b"\n").unwrap();
+        stdout.write_all(view.render().as_bytes()).unwrap();
+        stdout.write_all(b"\n").unwrap();
+        if let Some(progress) = bar {
+            stdout
+                .queue(terminal::Clear(ClearType::CurrentLine))
+                .unwrap();
+            stdout.write_all(progress.render().as_bytes()).unwrap();
+            stdout.write_all(b"\n").unwrap();
+        }
         stdout.flush().unwrap();
     }
 
-    pub fn new_line(&self) {
-        let mut stdout = stdout();
-        stdout.write(b"\n").unwrap();
+    /// Even if the view is rendered from another thread, the next view will not overwrite it.
+    fn render_line(&self, view: &dyn Renderable) {
+        // Stdout lock is reentrant, so it is ok to take it here once more
+        let _lock = stdout().lock();
+        self.render(view);
+        self.new_line();
+    }
+
+    fn render_lines(&self, views: &[&dyn Renderable]) {
+        let _lock = stdout().lock();
+        for view in views {
+            self.render_line(*view);
+        }
+    }
+
+    fn new_line(&self) {
+        if Renderer::is_quiet() {
+            return;
+        }
+        let mut stdout = stdout().lock();
+        stdout.write_all(b"\n").unwrap();
+        stdout.flush().unwrap();
+    }
+
+    /// If stdout isn't a terminal, the progress is shown as plain lines every time
+    /// an image is done. Otherwise, it is the bar at the bottom of the output.
+    fn start_progress(&self, images_total: usize, scales_total: usize) {
+        if Renderer::is_quiet() {
+            return;
+        }
+        let mut stdout = stdout().lock();
+        let progress = Progress {
+            images_done: 0,
+            images_total,
+            scales_done: 0,
+            scales_total,
+            bar: stdout.is_terminal(),
+        };
+        if progress.bar {
+            stdout.write_all(progress.render().as_bytes()).unwrap();
+            stdout.write_all(b"\n").unwrap();
+            stdout.flush().unwrap();
+        }
+        *PROGRESS.lock().unwrap() = Some(progress);
+    }
+
+    fn advance_progress(&self, images_done: usize, scales_done: usize) {
+        let mut stdout = stdout().lock();
+        let progress = match PROGRESS.lock().unwrap().as_mut() {
+            Some(progress) => {
+                progress.images_done += images_done;
+                progress.scales_done += scales_done;
+                *progress
+            }
+            None => return,
+        };
+        if progress.bar {
+            stdout.queue(cursor::MoveToPreviousLine(1u16)).unwrap();
+            stdout
+                .queue(terminal::Clear(ClearType::CurrentLine))
+                .unwrap();
+            stdout.write_all(progress.render().as_bytes()).unwrap();
+            stdout.write_all(b"\n").unwrap();
+        } else if images_done > 0 {
+            stdout.write_all(progress.render().as_bytes()).unwrap();
+            stdout.write_all(b"\n").unwrap();
+        }
         stdout.flush().unwrap();
     }
+
+    fn finish_progress(&self) {
+        let mut stdout = stdout().lock();
+        if let Some(Progress { bar: true, .. }) = PROGRESS.lock().unwrap().take() {
+            stdout.queue(cursor::MoveToPreviousLine(1u16)).unwrap();
+            stdout
+                .queue(terminal::Clear(ClearType::CurrentLine))
+                .unwrap();
+            stdout.flush().unwrap();
+        }
+    }
+}
+
+/// `BufferingRenderer` doesn't print anything, it records the rendered views instead,
+/// so the output can be checked in tests.
+/// ```ignore
+/// let renderer = BufferingRenderer::default();
+/// renderer.render(&View::Done { message: None });
+/// assert_eq!(renderer.views(), vec![View::Done { message: None }.render()]);
+/// ```
+#[derive(Default)]
+pub struct BufferingRenderer {
+    views: Mutex<Vec<String>>,
+}
+
+impl BufferingRenderer {
+    /// Rendered views in the order they were rendered.
+    pub fn views(&self) -> Vec<String> {
+        self.views.lock().unwrap().clone()
+    }
+}
+
+impl Render for BufferingRenderer {
+    fn render(&self, view: &dyn Renderable) {
+        self.views.lock().unwrap().push(view.render());
+    }
+
+    fn new_line(&self) {}
+}
+
+/// `WarningCounter` shows the views with another renderer and counts the warnings among them,
+/// see [Renderable::is_warning].
+/// ```ignore
+/// let counter = WarningCounter::new(&Renderer());
+/// counter.render_line(&View::Warning("Something is wrong".to_string()));
+/// assert_eq!(counter.count(), 1);
+/// ```
+pub struct WarningCounter<'a> {
+    renderer: &'a dyn Render,
+    count: AtomicUsize,
+}
+
+impl<'a> WarningCounter<'a> {
+    pub fn new(renderer: &'a dyn Render) -> Self {
+        WarningCounter {
+            renderer,
+            count: AtomicUsize::new(0),
+        }
+    }
+
+    /// Number of the warnings rendered so far.
+    pub fn count(&self) -> usize {
+        self.count.load(Ordering::Relaxed)
+    }
+
+    fn count_view(&self, view: &dyn Renderable) {
+        if view.is_warning() {
+            self.count.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+}
+
+impl Render for WarningCounter<'_> {
+    fn render(&self, view: &dyn Renderable) {
+        self.count_view(view);
+        self.renderer.render(view);
+    }
+
+    fn new_line(&self) {
+        self.renderer.new_line();
+    }
+
+    fn render_line(&self, view: &dyn Renderable) {
+        self.count_view(view);
+        self.renderer.render_line(view);
+    }
+
+    fn render_lines(&self, views: &[&dyn Renderable]) {
+        for view in views {
+            self.count_view(*view);
+        }
+        self.renderer.render_lines(views);
+    }
+
+    fn start_progress(&self, images_total: usize, scales_total: usize) {
+        self.renderer.start_progress(images_total, scales_total);
+    }
+
+    fn advance_progress(&self, images_done: usize, scales_done: usize) {
+        self.renderer.advance_progress(images_done, scales_done);
+    }
+
+    fn finish_progress(&self) {
+        self.renderer.finish_progress();
+    }
+}
+
+/// `GroupingRenderer` holds back the lines of one group of views, e.g. of one image exported
+/// in parallel with others, and shows them together with another renderer on
+/// [GroupingRenderer::flush], so the lines of different groups don't interleave.
+/// Views rendered in place of the previous line are shown at once, they are overwritten anyway.
+/// ```ignore
+/// let grouping = GroupingRenderer::new(&Renderer());
+/// grouping.render_line(&View::Info("First".to_string()));
+/// grouping.render_line(&View::Info("Second".to_string()));
+/// grouping.flush();
+/// ```
+pub struct GroupingRenderer<'a> {
+    renderer: &'a dyn Render,
+    lines: Mutex<Vec<RenderedView>>,
+}
+
+/// The view rendered in advance, which keeps the importance of the original view.
+struct RenderedView {
+    text: String,
+    important: bool,
+    warning: bool,
+}
+
+impl<'a> GroupingRenderer<'a> {
+    pub fn new(renderer: &'a dyn Render) -> Self {
+        GroupingRenderer {
+            renderer,
+            lines: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Show the held back lines with the other renderer.
+    pub fn flush(&self) {
+        let lines = std::mem::take(&mut *self.lines.lock().unwrap());
+        if lines.is_empty() {
+            return;
+        }
+        let views = lines
+            .iter()
+            .map(|line| line as &dyn Renderable)
+            .collect::<Vec<&dyn Renderable>>();
+        self.renderer.render_lines(&views);
+    }
+}
+
+impl Render for GroupingRenderer<'_> {
+    fn render(&self, view: &dyn Renderable) {
+        self.renderer.render(view);
+    }
+
+    fn new_line(&self) {}
+
+    fn render_line(&self, view: &dyn Renderable) {
+        self.lines.lock().unwrap().push(RenderedView {
+            text: view.render(),
+            important: view.is_important(),
+            warning: view.is_warning(),
+        });
+    }
+
+    fn start_progress(&self, images_total: usize, scales_total: usize) {
+        self.renderer.start_progress(images_total, scales_total);
+    }
+
+    fn advance_progress(&self, images_done: usize, scales_done: usize) {
+        self.renderer.advance_progress(images_done, scales_done);
+    }
+
+    fn finish_progress(&self) {
+        self.renderer.finish_progress();
+    }
+}
+
+impl Renderable for RenderedView {
+    fn render(&self) -> String {
+        self.text.clone()
+    }
+
+    fn is_important(&self) -> bool {
+        self.important
+    }
+
+    fn is_warning(&self) -> bool {
+        self.warning
+    }
+}
+
+impl Renderable for Progress {
+    fn render(&self) -> String {
+        let counters = format!(
+            "{}/{} images, {}/{} scales",
+            self.images_done, self.images_total, self.scales_done, self.scales_total,
+        );
+        if !self.bar {
+            return format!("{} {}", "Progress".indent().bold().cyan(), counters);
+        }
+        let filled = match self.scales_total {
+            0 => PROGRESS_BAR_WIDTH,
+            total => PROGRESS_BAR_WIDTH * self.scales_done.min(total) / total,
+        };
+        format!(
+            "{} [{}{}] {}",
+            "Progress".indent().bold().cyan(),
+            "=".repeat(filled),
+            " ".repeat(PROGRESS_BAR_WIDTH - filled),
+            counters,
+        )
+    }
+}
+
+#[test]
+fn test_grouping_renderer_holds_lines_until_flush() {
+    let buffering = BufferingRenderer::default();
+    let grouping = GroupingRenderer::new(&buffering);
+    grouping.render_line(&Progress {
+        images_done: 1,
+        images_total: 2,
+        scales_done: 1,
+        scales_total: 2,
+        bar: false,
+    });
+    assert!(buffering.views().is_empty());
+    grouping.flush();
+    assert_eq!(buffering.views().len(), 1);
+    assert!(buffering.views()[0].contains("1/2 images"));
 }
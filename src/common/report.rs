@@ -0,0 +1,447 @@
+use crate::api::figma::ApiStats;
+use crate::common::error::AppError;
+use serde::Serialize;
+use std::fs::File;
+use std::io::{stdout, BufWriter, Write};
+use std::time::Duration;
+
+/// Path of the report, which means that the report is printed to stdout.
+pub const REPORT_TO_STDOUT: &str = "-";
+
+/// Machine-readable summary of the export run for CI.
+///
+/// Serialized to JSON:
+/// ```json
+/// {
+///   "exported": 1,
+///   "skipped": 0,
+///   "missing": 1,
+///   "failed": 0,
+///   "planned": 0,
+///   "warnings": 2,
+///   "errors": ["Can't write lock file ./.fxn.lock. Cause: ..."],
+///   "stats": {
+///     "downloadUrlRequests": 2,
+///     "imageDownloads": 1,
+///     "downloadedBytes": 4321,
+///     "networkMs": 1830,
+///     "conversionMs": 420,
+///     "ioMs": 3,
+///     "totalMs": 2390
+///   },
+///   "items": [
+///     {
+///       "name": "img_logo",
+///       "scale": "xhdpi",
+///       "path": "./main/res/drawable-xhdpi/img_logo.webp",
+///       "bytes": 1234,
+///       "convertedToWebp": true,
+///       "status": "exported"
+///     },
+///     {
+///       "name": "img_lol",
+///       "convertedToWebp": false,
+///       "status": "missing"
+///     }
+///   ]
+/// }
+/// ```
+#[derive(Debug, Default)]
+pub struct ExportSummary {
+    items: Vec<SummaryItem>,
+    /// Problems which are not related to particular resources, e.g. with the lock file.
+    errors: Vec<String>,
+    webp_savings: WebpSavings,
+    stats: ExportStats,
+    /// Number of warnings shown during the export.
+    warnings: usize,
+}
+
+/// Sizes of the images before and after the conversion to WEBP, to show how much space is saved.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct WebpSavings {
+    pub converted: usize,
+    pub original_bytes: u64,
+    pub converted_bytes: u64,
+    pub duration: Duration,
+}
+
+/// Requests made and time spent by the export, to see where the quota and the time go.
+/// Durations of the images exported in parallel are summed up, so they may be longer
+/// than the wall-clock time of the whole export.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct ExportStats {
+    pub api: ApiStats,
+    /// Time spent converting, optimizing and transforming the downloaded images.
+    pub conversion_time: Duration,
+    /// Time spent writing the images into their final directories.
+    pub io_time: Duration,
+    /// Wall-clock time of the whole export.
+    pub total_time: Duration,
+}
+
+/// What has happened to one variant of the resource, e.g. to one scale of the image.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SummaryItem {
+    /// Name of the resource in Figma.
+    pub name: String,
+    /// Variant of the resource, e.g. `drawable-night-xhdpi`. Missing resources have no variants.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub scale: Option<String>,
+    /// Final path of the exported file.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+    /// Size of the exported file, only for the files written in this run.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub bytes: Option<u64>,
+    pub converted_to_webp: bool,
+    pub status: SummaryStatus,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum SummaryStatus {
+    Exported,
+    Skipped,
+    Missing,
+    Error,
+    /// The resource would be exported without `--dry-run` flag.
+    Planned,
+    /// The file of the resource doesn't exist yet, found with `--diff` flag.
+    Added,
+    /// The file of the resource differs from the exported one, found with `--diff` flag.
+    Changed,
+    /// The file of the resource is the same as the exported one, found with `--diff` flag.
+    Unchanged,
+}
+
+#[derive(Serialize)]
+struct SerializedSummary<'a> {
+    exported: usize,
+    skipped: usize,
+    missing: usize,
+    failed: usize,
+    planned: usize,
+    added: usize,
+    changed: usize,
+    unchanged: usize,
+    warnings: usize,
+    #[serde(skip_serializing_if = "<[String]>::is_empty")]
+    errors: &'a [String],
+    stats: SerializedStats,
+    items: &'a [SummaryItem],
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SerializedStats {
+    download_url_requests: usize,
+    image_downloads: usize,
+    downloaded_bytes: u64,
+    network_ms: u64,
+    conversion_ms: u64,
+    io_ms: u64,
+    total_ms: u64,
+}
+
+impl SummaryItem {
+    /// Item for the resource which is not found in Figma frame.
+    pub fn missing(name: String) -> Self {
+        Self {
+            name,
+            scale: None,
+            path: None,
+            bytes: None,
+            converted_to_webp: false,
+            status: SummaryStatus::Missing,
+            error: None,
+        }
+    }
+}
+
+impl WebpSavings {
+    /// Remember the conversion of one image.
+    pub fn add(&mut self, original_bytes: u64, converted_bytes: u64, duration: Duration) {
+        self.converted += 1;
+        self.original_bytes += original_bytes;
+        self.converted_bytes += converted_bytes;
+        self.duration += duration;
+    }
+
+    /// Describe the savings for the user, e.g. `converted 42 images to webp in 2.3s, saved 3.1 MB`.
+    /// Returns `None` if nothing has been converted.
+    pub fn describe(&self) -> Option<String> {
+        if self.converted == 0 {
+            return None;
+        }
+        let difference = match self.original_bytes.checked_sub(self.converted_bytes) {
+            Some(saved_bytes) => format!("saved {}", format_bytes(saved_bytes)),
+            None => format!(
+                "the images have grown by {}",
+                format_bytes(self.converted_bytes - self.original_bytes)
+            ),
+        };
+        Some(format!(
+            "converted {} images to webp in {:.1}s, {}",
+            self.converted,
+            self.duration.as_secs_f32(),
+            difference,
+        ))
+    }
+}
+
+impl ExportStats {
+    /// Describe the requests and the time for the user, e.g. `3 download url requests,
+    /// 42 images downloaded (3.1 MB); network 4.2s, conversion 1.3s, IO 0.1s, total 5.0s`.
+    /// Returns `None` if no images have been requested.
+    pub fn describe(&self) -> Option<String> {
+        if self.api.download_url_requests == 0 && self.api.image_downloads == 0 {
+            return None;
+        }
+        Some(format!(
+            "{} download url requests, {} images downloaded ({}); network {:.1}s, conversion {:.1}s, IO {:.1}s, total {:.1}s",
+            self.api.download_url_requests,
+            self.api.image_downloads,
+            format_bytes(self.api.downloaded_bytes),
+            self.api.network_time.as_secs_f32(),
+            self.conversion_time.as_secs_f32(),
+            self.io_time.as_secs_f32(),
+            self.total_time.as_secs_f32(),
+        ))
+    }
+
+    fn serialized(&self) -> SerializedStats {
+        let millis = |duration: Duration| duration.as_millis() as u64;
+        SerializedStats {
+            download_url_requests: self.api.download_url_requests,
+            image_downloads: self.api.image_downloads,
+            downloaded_bytes: self.api.downloaded_bytes,
+            network_ms: millis(self.api.network_time),
+            conversion_ms: millis(self.conversion_time),
+            io_ms: millis(self.io_time),
+            total_ms: millis(self.total_time),
+        }
+    }
+}
+
+/// Format the size for the user, e.g. `512 B`, `1.5 KB` or `3.1 MB`.
+pub fn format_bytes(bytes: u64) -> String {
+    const KB: f64 = 1024f64;
+    match bytes as f64 {
+        b if b < KB => format!("{} B", bytes),
+        b if b < KB * KB => format!("{:.1} KB", b / KB),
+        b => format!("{:.1} MB", b / KB / KB),
+    }
+}
+
+impl ExportSummary {
+    pub fn push(&mut self, item: SummaryItem) {
+        self.items.push(item);
+    }
+
+    /// What has happened to every variant of every resource, in the order of the export.
+    /// The terminal summary and the JSON report are made of them, so custom summaries can be too.
+    pub fn items(&self) -> &[SummaryItem] {
+        &self.items
+    }
+
+    /// Problems which are not related to particular resources.
+    pub fn errors(&self) -> &[String] {
+        &self.errors
+    }
+
+    /// Remember the problem which is not related to particular resources.
+    pub fn push_error(&mut self, error: &AppError) {
+        self.errors.push(error.to_string());
+    }
+
+    pub fn set_webp_savings(&mut self, webp_savings: WebpSavings) {
+        self.webp_savings = webp_savings;
+    }
+
+    pub fn set_stats(&mut self, stats: ExportStats) {
+        self.stats = stats;
+    }
+
+    /// Requests made and time spent by the export.
+    pub fn stats(&self) -> &ExportStats {
+        &self.stats
+    }
+
+    pub fn set_warnings(&mut self, warnings: usize) {
+        self.warnings = warnings;
+    }
+
+    /// Number of warnings shown during the export. They don't make the export incomplete.
+    pub fn warnings(&self) -> usize {
+        self.warnings
+    }
+
+    /// Number of the resources with the status.
+    pub fn count(&self, status: SummaryStatus) -> usize {
+        self.items.iter().filter(|i| i.status == status).count()
+    }
+
+    /// Returns `true` if some resources are missing or have failed to export.
+    pub fn has_failures(&self) -> bool {
+        self.count(SummaryStatus::Missing) > 0
+            || self.count(SummaryStatus::Error) > 0
+            || !self.errors.is_empty()
+    }
+
+    /// Describe the numbers of resources for the user, e.g. `2 exported, 1 failed`.
+    /// Numbers of variants are counted, e.g. every scale of the image. The space saved
+    /// by the conversion to WEBP is added, if there are converted images, and so is
+    /// the number of warnings.
+    pub fn describe(&self) -> String {
+        let counts = [
+            (SummaryStatus::Exported, "exported"),
+            (SummaryStatus::Planned, "planned"),
+            (SummaryStatus::Added, "added"),
+            (SummaryStatus::Changed, "changed"),
+            (SummaryStatus::Unchanged, "unchanged"),
+            (SummaryStatus::Skipped, "up to date"),
+            (SummaryStatus::Missing, "missing"),
+            (SummaryStatus::Error, "failed"),
+        ];
+        let description = counts
+            .iter()
+            .map(|(status, label)| (self.count(*status), label))
+            .filter(|(count, _)| *count > 0)
+            .map(|(count, label)| format!("{} {}", count, label))
+            .collect::<Vec<String>>()
+            .join(", ");
+        let description = if description.is_empty() {
+            "nothing to export".to_string()
+        } else {
+            description
+        };
+        let description = match self.webp_savings.describe() {
+            Some(webp_savings) => format!("{}; {}", description, webp_savings),
+            None => description,
+        };
+        match self.warnings {
+            0 => description,
+            1 => format!("{}; 1 warning", description),
+            warnings => format!("{}; {} warnings", description, warnings),
+        }
+    }
+
+    /// Serialize the summary to pretty JSON. Items are sorted by name and scale,
+    /// so the output doesn't depend on the order of parallel exports.
+    pub fn to_json(&self) -> Result<String, serde_json::Error> {
+        let mut items = self.items.clone();
+        items.sort_by(|a, b| (&a.name, &a.scale, &a.path).cmp(&(&b.name, &b.scale, &b.path)));
+        serde_json::to_string_pretty(&SerializedSummary {
+            exported: self.count(SummaryStatus::Exported),
+            skipped: self.count(SummaryStatus::Skipped),
+            missing: self.count(SummaryStatus::Missing),
+            failed: self.count(SummaryStatus::Error),
+            planned: self.count(SummaryStatus::Planned),
+            added: self.count(SummaryStatus::Added),
+            changed: self.count(SummaryStatus::Changed),
+            unchanged: self.count(SummaryStatus::Unchanged),
+            warnings: self.warnings,
+            errors: &self.errors,
+            stats: self.stats.serialized(),
+            items: &items,
+        })
+    }
+
+    /// Write the summary to the file, or to stdout if the `path` is [REPORT_TO_STDOUT].
+    pub fn write(&self, path: &String) -> Result<(), std::io::Error> {
+        let json = self.to_json()?;
+        if path == REPORT_TO_STDOUT {
+            let mut stdout = stdout().lock();
+            writeln!(stdout, "{}", json)?;
+            stdout.flush()
+        } else {
+            let mut writer = BufWriter::new(File::create(path)?);
+            writeln!(writer, "{}", json)?;
+            writer.flush()
+        }
+    }
+}
+
+#[test]
+fn test_summary_counts() {
+    let mut summary = ExportSummary::default();
+    summary.push(SummaryItem::missing("img_b".to_string()));
+    summary.push(SummaryItem {
+        status: SummaryStatus::Exported,
+        ..SummaryItem::missing("img_a".to_string())
+    });
+    let json: serde_json::Value = serde_json::from_str(&summary.to_json().unwrap()).unwrap();
+    assert_eq!(json["exported"], 1);
+    assert_eq!(json["missing"], 1);
+    assert_eq!(json["failed"], 0);
+    assert_eq!(json["items"][0]["name"], "img_a");
+    assert_eq!(json["items"][1]["status"], "missing");
+    assert_eq!(summary.describe(), "1 exported, 1 missing");
+    assert_eq!(summary.items()[0].name, "img_b".to_string());
+    assert!(summary.errors().is_empty());
+    assert!(summary.has_failures());
+    summary.set_warnings(2);
+    assert_eq!(summary.describe(), "1 exported, 1 missing; 2 warnings");
+}
+
+#[test]
+fn test_diff_summary() {
+    let mut summary = ExportSummary::default();
+    for status in [
+        SummaryStatus::Added,
+        SummaryStatus::Changed,
+        SummaryStatus::Unchanged,
+        SummaryStatus::Unchanged,
+    ] {
+        summary.push(SummaryItem {
+            status,
+            ..SummaryItem::missing("img_a".to_string())
+        });
+    }
+    let json: serde_json::Value = serde_json::from_str(&summary.to_json().unwrap()).unwrap();
+    assert_eq!(json["unchanged"], 2);
+    assert_eq!(summary.describe(), "1 added, 1 changed, 2 unchanged");
+    assert!(!summary.has_failures());
+}
+
+#[test]
+fn test_webp_savings() {
+    let mut webp_savings = WebpSavings::default();
+    assert_eq!(webp_savings.describe(), None);
+    webp_savings.add(3000, 1000, Duration::from_millis(1200));
+    webp_savings.add(2u64 << 20, 1000, Duration::from_millis(1100));
+    assert_eq!(
+        webp_savings.describe().unwrap(),
+        "converted 2 images to webp in 2.3s, saved 2.0 MB"
+    );
+    assert_eq!(format_bytes(512), "512 B");
+    assert_eq!(format_bytes(1536), "1.5 KB");
+}
+
+#[test]
+fn test_export_stats() {
+    let mut stats = ExportStats::default();
+    assert_eq!(stats.describe(), None);
+    stats.api = ApiStats {
+        download_url_requests: 2,
+        image_downloads: 3,
+        downloaded_bytes: 3 << 20,
+        network_time: Duration::from_millis(4200),
+    };
+    stats.conversion_time = Duration::from_millis(1300);
+    stats.io_time = Duration::from_millis(40);
+    stats.total_time = Duration::from_millis(5000);
+    assert_eq!(
+        stats.describe().unwrap(),
+        "2 download url requests, 3 images downloaded (3.0 MB); network 4.2s, conversion 1.3s, IO 0.0s, total 5.0s"
+    );
+    let mut summary = ExportSummary::default();
+    summary.set_stats(stats);
+    let json: serde_json::Value = serde_json::from_str(&summary.to_json().unwrap()).unwrap();
+    assert_eq!(json["stats"]["imageDownloads"], 3);
+    assert_eq!(json["stats"]["networkMs"], 4200);
+}
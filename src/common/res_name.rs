@@ -2,7 +2,7 @@
 /// The function turns any name into snake_case.
 ///
 /// # Examples
-/// ```rust
+/// ```ignore
 /// let source_name = "ImgAndroidBanner".to_string();
 /// let res_name = "img_android_banner".to_string();
 /// assert_eq!(to_res_name(&source_name), res_name);
@@ -47,6 +47,72 @@ fn test_camel_case_to_shake_case() {
     assert_eq!(to_res_name(&source_name), res_name);
 }
 
+/// Suffix of Figma names of nine-patch images, e.g. `bg_bubble.9`. It isn't a part of the
+/// resource name, Android recognizes nine-patches by `.9.png` extension.
+pub const NINE_PATCH_SUFFIX: &str = ".9";
+
+/// Returns the name without [NINE_PATCH_SUFFIX], if it is the name of a nine-patch image.
+pub fn strip_nine_patch_suffix(name: &str) -> Option<&str> {
+    name.strip_suffix(NINE_PATCH_SUFFIX)
+        .filter(|name| !name.is_empty())
+}
+
+#[test]
+fn test_strip_nine_patch_suffix() {
+    assert_eq!(strip_nine_patch_suffix("bg_bubble.9"), Some("bg_bubble"));
+    assert_eq!(strip_nine_patch_suffix("bg_bubble_9"), None);
+    assert_eq!(strip_nine_patch_suffix(".9"), None);
+}
+
+/// Returns `true` if the name can be used as a resource name in an android app:
+/// it starts with a lowercase letter and contains only lowercase letters, digits and underscores.
+pub fn is_valid_res_name(name: &str) -> bool {
+    name.starts_with(|ch: char| ch.is_ascii_lowercase())
+        && name
+            .chars()
+            .all(|ch| ch.is_ascii_lowercase() || ch.is_ascii_digit() || ch == '_')
+}
+
+/// Returns `true` if the locale can be used as a qualifier of android res directory,
+/// e.g. `de`, `pt-rBR` or `b+sr+Latn`.
+pub fn is_valid_locale_qualifier(locale: &str) -> bool {
+    let is_language =
+        |s: &str| (2..=3).contains(&s.len()) && s.chars().all(|c| c.is_ascii_lowercase());
+    if let Some(subtags) = locale.strip_prefix("b+") {
+        let mut subtags = subtags.split('+');
+        return subtags.next().is_some_and(is_language)
+            && subtags.all(|s| !s.is_empty() && s.chars().all(|c| c.is_ascii_alphanumeric()));
+    }
+    match locale.split_once("-r") {
+        Some((language, region)) => {
+            is_language(language)
+                && ((region.len() == 2 && region.chars().all(|c| c.is_ascii_uppercase()))
+                    || (region.len() == 3 && region.chars().all(|c| c.is_ascii_digit())))
+        }
+        None => is_language(locale),
+    }
+}
+
+#[test]
+fn test_is_valid_locale_qualifier() {
+    assert!(is_valid_locale_qualifier("de"));
+    assert!(is_valid_locale_qualifier("pt-rBR"));
+    assert!(is_valid_locale_qualifier("es-r419"));
+    assert!(is_valid_locale_qualifier("b+sr+Latn"));
+    assert!(!is_valid_locale_qualifier("DE"));
+    assert!(!is_valid_locale_qualifier("pt-BR"));
+    assert!(!is_valid_locale_qualifier("night"));
+}
+
+#[test]
+fn test_is_valid_res_name() {
+    assert!(is_valid_res_name("ds_img_logo_2"));
+    assert!(!is_valid_res_name("100_img"));
+    assert!(!is_valid_res_name("_img"));
+    assert!(!is_valid_res_name("Img-logo"));
+    assert!(!is_valid_res_name(""));
+}
+
 #[test]
 fn test_non_alphabetic_symbols() {
     let source_name = "ic_36/fingerprint".to_string();
@@ -1,41 +1,119 @@
-use eddie::JaroWinkler;
+use crate::common::res_name::to_res_name;
 use std::cmp::Ordering;
-use std::collections::BTreeSet;
+use strsim::jaro_winkler;
 
+/// Names with lower Jaro-Winkler similarity are never suggested, whatever the threshold is,
+/// because they have almost nothing in common with the desired name.
+const NOISE_SIMILARITY: f64 = 0.3f64;
+
+/// Generate names similar to the desired name from the available names, with their
+/// similarity scores, the most similar first. Returns `None` if there are no such names.
+/// Names are compared as resource names, so case and separators don't affect the similarity,
+/// e.g. `ic/arrow/back` and `Ic Arrow Back` are the same as `ic_arrow_back`.
+///
+/// # Arguments
+///
+/// * `desired_name` - Name which is not found
+/// * `available_names` - Names to look for the similar ones in
+/// * `threshold` - Minimum Jaro-Winkler similarity (from 0.0 to 1.0) of the suggested name.
+///   Names containing the desired name are suggested regardless of the threshold
+/// * `limit` - Maximum number of suggestions, pass 0 to suggest nothing
 pub fn generate_name_suggestions(
     desired_name: &String,
     available_names: &Vec<String>,
-) -> Option<Vec<String>> {
-    let mut output = BTreeSet::new();
-    let jarwin = JaroWinkler::new();
-
-    // First, look for the names that contains desired name
-    for name in available_names {
-        if name.contains(desired_name) {
-            output.insert(name.clone());
-        }
-    }
-
-    // Second, look for the `similar` names
-    let mut similar_names = available_names
+    threshold: f64,
+    limit: usize,
+) -> Option<Vec<(String, f64)>> {
+    let normalized_desired_name = to_res_name(desired_name);
+    let contains_desired_name =
+        |name: &String| to_res_name(name).contains(normalized_desired_name.as_str());
+    let mut output = available_names
         .iter()
-        .map(|n| (n, jarwin.similarity(n, &desired_name)))
-        .collect::<Vec<(&String, f64)>>();
-    similar_names.sort_by(|(_, s1), (_, s2)| s2.partial_cmp(s1).unwrap_or(Ordering::Equal));
-    similar_names.iter().take(5).for_each(|&(name, _)| {
-        output.insert(name.clone());
+        .map(|n| (n, jaro_winkler(&to_res_name(n), &normalized_desired_name)))
+        .filter(|&(name, similarity)| {
+            // Names that contain desired name are suggested first of all
+            contains_desired_name(name) || similarity >= threshold.max(NOISE_SIMILARITY)
+        })
+        .map(|(name, similarity)| (name.clone(), similarity))
+        .collect::<Vec<(String, f64)>>();
+    output.sort_by(|(n1, s1), (n2, s2)| {
+        let (c1, c2) = (contains_desired_name(n1), contains_desired_name(n2));
+        c2.cmp(&c1)
+            .then(s2.partial_cmp(s1).unwrap_or(Ordering::Equal))
+            .then(n1.cmp(n2))
     });
+    output.truncate(limit);
 
     if output.is_empty() {
         None
     } else {
-        // Take only 5 suggestions
-        let mut output = output
-            .iter()
-            .take(5usize)
-            .map(|s| s.clone())
-            .collect::<Vec<String>>();
-        output.sort_by(|a, b| a.cmp(b));
         Some(output)
     }
 }
+
+/// Check if the names differ only by case and separators, e.g. `img_Logo` and `img-logo`.
+/// Such a name is most likely a typo, not a different resource.
+pub fn differs_only_by_case_or_separators(name: &str, other_name: &str) -> bool {
+    let normalize = |name: &str| {
+        name.chars()
+            .filter(|c| !matches!(c, '_' | '-' | ' ' | '.'))
+            .flat_map(char::to_lowercase)
+            .collect::<String>()
+    };
+    normalize(name) == normalize(other_name)
+}
+
+#[test]
+fn test_suggestions_threshold_and_limit() {
+    let available_names = vec![
+        "ic_close".to_string(),
+        "ic_closed".to_string(),
+        "img_banner".to_string(),
+    ];
+    let desired_name = "ic_clse".to_string();
+
+    let suggestions = generate_name_suggestions(&desired_name, &available_names, 0.9, 5);
+    let names = suggestions.map(|s| s.into_iter().map(|(n, _)| n).collect::<Vec<String>>());
+    assert_eq!(
+        names,
+        Some(vec!["ic_close".to_string(), "ic_closed".to_string()])
+    );
+    let suggestions = generate_name_suggestions(&desired_name, &available_names, 0.0, 1);
+    assert_eq!(suggestions.map(|s| s.len()), Some(1));
+    let suggestions = generate_name_suggestions(&desired_name, &available_names, 0.0, 0);
+    assert_eq!(suggestions, None);
+}
+
+#[test]
+fn test_suggestions_noise_is_filtered() {
+    let available_names = vec!["ic_close".to_string(), "zzzzzz".to_string()];
+    let desired_name = "ic_clse".to_string();
+
+    let suggestions = generate_name_suggestions(&desired_name, &available_names, 0.0, 5).unwrap();
+    assert_eq!(suggestions.len(), 1);
+    assert_eq!(suggestions[0].0, "ic_close".to_string());
+    assert!(suggestions[0].1 > 0.9f64);
+}
+
+#[test]
+fn test_suggestions_ignore_separators_and_case() {
+    let available_names = vec![
+        "ic_arrow_black".to_string(),
+        "ic/arrow/back".to_string(),
+        "Arrow Back".to_string(),
+    ];
+    let desired_name = "ic_arrow_back".to_string();
+
+    let suggestions = generate_name_suggestions(&desired_name, &available_names, 0.0, 5).unwrap();
+    assert_eq!(suggestions[0], ("ic/arrow/back".to_string(), 1f64));
+    let suggestions =
+        generate_name_suggestions(&"Arrow-Back".to_string(), &available_names, 0.0, 5);
+    assert_eq!(suggestions.unwrap()[0].0, "Arrow Back".to_string());
+}
+
+#[test]
+fn test_differs_only_by_case_or_separators() {
+    assert!(differs_only_by_case_or_separators("img_Logo", "img-logo"));
+    assert!(differs_only_by_case_or_separators("imgLogo", "img_logo"));
+    assert!(!differs_only_by_case_or_separators("img_logo", "img_logos"));
+}
@@ -0,0 +1,443 @@
+use std::fs;
+
+use roxmltree::{Document, Node, NodeType};
+
+use crate::common::error::AppError;
+
+const SVG_NS: &str = "http://www.w3.org/2000/svg";
+const XLINK_NS: &str = "http://www.w3.org/1999/xlink";
+const XML_NS: &str = "http://www.w3.org/XML/1998/namespace";
+
+/// Attributes with coordinates and sizes, whose numbers are rounded. Transforms and `viewBox`
+/// are left as is, because rounding them may move the whole image.
+const ROUNDED_ATTRIBUTES: &[&str] = &[
+    "d",
+    "points",
+    "x",
+    "y",
+    "x1",
+    "y1",
+    "x2",
+    "y2",
+    "cx",
+    "cy",
+    "fx",
+    "fy",
+    "r",
+    "rx",
+    "ry",
+    "width",
+    "height",
+    "stroke-width",
+];
+
+/// Shapes which are painted with the fill, black by default.
+const FILLED_SHAPES: &[&str] = &[
+    "path", "rect", "circle", "ellipse", "polygon", "polyline", "text",
+];
+
+/// Fill of the shapes without the fill of their own or of their parents.
+const DEFAULT_FILL: &str = "#000000";
+
+/// Minify SVG image in place. Only the changes, which don't affect the rendering, are made:
+///
+/// - comments, `<metadata>` and elements and attributes of editors' namespaces are removed;
+/// - groups without attributes are replaced with their children;
+/// - numbers of coordinates are rounded to `precision` digits after the decimal point;
+/// - whitespace between elements is removed.
+///
+/// # Arguments
+///
+/// * `image_file_name` - Path to the SVG image.
+/// * `precision` - Number of digits after the decimal point to keep in coordinates.
+pub fn optimize_svg(image_file_name: &String, precision: u8) -> Result<(), AppError> {
+    let error = |cause: String| AppError::CannotOptimizeSvg(image_file_name.clone(), cause);
+    let source = fs::read_to_string(image_file_name).map_err(|e| error(e.to_string()))?;
+    let optimized = minify_svg(&source, precision).map_err(|e| error(e.to_string()))?;
+    fs::write(image_file_name, optimized).map_err(|e| error(e.to_string()))
+}
+
+/// Replace every fill and stroke color of the SVG image with `color` in place, so the image
+/// is painted with the single flat color. Images with several colors or gradients are left
+/// as is, because tinting them is destructive. Returns `false` for such images. Shapes without
+/// the fill are black, as SVG renders them, so black is one of the colors of the image then.
+///
+/// # Arguments
+///
+/// * `image_file_name` - Path to the SVG image.
+/// * `color` - Hex color, e.g. `#1C1B1F`.
+pub fn tint_svg(image_file_name: &String, color: &str) -> Result<bool, AppError> {
+    let error = |cause: String| AppError::CannotTintSvg(image_file_name.clone(), cause);
+    let source = fs::read_to_string(image_file_name).map_err(|e| error(e.to_string()))?;
+    match tint_source(&source, color).map_err(|e| error(e.to_string()))? {
+        Some(tinted) => fs::write(image_file_name, tinted)
+            .map(|_| true)
+            .map_err(|e| error(e.to_string())),
+        None => Ok(false),
+    }
+}
+
+/// Check if the value is a valid tint color, `#RGB` or `#RRGGBB`.
+pub fn is_valid_tint(color: &str) -> bool {
+    let digits = color.strip_prefix('#').unwrap_or_default();
+    matches!(digits.len(), 3 | 6) && digits.chars().all(|c| c.is_ascii_hexdigit())
+}
+
+/// Tinted source of the image, or `None` if the image has several colors or gradients.
+fn tint_source(source: &str, color: &str) -> Result<Option<String>, roxmltree::Error> {
+    let document = Document::parse(source)?;
+    if document
+        .descendants()
+        .any(|n| n.has_tag_name("linearGradient") || n.has_tag_name("radialGradient"))
+    {
+        return Ok(None);
+    }
+    // Ranges of the attribute values in the source with their new values
+    let mut replacements = Vec::new();
+    let mut paints = Vec::new();
+    for node in document.descendants().filter(|n| n.is_element()) {
+        for attribute in node.attributes() {
+            match attribute.name() {
+                "fill" | "stroke" | "color" if is_color(attribute.value()) => {
+                    paints.push(normalize_paint(attribute.value()));
+                    replacements.push((attribute.value_range(), escape(color)));
+                }
+                "style" => {
+                    let (style, style_paints) = tint_style(attribute.value(), color);
+                    if !style_paints.is_empty() {
+                        paints.extend(style_paints);
+                        replacements.push((attribute.value_range(), escape(&style)));
+                    }
+                }
+                _ => (),
+            }
+        }
+    }
+    // Shapes without the fill are black, they get the color from the root element then
+    let root = document.root_element();
+    if document.descendants().any(|n| has_default_fill(&n)) {
+        paints.push(DEFAULT_FILL.to_string());
+        match root.attributes().iter().find(|a| a.name() == "fill") {
+            // The root element inherits the fill, e.g. `fill="inherit"`
+            Some(attribute) => replacements.push((attribute.value_range(), escape(color))),
+            None => {
+                let start = root.range().start + 1;
+                let name_end = start
+                    + source[start..]
+                        .find([' ', '\t', '\r', '\n', '/', '>'])
+                        .unwrap_or(0);
+                let fill = format!(" fill=\"{}\"", escape(color));
+                replacements.push((name_end..name_end, fill));
+            }
+        }
+    }
+    paints.sort();
+    paints.dedup();
+    if paints.len() > 1 || paints.iter().any(|p| p.starts_with("url(")) {
+        return Ok(None);
+    }
+    let mut output = source.to_string();
+    // Replace from the end, so the ranges of the rest values stay the same
+    replacements.sort_by_key(|(range, _)| std::cmp::Reverse(range.start));
+    for (range, value) in replacements {
+        output.replace_range(range, &value);
+    }
+    Ok(Some(output))
+}
+
+/// Replace colors in `fill`, `stroke` and `color` properties of the inline style.
+/// Returns the new style and the replaced colors.
+fn tint_style(style: &str, color: &str) -> (String, Vec<String>) {
+    let mut paints = Vec::new();
+    let declarations = style
+        .split(';')
+        .map(|declaration| match declaration.split_once(':') {
+            Some((property, value))
+                if matches!(property.trim(), "fill" | "stroke" | "color") && is_color(value) =>
+            {
+                paints.push(normalize_paint(value));
+                format!("{}:{}", property, color)
+            }
+            _ => declaration.to_string(),
+        })
+        .collect::<Vec<String>>();
+    (declarations.join(";"), paints)
+}
+
+/// Returns `true` if the element is a shape painted with [DEFAULT_FILL], because neither it
+/// nor its parents specify the fill, or they only inherit it. Shapes of clip paths and masks
+/// are not painted.
+fn has_default_fill(node: &Node) -> bool {
+    if !node.is_element() || !FILLED_SHAPES.contains(&node.tag_name().name()) {
+        return false;
+    }
+    let mut ancestors = node.ancestors().filter(|n| n.is_element());
+    !ancestors
+        .clone()
+        .any(|n| n.has_tag_name("clipPath") || n.has_tag_name("mask"))
+        && ancestors
+            .find_map(|n| own_fill(&n).filter(|fill| !fill.eq_ignore_ascii_case("inherit")))
+            .is_none()
+}
+
+/// Fill of the element from its inline style or its attribute, if it is specified.
+fn own_fill<'a>(node: &Node<'a, '_>) -> Option<&'a str> {
+    let style_fill = node.attribute("style").and_then(|style| {
+        style
+            .split(';')
+            .rev()
+            .filter_map(|declaration| declaration.split_once(':'))
+            .find(|(property, _)| property.trim() == "fill")
+            .map(|(_, value)| value.trim())
+    });
+    style_fill.or_else(|| node.attribute("fill").map(str::trim))
+}
+
+/// Paint in lowercase, with the same value for all spellings of black, so they are one color.
+fn normalize_paint(paint: &str) -> String {
+    match paint.trim().to_lowercase().as_str() {
+        "black" | "#000" => DEFAULT_FILL.to_string(),
+        paint => paint.to_string(),
+    }
+}
+
+/// Paint values which are not colors: nothing is painted, or the color is inherited.
+fn is_color(paint: &str) -> bool {
+    !matches!(
+        paint.trim().to_lowercase().as_str(),
+        "none" | "transparent" | "inherit" | "currentcolor"
+    )
+}
+
+fn minify_svg(source: &str, precision: u8) -> Result<String, roxmltree::Error> {
+    let document = Document::parse(source)?;
+    let root = document.root_element();
+    let uses_xlink = root.descendants().any(|node| {
+        node.attributes()
+            .iter()
+            .any(|a| a.namespace() == Some(XLINK_NS))
+    });
+    let mut output = String::with_capacity(source.len());
+    write_element(&root, true, uses_xlink, precision, &mut output);
+    output.push('\n');
+    Ok(output)
+}
+
+fn write_element(node: &Node, is_root: bool, uses_xlink: bool, precision: u8, output: &mut String) {
+    let name = node.tag_name().name();
+    output.push('<');
+    output.push_str(name);
+    if is_root {
+        output.push_str(&format!(" xmlns=\"{}\"", SVG_NS));
+        if uses_xlink {
+            output.push_str(&format!(" xmlns:xlink=\"{}\"", XLINK_NS));
+        }
+    }
+    for attribute in node.attributes() {
+        let prefix = match attribute.namespace() {
+            None => "",
+            Some(XLINK_NS) => "xlink:",
+            Some(XML_NS) => "xml:",
+            // Attributes of the editors, e.g. `inkscape:label`
+            Some(_) => continue,
+        };
+        let value = if ROUNDED_ATTRIBUTES.contains(&attribute.name()) {
+            round_numbers(attribute.value(), precision)
+        } else {
+            attribute.value().to_string()
+        };
+        output.push_str(&format!(
+            " {}{}=\"{}\"",
+            prefix,
+            attribute.name(),
+            escape(&value)
+        ));
+    }
+    let mut content = String::new();
+    write_children(node, uses_xlink, precision, &mut content);
+    if content.is_empty() {
+        output.push_str("/>");
+    } else {
+        output.push('>');
+        output.push_str(&content);
+        output.push_str(&format!("</{}>", name));
+    }
+}
+
+fn write_children(node: &Node, uses_xlink: bool, precision: u8, output: &mut String) {
+    for child in node.children() {
+        match child.node_type() {
+            NodeType::Element => {
+                if child.tag_name().namespace() != Some(SVG_NS) || child.has_tag_name("metadata") {
+                    continue;
+                }
+                if child.has_tag_name("g") && child.attributes().is_empty() {
+                    write_children(&child, uses_xlink, precision, output);
+                } else {
+                    write_element(&child, false, uses_xlink, precision, output);
+                }
+            }
+            NodeType::Text => {
+                let text = child.text().unwrap_or_default();
+                if !text.trim().is_empty() {
+                    output.push_str(&escape(text));
+                }
+            }
+            // Comments and processing instructions
+            _ => (),
+        }
+    }
+}
+
+/// Round every number in the value, e.g. in path data, keeping all other characters.
+fn round_numbers(value: &str, precision: u8) -> String {
+    let chars = value.chars().collect::<Vec<char>>();
+    let mut output = String::with_capacity(value.len());
+    let mut i = 0usize;
+    // If the numbers go one after another, like `1.5.5`, they are separated only by the dot
+    let mut after_number = false;
+    while i < chars.len() {
+        let length = number_length(&chars[i..]);
+        if length == 0 {
+            output.push(chars[i]);
+            after_number = false;
+            i += 1;
+            continue;
+        }
+        let number = chars[i..i + length].iter().collect::<String>();
+        let rounded = match number.parse::<f64>() {
+            // Integers are kept as is, e.g. compact arc flags `011`
+            Ok(parsed) if number.contains('.') && !number.contains(['e', 'E']) => {
+                format_number(parsed, precision)
+            }
+            _ => number,
+        };
+        if after_number && !rounded.starts_with('-') {
+            output.push(' ');
+        }
+        output.push_str(&rounded);
+        after_number = true;
+        i += length;
+    }
+    output
+}
+
+/// Length of the number at the start of `chars`, 0 if there is no number. A number can't
+/// contain two dots, so `1.5.5` is `1.5` and `.5`.
+fn number_length(chars: &[char]) -> usize {
+    let mut i = 0usize;
+    if let Some('-' | '+') = chars.first() {
+        i += 1;
+    }
+    let digits_start = i;
+    while i < chars.len() && chars[i].is_ascii_digit() {
+        i += 1;
+    }
+    if i < chars.len() && chars[i] == '.' {
+        i += 1;
+        while i < chars.len() && chars[i].is_ascii_digit() {
+            i += 1;
+        }
+    }
+    let mantissa = &chars[digits_start..i];
+    if !mantissa.iter().any(|c| c.is_ascii_digit()) {
+        return 0;
+    }
+    if i < chars.len() && (chars[i] == 'e' || chars[i] == 'E') {
+        let mut j = i + 1;
+        if j < chars.len() && (chars[j] == '-' || chars[j] == '+') {
+            j += 1;
+        }
+        if j < chars.len() && chars[j].is_ascii_digit() {
+            while j < chars.len() && chars[j].is_ascii_digit() {
+                j += 1;
+            }
+            i = j;
+        }
+    }
+    i
+}
+
+/// Format the number with at most `precision` digits after the decimal point,
+/// without trailing zeros.
+fn format_number(number: f64, precision: u8) -> String {
+    let formatted = format!("{:.*}", precision as usize, number);
+    let formatted = if formatted.contains('.') {
+        formatted.trim_end_matches('0').trim_end_matches('.')
+    } else {
+        &formatted
+    };
+    match formatted {
+        "-0" => "0".to_string(),
+        _ => formatted.to_string(),
+    }
+}
+
+fn escape(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[test]
+fn test_round_numbers() {
+    assert_eq!(
+        round_numbers("M10.123456 -0.0001L1.5.5Z", 3),
+        "M10.123 0L1.5 0.5Z"
+    );
+    assert_eq!(round_numbers("12.34567px", 2), "12.35px");
+    assert_eq!(round_numbers("1.0001.5-1", 2), "1 0.5-1");
+    assert_eq!(round_numbers("a1 1 0 011 1", 2), "a1 1 0 011 1");
+    assert_eq!(round_numbers("1e-5", 2), "1e-5");
+}
+
+#[test]
+fn test_minify_svg() {
+    let source = r##"<?xml version="1.0"?>
+<!-- Generator: Figma -->
+<svg xmlns="http://www.w3.org/2000/svg" xmlns:inkscape="http://www.inkscape.org/namespaces/inkscape" width="24" height="24" viewBox="0 0 24 24" inkscape:version="1.0">
+  <metadata>editor data</metadata>
+  <g>
+    <path d="M1.23456 2L3 4.00001Z" fill="#000"/>
+  </g>
+  <g opacity="0.5"><rect x="0.33333" y="1" width="2" height="2"/></g>
+</svg>"##;
+    assert_eq!(
+        minify_svg(source, 3).unwrap(),
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"24\" height=\"24\" viewBox=\"0 0 24 24\">\
+        <path d=\"M1.235 2L3 4Z\" fill=\"#000\"/>\
+        <g opacity=\"0.5\"><rect x=\"0.333\" y=\"1\" width=\"2\" height=\"2\"/></g></svg>\n"
+    );
+}
+
+#[test]
+fn test_tint_source() {
+    let source = r##"<svg xmlns="http://www.w3.org/2000/svg"><path d="M1 1Z" fill="#1C1B1F"/><path d="M2 2Z" style="fill:#1c1b1f;opacity:0.5" stroke="none"/></svg>"##;
+    assert_eq!(
+        tint_source(source, "#FF0000").unwrap().unwrap(),
+        r##"<svg xmlns="http://www.w3.org/2000/svg"><path d="M1 1Z" fill="#FF0000"/><path d="M2 2Z" style="fill:#FF0000;opacity:0.5" stroke="none"/></svg>"##
+    );
+    let multi_color = r##"<svg xmlns="http://www.w3.org/2000/svg"><path d="M1 1Z" fill="#000"/><path d="M2 2Z" fill="#FFF"/></svg>"##;
+    assert_eq!(tint_source(multi_color, "#FF0000").unwrap(), None);
+    assert!(is_valid_tint("#FF0000"));
+    assert!(!is_valid_tint("FF0000"));
+}
+
+#[test]
+fn test_tint_source_with_default_fill() {
+    // The path without the fill is black, so the image has two colors
+    let default_and_red = r##"<svg xmlns="http://www.w3.org/2000/svg"><path d="M1 1Z" fill="#F00"/><g fill="inherit"><path d="M2 2Z"/></g></svg>"##;
+    assert_eq!(tint_source(default_and_red, "#1C1B1F").unwrap(), None);
+
+    let default_and_black = r##"<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 2 2"><path d="M1 1Z" fill="black"/><path d="M2 2Z"/><clipPath><rect width="1"/></clipPath></svg>"##;
+    assert_eq!(
+        tint_source(default_and_black, "#1C1B1F").unwrap().unwrap(),
+        r##"<svg fill="#1C1B1F" xmlns="http://www.w3.org/2000/svg" viewBox="0 0 2 2"><path d="M1 1Z" fill="#1C1B1F"/><path d="M2 2Z"/><clipPath><rect width="1"/></clipPath></svg>"##
+    );
+
+    // Figma exports images with `fill="none"` on the root, nothing is painted by default then
+    let no_default = r##"<svg xmlns="http://www.w3.org/2000/svg" fill="none"><path d="M1 1Z" fill="#F00"/><path d="M2 2Z" stroke="#F00"/></svg>"##;
+    assert!(tint_source(no_default, "#1C1B1F").unwrap().is_some());
+}
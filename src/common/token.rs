@@ -0,0 +1,156 @@
+use std::env;
+use std::fs;
+
+use super::error::AppError;
+use crate::models::config::AuthType;
+
+/// Environment variable with Figma personal access token.
+pub const TOKEN_ENV_VAR: &str = "FXN_FIGMA_TOKEN";
+
+/// Environment variable with Figma personal access token, supported for backward compatibility.
+pub const LEGACY_TOKEN_ENV_VAR: &str = "FIGMA_PERSONAL_TOKEN";
+
+/// Prefix of OAuth access tokens issued by Figma, personal access tokens start with `figd_`.
+const OAUTH_TOKEN_PREFIX: &str = "figu_";
+
+/// Places to take Figma personal access token from, specified in command line.
+pub struct TokenSource {
+    /// Token passed with `--token` option.
+    pub token: Option<String>,
+    /// Path passed with `--token-file` option.
+    pub token_file: Option<String>,
+}
+
+/// Find Figma personal access token. The token is taken from the first source where it is
+/// specified: `--token` option, [TOKEN_ENV_VAR] (or [LEGACY_TOKEN_ENV_VAR]) environment
+/// variable, `--token-file` option. The token must consist of visible ASCII characters only,
+/// so it can be sent in the http header. The token itself never gets into the error.
+pub fn resolve_token(source: &TokenSource) -> Result<String, AppError> {
+    resolve_token_with_env(source, |name| env::var(name).ok())
+}
+
+fn resolve_token_with_env<E>(source: &TokenSource, env_var: E) -> Result<String, AppError>
+where
+    E: Fn(&str) -> Option<String>,
+{
+    let token = find_token(source, env_var)?;
+    validate_token(&token)?;
+    Ok(token)
+}
+
+/// Check that the token consists of visible ASCII characters only, so it can be sent in
+/// the http header. Returns [AppError::MalformedToken] otherwise.
+pub fn validate_token(token: &str) -> Result<(), AppError> {
+    match token.chars().all(|c| c.is_ascii_graphic()) {
+        true => Ok(()),
+        false => Err(AppError::MalformedToken),
+    }
+}
+
+fn find_token<E>(source: &TokenSource, env_var: E) -> Result<String, AppError>
+where
+    E: Fn(&str) -> Option<String>,
+{
+    let token = source
+        .token
+        .clone()
+        .filter(|token| !token.trim().is_empty())
+        .or_else(|| env_var(TOKEN_ENV_VAR).filter(|token| !token.trim().is_empty()))
+        .or_else(|| env_var(LEGACY_TOKEN_ENV_VAR).filter(|token| !token.trim().is_empty()));
+    match (token, &source.token_file) {
+        (Some(token), _) => Ok(token.trim().to_string()),
+        (None, Some(token_file)) => {
+            let token = fs::read_to_string(token_file)
+                .map_err(|e| AppError::CannotReadTokenFile(token_file.clone(), e.to_string()))?;
+            match token.trim() {
+                "" => Err(AppError::TokenNotFound),
+                token => Ok(token.to_string()),
+            }
+        }
+        (None, None) => Err(AppError::TokenNotFound),
+    }
+}
+
+/// Type of the token to send it to Figma API with, [AuthType::Personal] or [AuthType::OAuth].
+/// For [AuthType::Auto] tokens of unknown format are taken as personal ones, as they used to be.
+pub fn resolve_auth_type(token: &str, auth_type: AuthType) -> AuthType {
+    match auth_type {
+        AuthType::Auto if token.starts_with(OAUTH_TOKEN_PREFIX) => AuthType::OAuth,
+        AuthType::Auto => AuthType::Personal,
+        auth_type => auth_type,
+    }
+}
+
+#[test]
+fn test_token_precedence() {
+    let source = TokenSource {
+        token: Some("cli".to_string()),
+        token_file: Some("/nonexistent/token".to_string()),
+    };
+    let env_var = |name: &str| (name == TOKEN_ENV_VAR).then(|| "env".to_string());
+    assert_eq!(resolve_token_with_env(&source, env_var).unwrap(), "cli");
+
+    let source = TokenSource {
+        token: None,
+        ..source
+    };
+    assert_eq!(resolve_token_with_env(&source, env_var).unwrap(), "env");
+    assert!(matches!(
+        resolve_token_with_env(&source, |_| None),
+        Err(AppError::CannotReadTokenFile(_, _))
+    ));
+
+    let source = TokenSource {
+        token: None,
+        token_file: None,
+    };
+    assert!(matches!(
+        resolve_token_with_env(&source, |_| None),
+        Err(AppError::TokenNotFound)
+    ));
+}
+
+#[test]
+fn test_malformed_token() {
+    let source = |token: &str| TokenSource {
+        token: Some(token.to_string()),
+        token_file: None,
+    };
+    assert_eq!(
+        resolve_token_with_env(&source(" figd_aBcD1234\r\n"), |_| None).unwrap(),
+        "figd_aBcD1234"
+    );
+    for token in [
+        "figd_aBcD\r1234",
+        "figd_aBcD 1234",
+        "figd_aBcD\u{0}1234",
+        "figd_токен",
+    ] {
+        assert!(matches!(
+            resolve_token_with_env(&source(token), |_| None),
+            Err(AppError::MalformedToken)
+        ));
+    }
+}
+
+#[test]
+fn test_resolve_auth_type() {
+    let oauth_token = "figu_aBcD1234";
+    assert_eq!(
+        resolve_auth_type(oauth_token, AuthType::Auto),
+        AuthType::OAuth
+    );
+    assert_eq!(
+        resolve_auth_type("figd_aBcD1234", AuthType::Auto),
+        AuthType::Personal
+    );
+    assert_eq!(
+        resolve_auth_type("12345-7c8a6f1e-4b2d", AuthType::Auto),
+        AuthType::Personal
+    );
+    assert_eq!(
+        resolve_auth_type(oauth_token, AuthType::Personal),
+        AuthType::Personal
+    );
+    assert_eq!(resolve_auth_type("token", AuthType::OAuth), AuthType::OAuth);
+}
@@ -3,6 +3,7 @@ use std::io;
 use thiserror::Error;
 
 #[derive(Debug, Error)]
+#[allow(clippy::enum_variant_names)]
 pub enum VectorDrawableError {
     #[error("Can't read file {0}. Cause: {1}")]
     CannotReadSvg(String, String),
@@ -10,6 +11,9 @@ pub enum VectorDrawableError {
     #[error("Can't parse file {0}. Cause: {1}")]
     CannotParseSvg(String, String),
 
+    #[error("Unsupported svg feature: {0}")]
+    UnsupportedFeature(String),
+
     #[error("Can't write to output xml file")]
     CannotWrite(#[from] io::Error),
 }
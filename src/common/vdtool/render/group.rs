@@ -0,0 +1,95 @@
+use std::io::{BufWriter, Write};
+
+use usvg::{Group, Node, NodeKind, PathData, Units};
+
+use crate::common::vdtool::error::VectorDrawableError;
+use crate::common::vdtool::render::path::write_path_data;
+use crate::common::vdtool::vdtool::{RenderContext, ToVectorDrawable};
+
+impl ToVectorDrawable for Group {
+    fn to_vector_drawable<W>(
+        &self,
+        w: &mut BufWriter<W>,
+        node: Option<&Node>,
+        ctx: &RenderContext,
+    ) -> Result<(), VectorDrawableError>
+    where
+        W: Write,
+    {
+        if !self.filter.is_empty() {
+            return Err(VectorDrawableError::UnsupportedFeature(
+                "filters".to_string(),
+            ));
+        }
+        if self.mask.is_some() {
+            return Err(VectorDrawableError::UnsupportedFeature("masks".to_string()));
+        }
+
+        // Vector drawable groups can't be skewed, so apply the transform directly to the paths
+        let mut transform = ctx.transform;
+        transform.append(&self.transform);
+        let clip_path_data = match &self.clip_path {
+            Some(id) => Some(resolve_clip_path(ctx, id, &transform)?),
+            None => None,
+        };
+        let children_indent = match clip_path_data {
+            Some(_) => ctx.indent + 4,
+            None => ctx.indent,
+        };
+        let children_ctx = RenderContext {
+            tree: ctx.tree,
+            transform,
+            // Vector drawable groups have no alpha, so apply the opacity to the paths
+            opacity: ctx.opacity * self.opacity.value(),
+            indent: children_indent,
+        };
+
+        if let Some(clip_path_data) = &clip_path_data {
+            writeln!(w, "{:s$}<group>", "", s = ctx.indent)?;
+            writeln!(w, "{:s$}<clip-path", "", s = children_indent)?;
+            write_path_data(w, clip_path_data, children_indent + 8)?;
+            writeln!(w, " />\n")?;
+        }
+        for child in node.unwrap().children() {
+            child.to_vector_drawable(w, None, &children_ctx)?;
+        }
+        if clip_path_data.is_some() {
+            writeln!(w, "{:s$}</group>\n", "", s = ctx.indent)?;
+        }
+        Ok(())
+    }
+}
+
+/// Returns the data of the clip path with the transform applied. Only basic clip paths
+/// are supported: consisting only of paths in user space and not clipped themselves.
+fn resolve_clip_path(
+    ctx: &RenderContext,
+    id: &str,
+    transform: &usvg::Transform,
+) -> Result<PathData, VectorDrawableError> {
+    let unsupported = || VectorDrawableError::UnsupportedFeature(format!("clip path `{}`", id));
+    let clip_node = ctx.tree.defs_by_id(id).ok_or_else(unsupported)?;
+    let clip_path = match &*clip_node.borrow() {
+        NodeKind::ClipPath(clip_path) => clip_path.clone(),
+        _ => return Err(unsupported()),
+    };
+    if clip_path.units != Units::UserSpaceOnUse || clip_path.clip_path.is_some() {
+        return Err(unsupported());
+    }
+
+    let mut clip_path_data = PathData::new();
+    for child in clip_node.children() {
+        match &*child.borrow() {
+            NodeKind::Path(path) => {
+                let mut ts = *transform;
+                ts.append(&clip_path.transform);
+                ts.append(&path.transform);
+                let mut data = (*path.data).clone();
+                data.transform(ts);
+                clip_path_data.extend_from_slice(&data);
+            }
+            _ => return Err(unsupported()),
+        }
+    }
+    Ok(clip_path_data)
+}
@@ -1,107 +1,290 @@
 use std::io::BufWriter;
 use std::io::Write;
 
-use usvg::PathSegment;
-use usvg::{Color, FillRule, Paint, Path, PathData};
+use usvg::{BaseGradient, PathSegment, SpreadMethod, Units};
+use usvg::{Color, FillRule, NodeKind, Paint, Path, PathData, Transform, Visibility};
 
-use crate::common::vdtool::vdtool::ToVectorDrawable;
+use crate::common::vdtool::error::VectorDrawableError;
+use crate::common::vdtool::vdtool::{RenderContext, ToVectorDrawable};
+
+/// Gradient to render inside the `<path>` tag with `aapt:attr`.
+struct GradientAttr {
+    attr_name: &'static str,
+    gradient: Gradient,
+}
+
+enum Gradient {
+    Linear {
+        start: (f64, f64),
+        end: (f64, f64),
+        base: BaseGradient,
+    },
+    Radial {
+        center: (f64, f64),
+        radius: f64,
+        base: BaseGradient,
+    },
+}
 
 impl ToVectorDrawable for Path {
     fn to_vector_drawable<W>(
         &self,
         w: &mut BufWriter<W>,
         _: Option<&usvg::Node>,
-    ) -> Result<(), std::io::Error>
+        ctx: &RenderContext,
+    ) -> Result<(), VectorDrawableError>
     where
         W: Write,
     {
-        writeln!(w, "{:s$}<path", "", s = 4)?;
+        if self.visibility != Visibility::Visible {
+            return Ok(());
+        }
+        let indent = ctx.indent;
+        let attr_indent = ctx.indent + 8;
+        let mut transform = ctx.transform;
+        transform.append(&self.transform);
+        let mut gradients: Vec<GradientAttr> = Vec::new();
+
+        writeln!(w, "{:s$}<path", "", s = indent)?;
 
         // Add provided stroke params or default
         if let Some(stroke) = &self.stroke {
             // Add strokeWidth anyway
-            write_stroke_width(w, stroke.width.value())?;
+            write_attr(
+                w,
+                attr_indent,
+                "strokeWidth",
+                stroke.width.value() * scale(&transform),
+            )?;
 
             // Add strokeColor anyway
-            write_stroke_color(w, &stroke.paint)?;
+            match resolve_paint(ctx, &stroke.paint, &self.data, &transform)? {
+                PaintValue::Color(color) => write_attr(w, attr_indent, "strokeColor", color)?,
+                PaintValue::Gradient(gradient) => gradients.push(GradientAttr {
+                    attr_name: "strokeColor",
+                    gradient,
+                }),
+            }
 
             // Add strokeAlpha only if it differs from 1.0
-            let stroke_alpha = stroke.opacity.value();
+            let stroke_alpha = stroke.opacity.value() * ctx.opacity;
             if stroke_alpha != 1f64 {
-                write_stroke_alpha(w, stroke_alpha)?;
+                write_attr(w, attr_indent, "strokeAlpha", stroke_alpha)?;
             }
 
             // TODO: add strokeLineCap, strokeLineJoin, strokeMiterLimit
         } else {
-            write_stroke_width(w, 1f64)?;
+            write_attr(w, attr_indent, "strokeWidth", 1f64)?;
         }
 
-        // Add provided fill params of default
+        // Add provided fill params, there is no fill if it is `none` in svg
         if let Some(fill) = &self.fill {
             // Add fillColor anyway
-            write_fill_color(w, &fill.paint)?;
+            match resolve_paint(ctx, &fill.paint, &self.data, &transform)? {
+                PaintValue::Color(color) => write_attr(w, attr_indent, "fillColor", color)?,
+                PaintValue::Gradient(gradient) => gradients.push(GradientAttr {
+                    attr_name: "fillColor",
+                    gradient,
+                }),
+            }
 
             // Add fillAlpha only if it differs from 1.0
-            let fill_alpha = fill.opacity.value();
+            let fill_alpha = fill.opacity.value() * ctx.opacity;
             if fill_alpha != 1f64 {
-                write_fill_alpha(w, fill_alpha)?;
+                write_attr(w, attr_indent, "fillAlpha", fill_alpha)?;
             }
 
             // Add fillType only if it differs from nonZero
-            match fill.rule {
-                FillRule::EvenOdd => write_fill_type(w, "evenOdd")?,
-                _ => (),
+            if fill.rule == FillRule::EvenOdd {
+                write_attr(w, attr_indent, "fillType", "evenOdd")?;
             };
-        } else {
-            write_fill_color(w, &Paint::Color(Color::black()))?;
         }
 
         // Add pathData
-        write_path_data(w, &self.data)?;
+        let mut data = (*self.data).clone();
+        data.transform(transform);
+        write_path_data(w, &data, attr_indent)?;
 
         // Close tag
-        writeln!(w, " />\n")
+        if gradients.is_empty() {
+            writeln!(w, " />\n")?;
+        } else {
+            writeln!(w, ">")?;
+            for gradient in &gradients {
+                write_gradient(w, gradient, indent + 4)?;
+            }
+            writeln!(w, "{:s$}</path>\n", "", s = indent)?;
+        }
+        Ok(())
     }
 }
 
-fn write_stroke_width<W: Write>(w: &mut BufWriter<W>, value: f64) -> Result<(), std::io::Error> {
-    writeln!(w, "{:s$}android:strokeWidth=\"{:.}\"", "", value, s = 12)
+enum PaintValue {
+    Color(String),
+    Gradient(Gradient),
 }
 
-fn write_stroke_alpha<W: Write>(w: &mut BufWriter<W>, value: f64) -> Result<(), std::io::Error> {
-    writeln!(w, "{:s$}android:strokeAlpha=\"{:.}\"", "", value, s = 12)
+/// Resolve the paint to the color or to the gradient in the coordinates of the viewport.
+fn resolve_paint(
+    ctx: &RenderContext,
+    paint: &Paint,
+    data: &PathData,
+    transform: &Transform,
+) -> Result<PaintValue, VectorDrawableError> {
+    let id = match paint {
+        Paint::Color(rgb_color) => return Ok(PaintValue::Color(rgb2hex(rgb_color))),
+        Paint::Link(id) => id,
+    };
+    let paint_node = ctx
+        .tree
+        .defs_by_id(id)
+        .ok_or_else(|| VectorDrawableError::UnsupportedFeature(format!("paint `{}`", id)))?;
+    let paint_node = paint_node.borrow();
+    let base = match &*paint_node {
+        NodeKind::LinearGradient(g) => &g.base,
+        NodeKind::RadialGradient(g) => &g.base,
+        _ => {
+            return Err(VectorDrawableError::UnsupportedFeature(
+                "pattern fills".to_string(),
+            ))
+        }
+    };
+
+    // Gradient coordinates are transformed by the path and gradient transforms
+    let mut ts = *transform;
+    if base.units == Units::ObjectBoundingBox {
+        if let Some(bbox) = data.bbox() {
+            ts.append(&Transform::new(
+                bbox.width(),
+                0f64,
+                0f64,
+                bbox.height(),
+                bbox.x(),
+                bbox.y(),
+            ));
+        }
+    }
+    ts.append(&base.transform);
+
+    match &*paint_node {
+        NodeKind::LinearGradient(g) => Ok(PaintValue::Gradient(Gradient::Linear {
+            start: ts.apply(g.x1, g.y1),
+            end: ts.apply(g.x2, g.y2),
+            base: g.base.clone(),
+        })),
+        NodeKind::RadialGradient(g) => {
+            if g.fx != g.cx || g.fy != g.cy {
+                return Err(VectorDrawableError::UnsupportedFeature(
+                    "radial gradients with a focal point".to_string(),
+                ));
+            }
+            Ok(PaintValue::Gradient(Gradient::Radial {
+                center: ts.apply(g.cx, g.cy),
+                radius: g.r.value() * scale(&ts),
+                base: g.base.clone(),
+            }))
+        }
+        _ => unreachable!("Checked above"),
+    }
 }
 
-fn rgb2hex(color: &Color) -> String {
-    format!("#{:02X}{:02X}{:02X}", color.red, color.green, color.blue)
+/// Uniform scale factor of the transform, used for lengths like stroke width.
+fn scale(transform: &Transform) -> f64 {
+    (transform.a * transform.d - transform.b * transform.c)
+        .abs()
+        .sqrt()
 }
 
-fn write_stroke_color<W: Write>(w: &mut BufWriter<W>, value: &Paint) -> Result<(), std::io::Error> {
-    let value = match value {
-        Paint::Color(rgb_color) => rgb2hex(rgb_color),
-        _ => "#ff000000".to_string(),
-    };
-    writeln!(w, "{:s$}android:strokeColor=\"{}\"", "", value, s = 12)
+fn write_attr<W: Write, V: std::fmt::Display>(
+    w: &mut BufWriter<W>,
+    indent: usize,
+    name: &str,
+    value: V,
+) -> Result<(), std::io::Error> {
+    writeln!(w, "{:s$}android:{}=\"{:.}\"", "", name, value, s = indent)
 }
 
-fn write_fill_color<W: Write>(w: &mut BufWriter<W>, value: &Paint) -> Result<(), std::io::Error> {
-    let value = match value {
-        Paint::Color(rgb_color) => rgb2hex(rgb_color),
-        _ => "#ff000000".to_string(),
-    };
-    writeln!(w, "{:s$}android:fillColor=\"{}\"", "", value, s = 12)
+fn rgb2hex(color: &Color) -> String {
+    format!("#{:02X}{:02X}{:02X}", color.red, color.green, color.blue)
 }
 
-fn write_fill_alpha<W: Write>(w: &mut BufWriter<W>, value: f64) -> Result<(), std::io::Error> {
-    writeln!(w, "{:s$}android:fillAlpha=\"{:.}\"", "", value, s = 12)
+fn argb2hex(color: &Color, alpha: f64) -> String {
+    format!(
+        "#{:02X}{:02X}{:02X}{:02X}",
+        (alpha * 255f64).round() as u8,
+        color.red,
+        color.green,
+        color.blue
+    )
 }
 
-fn write_fill_type<W: Write>(w: &mut BufWriter<W>, value: &str) -> Result<(), std::io::Error> {
-    writeln!(w, "{:s$}android:fillType=\"{}\"", "", value, s = 12)
+fn write_gradient<W: Write>(
+    w: &mut BufWriter<W>,
+    gradient: &GradientAttr,
+    indent: usize,
+) -> Result<(), std::io::Error> {
+    writeln!(
+        w,
+        "{:s$}<aapt:attr name=\"android:{}\">",
+        "",
+        gradient.attr_name,
+        s = indent
+    )?;
+    writeln!(w, "{:s$}<gradient", "", s = indent + 4)?;
+    let attr_indent = indent + 12;
+    let base = match &gradient.gradient {
+        Gradient::Linear { start, end, base } => {
+            write_attr(w, attr_indent, "type", "linear")?;
+            write_attr(w, attr_indent, "startX", start.0)?;
+            write_attr(w, attr_indent, "startY", start.1)?;
+            write_attr(w, attr_indent, "endX", end.0)?;
+            write_attr(w, attr_indent, "endY", end.1)?;
+            base
+        }
+        Gradient::Radial {
+            center,
+            radius,
+            base,
+        } => {
+            write_attr(w, attr_indent, "type", "radial")?;
+            write_attr(w, attr_indent, "centerX", center.0)?;
+            write_attr(w, attr_indent, "centerY", center.1)?;
+            write_attr(w, attr_indent, "gradientRadius", radius)?;
+            base
+        }
+    };
+    let tile_mode = match base.spread_method {
+        SpreadMethod::Pad => "clamp",
+        SpreadMethod::Reflect => "mirror",
+        SpreadMethod::Repeat => "repeat",
+    };
+    writeln!(
+        w,
+        "{:s$}android:tileMode=\"{}\">",
+        "",
+        tile_mode,
+        s = attr_indent
+    )?;
+    for stop in &base.stops {
+        writeln!(
+            w,
+            "{:s$}<item android:offset=\"{:.}\" android:color=\"{}\" />",
+            "",
+            stop.offset.value(),
+            argb2hex(&stop.color, stop.opacity.value()),
+            s = indent + 8
+        )?;
+    }
+    writeln!(w, "{:s$}</gradient>", "", s = indent + 4)?;
+    writeln!(w, "{:s$}</aapt:attr>", "", s = indent)
 }
 
-fn write_path_data<W: Write>(w: &mut BufWriter<W>, value: &PathData) -> Result<(), std::io::Error> {
-    write!(w, "{:s$}android:pathData=\"", "", s = 12)?;
+pub fn write_path_data<W: Write>(
+    w: &mut BufWriter<W>,
+    value: &PathData,
+    indent: usize,
+) -> Result<(), std::io::Error> {
+    write!(w, "{:s$}android:pathData=\"", "", s = indent)?;
     for p in &value.0 {
         match p {
             PathSegment::ClosePath => write!(w, "Z")?,
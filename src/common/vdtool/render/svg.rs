@@ -2,22 +2,35 @@ use std::io::{BufWriter, Write};
 
 use usvg::{Node, Svg};
 
-use crate::common::vdtool::vdtool::ToVectorDrawable;
+use crate::common::vdtool::error::VectorDrawableError;
+use crate::common::vdtool::vdtool::{uses_gradients, RenderContext, ToVectorDrawable};
 
 impl ToVectorDrawable for Svg {
     fn to_vector_drawable<W>(
         &self,
         w: &mut BufWriter<W>,
         node: Option<&Node>,
-    ) -> Result<(), std::io::Error>
+        ctx: &RenderContext,
+    ) -> Result<(), VectorDrawableError>
     where
         W: Write,
     {
         // Render header
         writeln!(
             w,
-            "<vector xmlns:android=\"http://schemas.android.com/apk/res/android\"\n\
-            \x20       android:width=\"{:.}dp\"\n\
+            "<vector xmlns:android=\"http://schemas.android.com/apk/res/android\""
+        )?;
+        if uses_gradients(ctx.tree) {
+            writeln!(
+                w,
+                "{:s$}xmlns:aapt=\"http://schemas.android.com/aapt\"",
+                "",
+                s = 8
+            )?;
+        }
+        writeln!(
+            w,
+            "\x20       android:width=\"{:.}dp\"\n\
             \x20       android:height=\"{:.}dp\"\n\
             \x20       android:viewportWidth=\"{:.}\"\n\
             \x20       android:viewportHeight=\"{:.}\">\n",
@@ -28,8 +41,14 @@ impl ToVectorDrawable for Svg {
         )?;
 
         // Render content of every child
+        let children_ctx = RenderContext {
+            tree: ctx.tree,
+            transform: ctx.transform,
+            opacity: ctx.opacity,
+            indent: ctx.indent + 4,
+        };
         for child in node.unwrap().children() {
-            child.to_vector_drawable(w, None)?;
+            child.to_vector_drawable(w, None, &children_ctx)?;
         }
 
         // Render footer
@@ -4,60 +4,140 @@ use std::io::Write;
 use std::path::Path;
 
 use usvg::Node;
+use usvg::NodeKind;
 use usvg::Options;
+use usvg::Paint;
+use usvg::Transform;
 use usvg::Tree;
 
-use crate::common::fileutils::TEMP_DIR_PATH;
 use crate::common::vdtool::error::VectorDrawableError;
 
+/// State passed down the svg tree while it is rendered to vector drawable.
+pub struct RenderContext<'a> {
+    pub tree: &'a Tree,
+    /// Transform accumulated from all parent groups.
+    pub transform: Transform,
+    /// Opacity accumulated from all parent groups.
+    pub opacity: f64,
+    /// Indent of the xml tag of the current node.
+    pub indent: usize,
+}
+
 pub trait ToVectorDrawable {
     fn to_vector_drawable<W>(
         &self,
         w: &mut BufWriter<W>,
         node: Option<&Node>,
-    ) -> Result<(), std::io::Error>
+        ctx: &RenderContext,
+    ) -> Result<(), VectorDrawableError>
     where
         W: Write;
 }
 
 pub fn convert_svg_to_xml(file_path: &String) -> Result<String, VectorDrawableError> {
-    let svg_content = fs::read_to_string(&file_path)
+    let svg_content = fs::read_to_string(file_path)
         .map_err(|e| VectorDrawableError::CannotReadSvg(file_path.clone(), e.to_string()))?;
 
     let svg_tree = Tree::from_str(&svg_content, &Options::default().to_ref())
         .map_err(|e| VectorDrawableError::CannotParseSvg(file_path.clone(), e.to_string()))?;
 
     // Put xml-icon in the location of the original svg icon
-    let original_icon_file_name = Path::new(file_path).file_stem().unwrap().to_str().unwrap();
-    // Make full output path for webp-image.
-    let xml_icon_path = format!("{}/{}.xml", TEMP_DIR_PATH, original_icon_file_name);
+    let xml_icon_path = Path::new(file_path)
+        .with_extension("xml")
+        .to_string_lossy()
+        .to_string();
+    // Render to memory first, so we don't leave broken xml file if the svg is not supported
+    let xml_content = render_vector_drawable(&svg_tree)?;
     // write to file
-    let xml_file = fs::OpenOptions::new()
-        .create(true)
-        .truncate(true)
-        .write(true)
-        .open(&xml_icon_path)
-        .unwrap();
-    let mut writer = BufWriter::new(xml_file);
-    svg_tree.root().to_vector_drawable(&mut writer, None)?;
+    fs::write(&xml_icon_path, xml_content)?;
 
     // return ok
     Ok(xml_icon_path)
 }
 
+fn render_vector_drawable(svg_tree: &Tree) -> Result<Vec<u8>, VectorDrawableError> {
+    let mut writer = BufWriter::new(Vec::new());
+    let ctx = RenderContext {
+        tree: svg_tree,
+        transform: Transform::default(),
+        opacity: 1f64,
+        indent: 0,
+    };
+    svg_tree
+        .root()
+        .to_vector_drawable(&mut writer, None, &ctx)?;
+    Ok(writer.into_inner().map_err(|e| e.into_error())?)
+}
+
+/// Returns true if some path in the tree is painted with a gradient.
+/// Gradients need an additional `aapt` namespace in the vector drawable header.
+pub fn uses_gradients(tree: &Tree) -> bool {
+    tree.root().descendants().any(|node| match &*node.borrow() {
+        NodeKind::Path(path) if !tree.is_in_defs(&node) => {
+            let fill_paint = path.fill.as_ref().map(|f| &f.paint);
+            let stroke_paint = path.stroke.as_ref().map(|s| &s.paint);
+            [fill_paint, stroke_paint]
+                .iter()
+                .any(|paint| matches!(paint, Some(Paint::Link(_))))
+        }
+        _ => false,
+    })
+}
+
 impl ToVectorDrawable for Node {
     fn to_vector_drawable<W>(
         &self,
         w: &mut BufWriter<W>,
         _: Option<&Node>,
-    ) -> Result<(), std::io::Error>
+        ctx: &RenderContext,
+    ) -> Result<(), VectorDrawableError>
     where
         W: Write,
     {
         match &*self.borrow() {
-            usvg::NodeKind::Svg(svg) => svg.to_vector_drawable(w, Some(&self)),
-            usvg::NodeKind::Path(path) => path.to_vector_drawable(w, Some(&self)),
+            NodeKind::Svg(svg) => svg.to_vector_drawable(w, Some(self), ctx),
+            NodeKind::Group(group) => group.to_vector_drawable(w, Some(self), ctx),
+            NodeKind::Path(path) => path.to_vector_drawable(w, Some(self), ctx),
+            NodeKind::Image(_) => Err(VectorDrawableError::UnsupportedFeature(
+                "embedded raster images".to_string(),
+            )),
+            // Gradients and clip paths are rendered where they are referenced
             _ => Ok(()),
         }
     }
 }
+
+#[cfg(test)]
+fn render_svg_str(svg: &str) -> Result<String, VectorDrawableError> {
+    let svg_tree = Tree::from_str(svg, &Options::default().to_ref()).unwrap();
+    Ok(String::from_utf8(render_vector_drawable(&svg_tree)?).unwrap())
+}
+
+#[test]
+fn test_linear_gradient() {
+    let xml = render_svg_str(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"24\" height=\"24\">\
+        <defs><linearGradient id=\"g\" x1=\"0\" y1=\"0\" x2=\"24\" y2=\"0\" \
+        gradientUnits=\"userSpaceOnUse\"><stop offset=\"0\" stop-color=\"#FF0000\"/>\
+        <stop offset=\"1\" stop-color=\"#0000FF\"/></linearGradient></defs>\
+        <rect width=\"24\" height=\"24\" fill=\"url(#g)\"/></svg>",
+    )
+    .unwrap();
+    assert!(xml.contains("xmlns:aapt"));
+    assert!(xml.contains("<aapt:attr name=\"android:fillColor\">"));
+    assert!(xml.contains("android:type=\"linear\""));
+    assert!(xml.contains("android:color=\"#FFFF0000\""));
+}
+
+#[test]
+fn test_unsupported_filter() {
+    let result = render_svg_str(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"24\" height=\"24\">\
+        <defs><filter id=\"f\"><feGaussianBlur stdDeviation=\"2\"/></filter></defs>\
+        <rect width=\"24\" height=\"24\" fill=\"#000000\" filter=\"url(#f)\"/></svg>",
+    );
+    assert!(matches!(
+        result,
+        Err(VectorDrawableError::UnsupportedFeature(_))
+    ));
+}
@@ -1,12 +1,21 @@
 use image::io::Reader as ImageReader;
-use image::EncodableLayout;
+use image::{Rgba, RgbaImage};
+use libwebp_sys::{
+    WebPConfig, WebPConfigInitInternal, WebPEncode, WebPMemoryWrite, WebPMemoryWriter,
+    WebPMemoryWriterClear, WebPMemoryWriterInit, WebPPicture, WebPPictureFree,
+    WebPPictureImportRGBA, WebPPictureInitInternal, WebPPreset, WEBP_ENCODER_ABI_VERSION,
+};
 use webp::Encoder;
 
+use std::collections::VecDeque;
 use std::fs::File;
 use std::io::Write;
+use std::mem::MaybeUninit;
+use std::os::raw::{c_int, c_void};
 use std::path::Path;
 
 use super::error::AppError;
+use crate::models::config::WebpAlpha;
 
 /// Converts PNG image to WEBP. Returns the path to the WEBP image, or an error with a description.
 /// In case of successful conversion, the function will create a WEPB file next to the original PNG file.
@@ -15,9 +24,88 @@ use super::error::AppError;
 ///
 /// * `file_path` - Path to PNG image
 /// * `quality` - Encoding quality in percents (from 0 to 100). Pass 100% for loseless encoding
+/// * `alpha` - How the colors of the transparent pixels are encoded
 ///
 /// Function uses adopted code from: https://users.rust-lang.org/t/converting-png-jpeg-image-to-webp/71080
-pub fn image_to_webp(file_path: &String, quality: f32) -> Result<String, AppError> {
+pub fn image_to_webp(
+    file_path: &String,
+    quality: f32,
+    alpha: WebpAlpha,
+) -> Result<String, AppError> {
+    encode_to_webp(file_path, Some(quality), alpha)
+}
+
+/// Converts PNG image to lossless WEBP. Returns the path to the WEBP image, or an error with
+/// a description. Works the same way as [image_to_webp].
+///
+/// # Arguments
+///
+/// * `file_path` - Path to PNG image
+/// * `alpha` - How the colors of the transparent pixels are encoded
+pub fn image_to_webp_lossless(file_path: &String, alpha: WebpAlpha) -> Result<String, AppError> {
+    encode_to_webp(file_path, None, alpha)
+}
+
+/// Checks that the WEBP encoder works, by encoding a tiny image in memory.
+pub fn is_encoder_available() -> bool {
+    let pixel = [0u8; 4];
+    !Encoder::from_rgba(&pixel, 1, 1).encode(100f32).is_empty()
+}
+
+/// Quality of lossy encoding, below which the images with hard edges are noticeably degraded.
+pub const HARD_EDGES_QUALITY_THRESHOLD: f32 = 90f32;
+
+/// Checks whether the image looks like an icon: mostly flat colors with hard edges.
+/// Lossy WEBP encoding blurs such images, unlike photos and smooth gradients.
+/// Returns `false` if the image can't be read, because the check is advisory only.
+///
+/// # Arguments
+///
+/// * `file_path` - Path to PNG image
+pub fn has_hard_edges(file_path: &String) -> bool {
+    ImageReader::open(file_path)
+        .ok()
+        .and_then(|reader| reader.with_guessed_format().ok())
+        .and_then(|reader| reader.decode().ok())
+        .map(|image| image_has_hard_edges(&image.to_rgba8()))
+        .unwrap_or(false)
+}
+
+/// The image has hard edges if almost all of its pixels are either fully opaque or fully
+/// transparent, and most of the neighbouring pixels have exactly the same color.
+fn image_has_hard_edges(image: &RgbaImage) -> bool {
+    let pixels_count = image.pixels().len();
+    if pixels_count == 0 {
+        return false;
+    }
+    let hard_alpha_count = image
+        .pixels()
+        .filter(|pixel| pixel[3] == 0 || pixel[3] == 255)
+        .count();
+    let (mut pairs_count, mut flat_pairs_count) = (0usize, 0usize);
+    for row in image.rows() {
+        let row = row.collect::<Vec<_>>();
+        for pair in row.windows(2) {
+            pairs_count += 1;
+            if pair[0] == pair[1] {
+                flat_pairs_count += 1;
+            }
+        }
+    }
+    let hard_alpha_ratio = hard_alpha_count as f32 / pixels_count as f32;
+    let flat_ratio = match pairs_count {
+        0 => 0f32,
+        _ => flat_pairs_count as f32 / pairs_count as f32,
+    };
+    hard_alpha_ratio >= 0.9f32 && flat_ratio >= 0.6f32
+}
+
+/// Encodes the image to WEBP next to it, lossless if `quality` is `None`.
+fn encode_to_webp(
+    file_path: &String,
+    quality: Option<f32>,
+    alpha: WebpAlpha,
+) -> Result<String, AppError> {
     let reader = ImageReader::open(file_path)
         .map_err(|_| AppError::SourceNotFound(file_path.clone()))?
         .with_guessed_format()
@@ -27,20 +115,203 @@ pub fn image_to_webp(file_path: &String, quality: f32) -> Result<String, AppErro
         .decode()
         .map_err(|_| AppError::CannotDecode(file_path.clone()))?;
 
-    let encoder =
-        Encoder::from_image(&image).map_err(|_| AppError::CannotEncode(file_path.clone()))?;
-
     // Create webp encoded image in RAM
-    let webp_memory = encoder.encode(quality);
+    let webp_memory = match alpha {
+        WebpAlpha::Default => {
+            let encoder = Encoder::from_image(&image)
+                .map_err(|_| AppError::CannotEncode(file_path.clone()))?;
+            encode_simple(&encoder, quality)
+        }
+        WebpAlpha::Exact => encode_exact(&image.to_rgba8(), quality)
+            .map_err(|e| AppError::CannotEncodeWebp(file_path.clone(), e))?,
+        WebpAlpha::Bleed => {
+            let mut image = image.to_rgba8();
+            bleed_transparent_pixels(&mut image);
+            encode_simple(
+                &Encoder::from_rgba(image.as_raw(), image.width(), image.height()),
+                quality,
+            )
+        }
+    };
     // Put webp-image in the location of the original image
-    let original_image_file_name = Path::new(file_path).file_stem().unwrap().to_str().unwrap();
-    // Make full output path for webp-image
-    let webp_image_path = format!("{}.webp", original_image_file_name);
+    let webp_image_path = Path::new(file_path)
+        .with_extension("webp")
+        .to_string_lossy()
+        .to_string();
 
-    File::create(webp_image_path.to_string())
+    File::create(&webp_image_path)
         .map_err(|e| AppError::WriteWebpTemporarySave(format!("{}", e)))?
-        .write_all(webp_memory.as_bytes())
+        .write_all(&webp_memory)
         .map_err(|e| AppError::WriteWebpTemporarySave(format!("{}", e)))?;
 
     Ok(webp_image_path)
 }
+
+/// Encodes with the default settings of libwebp, lossless if `quality` is `None`.
+fn encode_simple(encoder: &Encoder, quality: Option<f32>) -> Vec<u8> {
+    let webp_memory = match quality {
+        Some(quality) => encoder.encode(quality),
+        None => encoder.encode_lossless(),
+    };
+    webp_memory.to_vec()
+}
+
+/// Encodes like [encode_simple], but with `exact` setting of libwebp, so the colors of fully
+/// transparent pixels are kept. The simple API of libwebp doesn't allow changing the settings.
+fn encode_exact(image: &RgbaImage, quality: Option<f32>) -> Result<Vec<u8>, String> {
+    // Same quality as the simple API of libwebp uses for lossless encoding
+    let quality_factor = quality.unwrap_or(70f32);
+    unsafe {
+        let mut config = MaybeUninit::<WebPConfig>::uninit();
+        let preset = WebPPreset::WEBP_PRESET_DEFAULT;
+        let abi_version = WEBP_ENCODER_ABI_VERSION;
+        if WebPConfigInitInternal(config.as_mut_ptr(), preset, quality_factor, abi_version) == 0 {
+            return Err("incompatible libwebp version".to_string());
+        }
+        let mut config = config.assume_init();
+        config.lossless = quality.is_none() as c_int;
+        config.exact = 1;
+
+        let mut picture = MaybeUninit::<WebPPicture>::uninit();
+        if WebPPictureInitInternal(picture.as_mut_ptr(), abi_version) == 0 {
+            return Err("incompatible libwebp version".to_string());
+        }
+        let mut picture = picture.assume_init();
+        picture.use_argb = config.lossless;
+        picture.width = image.width() as c_int;
+        picture.height = image.height() as c_int;
+        let stride = image.width() as c_int * 4;
+        if WebPPictureImportRGBA(&mut picture, image.as_raw().as_ptr(), stride) == 0 {
+            WebPPictureFree(&mut picture);
+            return Err("out of memory".to_string());
+        }
+
+        let mut writer = MaybeUninit::<WebPMemoryWriter>::uninit();
+        WebPMemoryWriterInit(writer.as_mut_ptr());
+        let mut writer = writer.assume_init();
+        picture.writer = Some(WebPMemoryWrite);
+        picture.custom_ptr = &mut writer as *mut WebPMemoryWriter as *mut c_void;
+        let encoded = WebPEncode(&config, &mut picture) != 0;
+        let error_code = picture.error_code;
+        WebPPictureFree(&mut picture);
+        if !encoded {
+            WebPMemoryWriterClear(&mut writer);
+            return Err(format!("{:?}", error_code));
+        }
+        let webp_memory = std::slice::from_raw_parts(writer.mem, writer.size).to_vec();
+        WebPMemoryWriterClear(&mut writer);
+        Ok(webp_memory)
+    }
+}
+
+/// Gives fully transparent pixels the colors of the closest visible pixels, keeping them
+/// transparent. Lossy encoding mixes the colors of the neighbouring pixels, so the edges
+/// don't get the colors of the transparent pixels, which are usually black.
+fn bleed_transparent_pixels(image: &mut RgbaImage) {
+    let (width, height) = image.dimensions();
+    let mut filled = image
+        .pixels()
+        .map(|pixel| pixel[3] > 0)
+        .collect::<Vec<bool>>();
+    let mut queue = image
+        .enumerate_pixels()
+        .filter(|(_, _, pixel)| pixel[3] > 0)
+        .map(|(x, y, _)| (x, y))
+        .collect::<VecDeque<(u32, u32)>>();
+    // Breadth-first, so every pixel gets the color of the closest visible one
+    while let Some((x, y)) = queue.pop_front() {
+        let [red, green, blue, _] = image.get_pixel(x, y).0;
+        let neighbours = [
+            (x.wrapping_sub(1), y),
+            (x + 1, y),
+            (x, y.wrapping_sub(1)),
+            (x, y + 1),
+        ];
+        for (x, y) in neighbours {
+            let index = (y as usize) * (width as usize) + x as usize;
+            if x >= width || y >= height || filled[index] {
+                continue;
+            }
+            filled[index] = true;
+            image.put_pixel(x, y, Rgba([red, green, blue, 0]));
+            queue.push_back((x, y));
+        }
+    }
+}
+
+#[test]
+fn test_image_has_hard_edges() {
+    let icon = RgbaImage::from_fn(16, 16, |x, _| match x < 8 {
+        true => Rgba([0, 0, 0, 255]),
+        false => Rgba([0, 0, 0, 0]),
+    });
+    assert!(image_has_hard_edges(&icon));
+
+    let gradient = RgbaImage::from_fn(16, 16, |x, y| {
+        Rgba([(x * 16) as u8, (y * 16) as u8, 0, 255])
+    });
+    assert!(!image_has_hard_edges(&gradient));
+
+    let shadow = RgbaImage::from_fn(16, 16, |x, _| Rgba([0, 0, 0, (x * 16) as u8]));
+    assert!(!image_has_hard_edges(&shadow));
+}
+
+#[test]
+fn test_encode_alpha_edges() {
+    // Thin antialiased orange ring on transparent black, as Figma exports the outlined icons
+    let ring = RgbaImage::from_fn(32, 32, |x, y| {
+        let distance = ((x as f32 - 15.3).powi(2) + (y as f32 - 15.7).powi(2)).sqrt();
+        let alpha = (1.2f32 - (distance - 10f32).abs()).clamp(0f32, 1f32);
+        match alpha > 0f32 {
+            true => Rgba([255, 160, 40, (alpha * 255f32) as u8]),
+            false => Rgba([0, 0, 0, 0]),
+        }
+    });
+    // Mean color difference of the semi-transparent edge pixels after the lossy encoding
+    let edge_error = |webp_memory: &[u8]| {
+        let decoded = webp::Decoder::new(webp_memory)
+            .decode()
+            .unwrap()
+            .to_image()
+            .to_rgba8();
+        let errors = ring
+            .pixels()
+            .zip(decoded.pixels())
+            .filter(|(source, _)| source[3] > 0 && source[3] < 255)
+            .map(|(source, decoded)| {
+                (0..3)
+                    .map(|c| (source[c] as f32 - decoded[c] as f32).abs())
+                    .sum::<f32>()
+                    / 3f32
+            })
+            .collect::<Vec<f32>>();
+        errors.iter().sum::<f32>() / errors.len() as f32
+    };
+
+    let default = encode_simple(
+        &Encoder::from_image(&ring.clone().into()).unwrap(),
+        Some(75f32),
+    );
+    let mut bled = ring.clone();
+    bleed_transparent_pixels(&mut bled);
+    assert!(bled.pixels().zip(ring.pixels()).all(|(a, b)| a[3] == b[3]));
+    let bleed = encode_simple(&Encoder::from_rgba(bled.as_raw(), 32, 32), Some(75f32));
+    // The edges get darker with the default encoding and keep the color with bleeding
+    assert!(edge_error(&default) > 4f32);
+    assert!(edge_error(&bleed) < 2f32);
+}
+
+#[test]
+fn test_encode_exact_keeps_transparent_colors() {
+    let image = RgbaImage::from_fn(8, 8, |x, _| match x < 4 {
+        true => Rgba([200, 100, 50, 255]),
+        false => Rgba([12, 34, 56, 0]),
+    });
+    let webp_memory = encode_exact(&image, None).unwrap();
+    let decoded = webp::Decoder::new(&webp_memory)
+        .decode()
+        .unwrap()
+        .to_image()
+        .to_rgba8();
+    assert_eq!(decoded, image);
+}
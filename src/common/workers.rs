@@ -0,0 +1,90 @@
+use std::sync::Mutex;
+use std::thread;
+
+/// Process every item of the `items` with the `worker` function on a bounded pool of threads.
+/// Returns when all items have been processed.
+///
+/// # Arguments
+///
+/// * `items` - Items to process. Each item is taken by exactly one thread.
+/// * `concurrency` - Maximum number of threads processing items at the same time.
+///   Values lower than 1 are treated as 1.
+/// * `worker` - Function to call for each item.
+///
+/// # Example
+///
+/// ```ignore
+/// for_each_parallel(vec![1, 2, 3], 2, |i| println!("{}", i));
+/// ```
+pub fn for_each_parallel<T, F>(items: Vec<T>, concurrency: usize, worker: F)
+where
+    T: Send,
+    F: Fn(T) + Sync,
+{
+    map_parallel(items, concurrency, worker);
+}
+
+/// Process every item like [for_each_parallel] and return the results of the `worker`
+/// in the order of the `items`, no matter which of them has been processed first.
+///
+/// # Example
+///
+/// ```ignore
+/// let squares = map_parallel(vec![1, 2, 3], 2, |i| i * i);
+/// ```
+pub fn map_parallel<T, R, F>(items: Vec<T>, concurrency: usize, worker: F) -> Vec<R>
+where
+    T: Send,
+    R: Send,
+    F: Fn(T) -> R + Sync,
+{
+    let threads_count = concurrency.max(1).min(items.len());
+    let items_count = items.len();
+    let queue = Mutex::new(items.into_iter().enumerate());
+    let results = Mutex::new(Vec::with_capacity(items_count));
+    thread::scope(|scope| {
+        for _ in 0..threads_count {
+            scope.spawn(|| loop {
+                // Don't hold the lock while the item is being processed
+                let next = queue.lock().unwrap().next();
+                match next {
+                    Some((index, item)) => {
+                        let result = worker(item);
+                        results.lock().unwrap().push((index, result));
+                    }
+                    None => break,
+                }
+            });
+        }
+    });
+    let mut results = results.into_inner().unwrap();
+    results.sort_by_key(|(index, _)| *index);
+    results.into_iter().map(|(_, result)| result).collect()
+}
+
+#[test]
+fn test_all_items_processed() {
+    let processed = Mutex::new(Vec::new());
+    for_each_parallel((0..10).collect(), 3, |i| processed.lock().unwrap().push(i));
+    let mut processed = processed.into_inner().unwrap();
+    processed.sort();
+    assert_eq!(processed, (0..10).collect::<Vec<i32>>());
+}
+
+#[test]
+fn test_zero_concurrency() {
+    let processed = Mutex::new(Vec::new());
+    for_each_parallel(vec![1, 2], 0, |i| processed.lock().unwrap().push(i));
+    assert_eq!(processed.into_inner().unwrap().len(), 2);
+}
+
+#[test]
+fn test_results_keep_order() {
+    let items = (0..20).collect::<Vec<u64>>();
+    let results = map_parallel(items, 4, |i| {
+        // The first items are processed the longest
+        thread::sleep(std::time::Duration::from_millis(20 - i));
+        i * 2
+    });
+    assert_eq!(results, (0..20).map(|i| i * 2).collect::<Vec<u64>>());
+}
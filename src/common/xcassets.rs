@@ -0,0 +1,174 @@
+use serde::Serialize;
+use std::fs::{self, File};
+use std::io::BufWriter;
+use std::path::Path;
+
+/// Content of the `Contents.json` file, which describes the asset catalog
+/// or the image set inside it.
+///
+/// Details: https://developer.apple.com/library/archive/documentation/Xcode/Reference/xcode_ref-Asset_Catalog_Format/
+#[derive(Debug, Serialize)]
+struct Contents {
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    images: Vec<ContentsImage>,
+    info: ContentsInfo,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    properties: Option<ContentsProperties>,
+}
+
+#[derive(Debug, Serialize)]
+struct ContentsImage {
+    filename: String,
+    idiom: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    scale: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    appearances: Option<Vec<ContentsAppearance>>,
+}
+
+#[derive(Debug, Serialize)]
+struct ContentsAppearance {
+    appearance: String,
+    value: String,
+}
+
+#[derive(Debug, Serialize)]
+struct ContentsInfo {
+    author: String,
+    version: u32,
+}
+
+#[derive(Debug, Serialize)]
+struct ContentsProperties {
+    #[serde(rename = "preserves-vector-representation")]
+    preserves_vector_representation: bool,
+}
+
+/// Image file inside the image set.
+#[derive(Debug, Clone)]
+pub struct ImageSetEntry {
+    pub file_name: String,
+    /// Scale of the image, `None` for vector images.
+    pub scale: Option<f32>,
+    pub dark: bool,
+}
+
+impl Default for ContentsInfo {
+    fn default() -> Self {
+        Self {
+            author: "xcode".to_string(),
+            version: 1,
+        }
+    }
+}
+
+/// Returns the name of the iOS image scale (`1x`, `2x` or `3x`), which is used in the
+/// image file name suffix (`@2x`) and in the `Contents.json`. Returns `None` if the scale
+/// is not supported by asset catalogs.
+///
+/// # Example
+/// ```ignore
+/// assert_eq!(ios_scale_name(2.0), Some("2x".to_string()));
+/// assert_eq!(ios_scale_name(1.5), None);
+/// ```
+pub fn ios_scale_name(scale: f32) -> Option<String> {
+    match scale {
+        s if s == 1.0 || s == 2.0 || s == 3.0 => Some(format!("{}x", s)),
+        _ => None,
+    }
+}
+
+/// Write `Contents.json` of the image set. Existing file will be replaced.
+///
+/// # Arguments
+///
+/// * `imageset_dir` - Path to the `{name}.imageset` directory.
+/// * `entries` - Image files inside the image set.
+pub fn write_imageset_contents(
+    imageset_dir: &String,
+    entries: &[ImageSetEntry],
+) -> Result<(), std::io::Error> {
+    let vector = entries.iter().all(|e| e.scale.is_none());
+    let contents = Contents {
+        images: entries
+            .iter()
+            .map(|e| ContentsImage {
+                filename: e.file_name.clone(),
+                idiom: "universal".to_string(),
+                scale: e.scale.and_then(ios_scale_name),
+                appearances: if e.dark {
+                    Some(vec![ContentsAppearance {
+                        appearance: "luminosity".to_string(),
+                        value: "dark".to_string(),
+                    }])
+                } else {
+                    None
+                },
+            })
+            .collect(),
+        info: ContentsInfo::default(),
+        properties: if vector {
+            Some(ContentsProperties {
+                preserves_vector_representation: true,
+            })
+        } else {
+            None
+        },
+    };
+    write_contents(imageset_dir, &contents)
+}
+
+/// Create the asset catalog directory with its own `Contents.json`, if it doesn't exist yet.
+///
+/// # Arguments
+///
+/// * `xcassets_dir` - Path to the `*.xcassets` directory.
+pub fn create_catalog_if_missing(xcassets_dir: &String) -> Result<(), std::io::Error> {
+    fs::create_dir_all(xcassets_dir)?;
+    if Path::new(xcassets_dir).join("Contents.json").exists() {
+        return Ok(());
+    }
+    let contents = Contents {
+        images: vec![],
+        info: ContentsInfo::default(),
+        properties: None,
+    };
+    write_contents(xcassets_dir, &contents)
+}
+
+fn write_contents(dir: &String, contents: &Contents) -> Result<(), std::io::Error> {
+    let file = File::create(Path::new(dir).join("Contents.json"))?;
+    serde_json::to_writer_pretty(BufWriter::new(file), contents)?;
+    Ok(())
+}
+
+#[test]
+fn test_ios_scale_name() {
+    assert_eq!(ios_scale_name(1.0), Some("1x".to_string()));
+    assert_eq!(ios_scale_name(3.0), Some("3x".to_string()));
+    assert_eq!(ios_scale_name(1.5), None);
+    assert_eq!(ios_scale_name(4.0), None);
+}
+
+#[test]
+fn test_contents_serialization() {
+    let contents = Contents {
+        images: vec![ContentsImage {
+            filename: "img_logo_dark@2x.png".to_string(),
+            idiom: "universal".to_string(),
+            scale: Some("2x".to_string()),
+            appearances: Some(vec![ContentsAppearance {
+                appearance: "luminosity".to_string(),
+                value: "dark".to_string(),
+            }]),
+        }],
+        info: ContentsInfo::default(),
+        properties: None,
+    };
+    assert_eq!(
+        serde_json::to_string(&contents).unwrap(),
+        "{\"images\":[{\"filename\":\"img_logo_dark@2x.png\",\"idiom\":\"universal\",\
+        \"scale\":\"2x\",\"appearances\":[{\"appearance\":\"luminosity\",\"value\":\"dark\"}]}],\
+        \"info\":{\"author\":\"xcode\",\"version\":1}}"
+    );
+}
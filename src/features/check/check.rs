@@ -0,0 +1,206 @@
+use std::fs;
+use std::path::Path;
+use std::process;
+
+use crate::api::figma::{FigmaApi, RetryPolicy};
+use crate::common::error::AppError;
+use crate::common::exit_status::ExitStatus;
+use crate::common::fetching::{fetch, read_config, validate_config, FetcherTarget};
+use crate::common::fileutils::{set_temp_files_dir, temp_files_dir};
+use crate::common::renderer::{BufferingRenderer, Render, Renderer};
+use crate::common::token::{resolve_token, TokenSource};
+use crate::common::webp;
+use crate::feature_check::view::View;
+use crate::models::config::{AppConfig, AuthType, Platform};
+
+/// Options of `check` command.
+pub struct CheckOptions {
+    /// Platform to check the config and the output directory for.
+    pub platform: Platform,
+    /// Show every HTTP request with its status and timing.
+    pub verbose: bool,
+}
+
+/// Diagnose everything the export depends on: the config, the output and temporary
+/// directories, the WEBP encoder, the token, the Figma file and the frames in it.
+/// Every check is shown as passed or failed, nothing is exported.
+/// Checks which depend on a failed check are skipped.
+pub fn check_environment(
+    token_source: &TokenSource,
+    yaml_config_path: &String,
+    profile: &Option<String>,
+    options: &CheckOptions,
+) -> ExitStatus {
+    let renderer = Renderer();
+    let mut failed = 0;
+    renderer.new_line();
+
+    // The config is read silently, so only the results of the checks are shown
+    let fetcher_target = FetcherTarget::Images(options.platform);
+    let app_config = read_config(yaml_config_path, profile, &BufferingRenderer::default())
+        .and_then(|app_config| {
+            let mut errors = validate_config(&app_config, yaml_config_path, &fetcher_target);
+            match errors.len() {
+                0 => Ok(app_config),
+                1 => Err(errors.remove(0)),
+                _ => Err(AppError::AppConfigInvalid(yaml_config_path.clone(), errors)),
+            }
+        })
+        .map(|app_config| (app_config, format!("{} is valid", yaml_config_path)));
+    let app_config = report(&renderer, "Config", app_config, &mut failed);
+
+    match &app_config {
+        Some(app_config) => {
+            let output_dir = output_dir(app_config, options.platform);
+            let result = check_writable(&output_dir).map(|details| ((), details));
+            report(&renderer, "Output directory", result, &mut failed);
+        }
+        None => skip(&renderer, "Output directory", "the config check has failed"),
+    }
+    let temp_dir = app_config
+        .as_ref()
+        .and_then(|app_config| app_config.common.temp_dir.clone());
+    set_temp_files_dir(&temp_dir);
+    let result = check_writable(&temp_files_dir()).map(|details| ((), details));
+    report(&renderer, "Temporary directory", result, &mut failed);
+    let result = match webp::is_encoder_available() {
+        true => Ok(((), "a test image is encoded".to_string())),
+        false => Err(AppError::WebpEncoderUnavailable),
+    };
+    report(&renderer, "WEBP encoder", result, &mut failed);
+
+    let api = resolve_token(token_source).and_then(|token| {
+        let mut api = FigmaApi::new(&token)?;
+        api.set_verbose(options.verbose);
+        if let Some(app_config) = &app_config {
+            api.set_retry_policy(RetryPolicy::from(&app_config.figma.retry));
+            api.set_http_config(&app_config.http);
+            api.set_rate_limit(app_config.figma.requests_per_minute);
+            api.set_base_url(&app_config.figma.base_url);
+            api.set_auth_type(app_config.figma.auth_type)?;
+        }
+        api.verify_token(&renderer)?;
+        let token_type = match api.auth_type() {
+            AuthType::OAuth => "OAuth access token",
+            _ => "personal access token",
+        };
+        Ok((api, format!("accepted by Figma API as {}", token_type)))
+    });
+    let api = report(&renderer, "Token", api, &mut failed);
+
+    match (app_config, api) {
+        (Some(app_config), Some(mut api)) => {
+            let file_id = app_config.figma.file_id.clone();
+            let result = api
+                .verify_file(&file_id, &renderer)
+                .map(|_| ((), format!("{} is accessible", file_id)));
+            if report(&renderer, "Figma file", result, &mut failed).is_some() {
+                let result = fetch(
+                    &mut api,
+                    app_config,
+                    yaml_config_path,
+                    fetcher_target,
+                    false,
+                    false,
+                    &BufferingRenderer::default(),
+                )
+                .map(|entry| {
+                    (
+                        (),
+                        format!("{} images found", entry.image_names_to_ids.len()),
+                    )
+                });
+                report(&renderer, "Frames", result, &mut failed);
+            } else {
+                skip(&renderer, "Frames", "the Figma file check has failed");
+            }
+        }
+        (None, _) => {
+            skip(&renderer, "Figma file", "the config check has failed");
+            skip(&renderer, "Frames", "the config check has failed");
+        }
+        (_, None) => {
+            skip(&renderer, "Figma file", "the token check has failed");
+            skip(&renderer, "Frames", "the token check has failed");
+        }
+    }
+
+    renderer.render(&View::Done { failed });
+    renderer.new_line();
+    match failed {
+        0 => ExitStatus::Success,
+        _ => ExitStatus::ConfigError,
+    }
+}
+
+/// Show the result of the check and count it if it has failed.
+/// Returns the value produced by the check, if it has passed.
+fn report<T>(
+    renderer: &dyn Render,
+    name: &str,
+    result: Result<(T, String), AppError>,
+    failed: &mut usize,
+) -> Option<T> {
+    match result {
+        Ok((value, details)) => {
+            renderer.render_line(&View::Passed {
+                name: name.to_string(),
+                details,
+            });
+            Some(value)
+        }
+        Err(e) => {
+            *failed += 1;
+            renderer.render_line(&View::Failed {
+                name: name.to_string(),
+                description: format!("{}", e),
+            });
+            None
+        }
+    }
+}
+
+fn skip(renderer: &dyn Render, name: &str, reason: &str) {
+    renderer.render_line(&View::Skipped {
+        name: name.to_string(),
+        reason: reason.to_string(),
+    });
+}
+
+/// Base directory of the exported images for the platform, validated by now.
+fn output_dir(app_config: &AppConfig, platform: Platform) -> String {
+    match (platform, &app_config.ios, &app_config.web) {
+        (Platform::Ios, Some(ios), _) => ios.xcassets.clone(),
+        (Platform::Web, _, Some(web)) => web.assets_dir.clone(),
+        _ => app_config
+            .main_res_images()
+            .expect("Validation is done before"),
+    }
+}
+
+/// Check that files can be created in the directory, by writing and removing an empty file.
+/// Missing directories are created by the export, so the closest existing one is checked.
+fn check_writable(dir: &String) -> Result<String, AppError> {
+    let existing_dir = closest_existing_dir(Path::new(dir));
+    let probe_path = existing_dir.join(format!(".fxn_check_{}", process::id()));
+    fs::write(&probe_path, [])
+        .and_then(|_| fs::remove_file(&probe_path))
+        .map(|_| format!("{} is writable", dir))
+        .map_err(|e| AppError::CannotWriteToDir(existing_dir.display().to_string(), e.to_string()))
+}
+
+fn closest_existing_dir(path: &Path) -> &Path {
+    path.ancestors()
+        .find(|ancestor| ancestor.is_dir())
+        .unwrap_or_else(|| Path::new("."))
+}
+
+#[test]
+fn test_closest_existing_dir() {
+    assert_eq!(
+        closest_existing_dir(Path::new("src/missing/drawable")),
+        Path::new("src")
+    );
+    assert_eq!(closest_existing_dir(Path::new("src")), Path::new("src"));
+    assert_eq!(closest_existing_dir(Path::new("missing")), Path::new("."));
+}
@@ -0,0 +1,4 @@
+pub mod check;
+pub mod view;
+
+pub use check::{check_environment, CheckOptions};
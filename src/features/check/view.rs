@@ -0,0 +1,59 @@
+use crossterm::style::Stylize;
+
+use crate::common::renderer::{Indentable, Renderable};
+
+pub enum View {
+    /// The check has passed, with the details of what has been checked.
+    Passed {
+        name: String,
+        details: String,
+    },
+    Failed {
+        name: String,
+        description: String,
+    },
+    /// The check can't be done, because the check it depends on has failed.
+    Skipped {
+        name: String,
+        reason: String,
+    },
+    Done {
+        failed: usize,
+    },
+}
+
+impl Renderable for View {
+    fn is_important(&self) -> bool {
+        true
+    }
+
+    fn render(&self) -> String {
+        match self {
+            View::Passed { name, details } => {
+                format!("{} {}: {}", "Passed".indent().bold().green(), name, details)
+            }
+            View::Failed { name, description } => {
+                format!(
+                    "{} {}: {}",
+                    "Failed".indent().bold().red(),
+                    name,
+                    description
+                )
+            }
+            View::Skipped { name, reason } => {
+                format!(
+                    "{} {}: {}",
+                    "Skipped".indent().bold().dark_grey(),
+                    name,
+                    reason
+                )
+            }
+            View::Done { failed: 0 } => {
+                format!("{} all checks passed", "Done".indent().bold().green())
+            }
+            View::Done { failed } => {
+                format!("{} failed checks: {}", "Done".indent().bold().red(), failed)
+            }
+        }
+    }
+}
@@ -1,16 +1,23 @@
 use crate::common::fileutils::remove_temp_dir;
 
-use crate::common::renderer::Renderer;
+use crate::common::exit_status::ExitStatus;
+use crate::common::renderer::{Render, Renderer};
 use crate::feature_cleanup::view::View;
 
-pub fn cleanup() {
+pub fn cleanup() -> ExitStatus {
     let renderer = Renderer();
     renderer.new_line();
     match remove_temp_dir() {
-        Ok(()) => renderer.render(View::Done),
-        Err(e) => renderer.render(View::Error(format!(
-            "Can't delete temporary `.fxn` directory: {}",
-            &e
-        ))),
+        Ok(()) => {
+            renderer.render(&View::Done);
+            ExitStatus::Success
+        }
+        Err(e) => {
+            renderer.render(&View::Error(format!(
+                "Can't delete temporary `.fxn` directory: {}",
+                &e
+            )));
+            ExitStatus::PartialFailure
+        }
     }
 }
@@ -0,0 +1,171 @@
+use std::collections::HashMap;
+
+use crate::api::figma::FigmaApi;
+use crate::common::colors::colors_xml_source;
+use crate::common::error::AppError;
+use crate::common::exit_status::ExitStatus;
+use crate::common::fetching::{fetch, read_config, FetcherTarget};
+use crate::common::kotlin::{colors_object_source, write_source};
+use crate::common::renderer::{Render, Renderer};
+use crate::common::res_name::{is_valid_res_name, to_res_name};
+use crate::common::token::{resolve_token, TokenSource};
+use crate::feature_colors::view::View;
+use crate::models::config::AppConfig;
+use crate::models::figma::Color;
+
+/// Export color styles of the Figma file to `values/colors.xml` of the android project,
+/// and optionally to Kotlin object for Jetpack Compose.
+pub fn export_colors(
+    token_source: &TokenSource,
+    yaml_config_path: &String,
+    profile: &Option<String>,
+    verbose: bool,
+) -> ExitStatus {
+    let renderer = Renderer();
+    let token = match resolve_token(token_source) {
+        Ok(token) => token,
+        Err(e) => {
+            renderer.render(&View::Error(format!("{}", e)));
+            return ExitStatus::ConfigError;
+        }
+    };
+    let mut api = match FigmaApi::new(&token) {
+        Ok(api) => api,
+        Err(e) => {
+            renderer.render(&View::Error(format!("{}", e)));
+            return ExitStatus::ConfigError;
+        }
+    };
+    api.set_verbose(verbose);
+
+    let fetcher_entry =
+        match read_config(yaml_config_path, profile, &renderer).and_then(|app_config| {
+            fetch(
+                &mut api,
+                app_config,
+                yaml_config_path,
+                FetcherTarget::Colors,
+                false,
+                false,
+                &renderer,
+            )
+        }) {
+            Ok(fetcher_entry) => fetcher_entry,
+            Err(e) => {
+                renderer.render(&View::Error(format!("{}", e)));
+                return ExitStatus::ConfigError;
+            }
+        };
+    let (app_config, names_to_ids) = (fetcher_entry.app_config, fetcher_entry.image_names_to_ids);
+
+    match export_color_styles(&api, &app_config, &names_to_ids, &renderer) {
+        Ok(skipped) => {
+            renderer.render(&View::Done {
+                message: Some(format!(
+                    "{} exported, {} skipped",
+                    names_to_ids.len() - skipped,
+                    skipped
+                )),
+            });
+            if skipped > 0 {
+                ExitStatus::PartialFailure
+            } else {
+                ExitStatus::Success
+            }
+        }
+        Err(e) => {
+            renderer.render(&View::Error(e.to_string()));
+            ExitStatus::PartialFailure
+        }
+    }
+}
+
+/// Write the colors of the styles and return the number of the skipped styles.
+fn export_color_styles(
+    api: &FigmaApi,
+    app_config: &AppConfig,
+    names_to_ids: &HashMap<String, String>,
+    renderer: &dyn Render,
+) -> Result<usize, AppError> {
+    let mut names = names_to_ids.keys().cloned().collect::<Vec<String>>();
+    names.sort();
+    for name in &names {
+        let res_name = to_res_name(name);
+        if !is_valid_res_name(&res_name) {
+            return Err(AppError::InvalidResName(name.clone(), res_name));
+        }
+    }
+    check_res_name_collisions(&names)?;
+
+    renderer.render(&View::FetchingColors(names.len()));
+    let ids = names
+        .iter()
+        .map(|name| names_to_ids[name].clone())
+        .collect::<Vec<String>>();
+    let colors_by_ids = api.get_style_colors(&app_config.figma.file_id, &ids, renderer)?;
+
+    let mut colors: Vec<(String, Color)> = Vec::with_capacity(names.len());
+    for name in &names {
+        match colors_by_ids.get(&names_to_ids[name]) {
+            Some(&color) => colors.push((to_res_name(name), color)),
+            None => renderer.render_line(&View::NoSolidColor(name.clone())),
+        }
+    }
+    colors.sort_by(|(a, _), (b, _)| a.cmp(b));
+
+    let main_res = app_config
+        .main_res_colors()
+        .expect("Validation is done in fetcher");
+    let colors_file_path = format!(
+        "{}/values/{}",
+        main_res, &app_config.android.colors.file_name
+    );
+    write_source(&colors_file_path, &colors_xml_source(&colors))
+        .map_err(|e| AppError::CannotWriteColorsFile(colors_file_path.clone(), e.to_string()))?;
+    renderer.render_line(&View::ColorsExported(colors_file_path));
+
+    let compose = &app_config.android.colors.compose;
+    if compose.generate {
+        let output_path = compose
+            .output_path
+            .as_ref()
+            .expect("Validation is done in fetcher");
+        let package_name = compose
+            .package_name
+            .as_ref()
+            .expect("Validation is done in fetcher");
+        let source = colors_object_source(package_name, &compose.object_name, &colors);
+        write_source(output_path, &source)
+            .map_err(|e| AppError::CannotWriteKotlinFile(output_path.clone(), e.to_string()))?;
+        renderer.render_line(&View::GeneratedKotlinFile(output_path.clone()));
+    }
+
+    Ok(names.len() - colors.len())
+}
+
+/// Check that different color styles don't get the same resource name.
+fn check_res_name_collisions(names: &[String]) -> Result<(), AppError> {
+    let mut names_by_res_names: HashMap<String, Vec<&String>> = HashMap::new();
+    for name in names {
+        names_by_res_names
+            .entry(to_res_name(name))
+            .or_default()
+            .push(name);
+    }
+    let mut collisions = names_by_res_names
+        .into_iter()
+        .filter(|(_, names)| names.len() > 1)
+        .collect::<Vec<(String, Vec<&String>)>>();
+    collisions.sort();
+    match collisions.into_iter().next() {
+        Some((res_name, names)) => Err(AppError::ResNameCollision(
+            res_name,
+            names
+                .iter()
+                .map(|name| format!("\"{}\"", name))
+                .collect::<Vec<String>>()
+                .join(", "),
+        )),
+        None => Ok(()),
+    }
+}
@@ -0,0 +1,4 @@
+pub mod colors;
+pub mod view;
+
+pub use colors::export_colors;
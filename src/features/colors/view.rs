@@ -0,0 +1,51 @@
+use crossterm::style::Stylize;
+
+use crate::common::renderer::{Indentable, Renderable};
+
+pub enum View {
+    FetchingColors(usize),
+    NoSolidColor(String),
+    ColorsExported(String),
+    GeneratedKotlinFile(String),
+    Error(String),
+    Done { message: Option<String> },
+}
+
+impl Renderable for View {
+    fn is_important(&self) -> bool {
+        matches!(self, View::Error(_) | View::Done { .. })
+    }
+
+    fn render(&self) -> String {
+        match self {
+            View::FetchingColors(count) => format!(
+                "{} values of {} color styles",
+                "Fetching".indent().bold().cyan(),
+                count,
+            ),
+            View::NoSolidColor(name) => format!(
+                "{} color style `{}` is skipped, because it has no solid fill",
+                "Warning".indent().bold().yellow(),
+                &name,
+            ),
+            View::ColorsExported(path) => {
+                format!("{} colors to {}", "Exported".indent().bold().green(), &path,)
+            }
+            View::GeneratedKotlinFile(path) => format!(
+                "{} Kotlin object with colors {}",
+                "Generated".indent().bold().green(),
+                &path,
+            ),
+            View::Error(description) => {
+                format!("{} {}", "Error".indent().bold().red(), &description)
+            }
+            View::Done { message } => {
+                if let Some(m) = message {
+                    format!("{} {}", "Done".indent().bold().green(), &m)
+                } else {
+                    format!("{}", "Done".indent().bold().green())
+                }
+            }
+        }
+    }
+}
@@ -1,10 +1,53 @@
 use std::fmt;
 use std::fs;
 use std::io::BufWriter;
+use std::io::ErrorKind;
 use std::io::Write;
 
-use crate::common::renderer::Renderer;
+use crate::common::exit_status::ExitStatus;
+use crate::common::renderer::{Render, Renderer};
 use crate::feature_config::view::View;
+use crate::models::config::AppConfig;
+
+/// Comments for the fields of the starter config, by paths of the fields.
+const TEMPLATE_COMMENTS: &[(&str, &str)] = &[
+    (
+        "figma.fileId",
+        "Identifier of the Figma file. It is in the URL of the file after the word file",
+    ),
+    (
+        "figma.retry",
+        "Retrying of the Figma API requests failed with 429 or 5xx status",
+    ),
+    (
+        "http",
+        "Timeouts of the requests in seconds. Add `proxy: <url>` to send requests through a proxy",
+    ),
+    (
+        "common.images.figmaFrameName",
+        "Names of the Figma's frames where images are located",
+    ),
+    (
+        "common.icons.figmaFrameName",
+        "Name of the Figma's frame where icons are located",
+    ),
+    (
+        "android.mainRes",
+        "Relative or absolute path to the `main/res` folder of your android module",
+    ),
+    (
+        "android.images.scales",
+        "Names of the drawable directories and scales of the images exported there",
+    ),
+    (
+        "android.images.format",
+        "Exported images file format: png | webp | jpeg | avif | svg",
+    ),
+    (
+        "android.icons.format",
+        "Exported icons file format: xml | svg",
+    ),
+];
 
 #[derive(Debug)]
 pub struct ConfigFeatureError {
@@ -18,12 +61,12 @@ impl fmt::Display for ConfigFeatureError {
     }
 }
 
-pub fn create_default_config(path: &String) {
+pub fn create_default_config(path: &String) -> ExitStatus {
     let file = fs::OpenOptions::new()
         .create(true)
         .write(true)
         .truncate(true)
-        .open(&path);
+        .open(path);
 
     let result = match file {
         Ok(file) => {
@@ -44,7 +87,103 @@ pub fn create_default_config(path: &String) {
     let renderer = Renderer();
     renderer.new_line();
     match result {
-        Ok(()) => renderer.render(View::Created(path.clone())),
-        Err(e) => renderer.render(View::Error(format!("{}", e))),
+        Ok(()) => {
+            renderer.render(&View::Created(path.clone()));
+            ExitStatus::Success
+        }
+        Err(e) => {
+            renderer.render(&View::Error(format!("{}", e)));
+            ExitStatus::ConfigError
+        }
+    }
+}
+
+/// Write the starter config, built from the default [AppConfig], so it always matches
+/// the fields the app expects. Existing file is overwritten only if `force` is true.
+pub fn init_config(path: &String, force: bool) -> ExitStatus {
+    let file = fs::OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .create_new(!force)
+        .open(path);
+
+    let result = match file {
+        Ok(file) => {
+            let mut writer = BufWriter::new(&file);
+            writeln!(writer, "{}", config_template()).or_else(|e| {
+                let message = format!("Can't create config file {}", &path);
+                let cause = format!("{}", e);
+                Err(ConfigFeatureError { message, cause })
+            })
+        }
+        Err(e) if e.kind() == ErrorKind::AlreadyExists => Err(ConfigFeatureError {
+            message: format!("Config file {} already exists", &path),
+            cause: "use `--force` to overwrite it".to_string(),
+        }),
+        Err(e) => {
+            let message = format!("Can't create config file {}", &path);
+            let cause = format!("{}", e);
+            Err(ConfigFeatureError { message, cause })
+        }
+    };
+    let renderer = Renderer();
+    renderer.new_line();
+    match result {
+        Ok(()) => {
+            renderer.render(&View::Created(path.clone()));
+            ExitStatus::Success
+        }
+        Err(e) => {
+            renderer.render(&View::Error(format!("{}", e)));
+            ExitStatus::ConfigError
+        }
+    }
+}
+
+fn config_template() -> String {
+    let yaml = serde_yaml::to_string(&AppConfig::template()).expect("Config is serializable");
+    let yaml = yaml.trim_start_matches("---\n");
+    format!(
+        "# Starter config with the default values. Run `fxn config` to get the config\n\
+         # with all available fields and their descriptions.\n{}",
+        with_comments(yaml, TEMPLATE_COMMENTS)
+    )
+}
+
+/// Put the comment above every field of the yaml, whose path is in `comments`.
+fn with_comments(yaml: &str, comments: &[(&str, &str)]) -> String {
+    let mut path: Vec<(usize, &str)> = Vec::new();
+    let mut result = String::with_capacity(yaml.len());
+    for line in yaml.lines() {
+        let content = line.trim_start();
+        let indent = line.len() - content.len();
+        if let (false, Some((key, _))) = (content.starts_with('-'), content.split_once(':')) {
+            while path.last().filter(|(i, _)| *i >= indent).is_some() {
+                path.pop();
+            }
+            path.push((indent, key));
+            let field_path = path
+                .iter()
+                .map(|(_, k)| *k)
+                .collect::<Vec<&str>>()
+                .join(".");
+            if let Some((_, comment)) = comments.iter().find(|(p, _)| *p == field_path) {
+                result.push_str(&format!("{:indent$}# {}\n", "", comment, indent = indent));
+            }
+        }
+        result.push_str(line);
+        result.push('\n');
     }
+    result
+}
+
+#[test]
+fn test_config_template_is_valid() {
+    let template = config_template();
+    let app_config: AppConfig = serde_yaml::from_str(&template).unwrap();
+    assert_eq!(app_config.figma.file_id, "YOUR_FIGMA_FILE_ID");
+    assert!(template.contains(
+        "  # Exported images file format: png | webp | jpeg | avif | svg\n    format: webp"
+    ));
 }
@@ -1,11 +1,14 @@
+use std::collections::HashMap;
+
 use crate::api::figma::FigmaApi;
 use crate::common::error::AppError;
-use crate::common::fetching::{fetch, FetcherTarget};
-use crate::common::fileutils::{create_dir, move_file};
-use crate::common::gathering::gathering::gather_names;
-use crate::common::http_client::create_http_client;
-use crate::common::renderer::Renderer;
+use crate::common::exit_status::ExitStatus;
+use crate::common::fetching::{fetch, read_config, FetcherTarget};
+use crate::common::fileutils::{create_dir, move_file, set_temp_files_dir, TempFiles};
+use crate::common::gathering::gathering::{gather_names, SuggestionOptions};
+use crate::common::renderer::{Render, Renderer};
 use crate::common::res_name::to_res_name;
+use crate::common::token::{resolve_token, TokenSource};
 use crate::common::vdtool::vdtool::convert_svg_to_xml;
 use crate::feature_icons::view::View;
 use crate::models::config::{AppConfig, IconFormat, ImageFormat};
@@ -34,27 +37,62 @@ impl IconInfo {
     }
 }
 
-pub fn export_icons(token: &String, image_names: &Vec<String>, yaml_config_path: &String) {
+pub fn export_icons(
+    token_source: &TokenSource,
+    image_names: &[String],
+    yaml_config_path: &String,
+    profile: &Option<String>,
+    verbose: bool,
+) -> ExitStatus {
     let renderer = Renderer();
-    let api = FigmaApi::new(create_http_client(&token));
-
-    let fetcher_entry = match fetch(&api, &yaml_config_path, FetcherTarget::Icons, &renderer) {
-        Ok(fetcher_entry) => fetcher_entry,
+    let token = match resolve_token(token_source) {
+        Ok(token) => token,
         Err(e) => {
-            renderer.render(View::Error(format!("{}", e)));
-            return;
+            renderer.render(&View::Error(format!("{}", e)));
+            return ExitStatus::ConfigError;
         }
     };
+    let mut api = match FigmaApi::new(&token) {
+        Ok(api) => api,
+        Err(e) => {
+            renderer.render(&View::Error(format!("{}", e)));
+            return ExitStatus::ConfigError;
+        }
+    };
+    api.set_verbose(verbose);
+
+    let fetcher_entry =
+        match read_config(yaml_config_path, profile, &renderer).and_then(|app_config| {
+            fetch(
+                &mut api,
+                app_config,
+                yaml_config_path,
+                FetcherTarget::Icons,
+                false,
+                false,
+                &renderer,
+            )
+        }) {
+            Ok(fetcher_entry) => fetcher_entry,
+            Err(e) => {
+                renderer.render(&View::Error(format!("{}", e)));
+                return ExitStatus::ConfigError;
+            }
+        };
     let (app_config, names_to_ids) = (fetcher_entry.app_config, fetcher_entry.image_names_to_ids);
+    set_temp_files_dir(&app_config.common.temp_dir);
 
     let frame_name = &app_config.common.icons.figma_frame_name;
     let format = &app_config.android.icons.format;
     let icons_for_export: Vec<IconInfo> = gather_names(
-        &app_config,
-        &frame_name,
-        &image_names,
+        std::slice::from_ref(frame_name),
+        image_names,
         &names_to_ids,
-        true,
+        &HashMap::new(),
+        &HashMap::new(),
+        |_| true,
+        &SuggestionOptions::default(),
+        &renderer,
         |e| IconInfo {
             id: e.figma_id,
             user_name: e.user_name.clone(),
@@ -66,55 +104,79 @@ pub fn export_icons(token: &String, image_names: &Vec<String>, yaml_config_path:
         },
     );
 
-    for icon in icons_for_export {
-        let export_result = export_icon(&api, &app_config, &icon, &renderer);
-
-        match export_result {
-            Err(e) => renderer.render(View::Error(e.to_string())),
-            Ok(()) => (),
+    let missing = image_names
+        .iter()
+        .filter(|name| !icons_for_export.iter().any(|i| &i.user_name == *name))
+        .count();
+    let mut failed = 0usize;
+    for icon in &icons_for_export {
+        let export_result = export_icon(&api, &app_config, icon, &renderer);
+
+        if let Err(e) = export_result {
+            renderer.render(&View::Error(e.to_string()));
+            failed += 1;
         }
 
         renderer.new_line();
     }
 
-    renderer.render(View::Done { message: None });
+    let exported = icons_for_export.len() - failed;
+    renderer.render(&View::Done {
+        message: Some(format!(
+            "{} exported, {} missing, {} failed",
+            exported, missing, failed
+        )),
+    });
+    if missing > 0 || failed > 0 {
+        ExitStatus::PartialFailure
+    } else {
+        ExitStatus::Success
+    }
 }
 
 fn export_icon(
     api: &FigmaApi,
     app_config: &AppConfig,
     icon: &IconInfo,
-    renderer: &Renderer,
+    renderer: &dyn Render,
 ) -> Result<(), AppError> {
     let file_id = &app_config.figma.file_id;
     let node_id = &icon.id;
 
     // Get download url for exported icon
-    renderer.render(View::FetchingIcon(
+    renderer.render(&View::FetchingIcon(
         icon.user_name.clone(),
         icon.drawable_dir_name(),
     ));
     let icon_download_url =
-        api.get_image_download_url(file_id, node_id, 1.0f32, &ImageFormat::Svg)?;
+        api.get_image_download_url(file_id, node_id, 1.0f32, &ImageFormat::Svg, renderer)?;
 
     // Download icon from gotten url to app's TEMPORARY dir
-    renderer.render(View::DownloadingIcon(
+    renderer.render(&View::DownloadingIcon(
         icon.user_name.clone(),
         icon.drawable_dir_name(),
     ));
-    let icon_temporary_file_name = api.get_image(
+    // Every temporary file is removed at the end, even if the export fails
+    let mut temp_files = TempFiles::default();
+    let downloaded_icon = api.get_image(
         &icon_download_url,
         &icon.res.name,
         &icon.drawable_dir_name(),
         &ImageFormat::Svg,
+        renderer,
     )?;
+    temp_files.track(downloaded_icon.file_name.clone());
+    let icon_temporary_file_name = downloaded_icon.verified(&icon.user_name)?;
 
     // Convert to VectorDrawable XML
-    let icon_temporary_file_name =
-        convert_to_vector_drawable(&icon, &icon_temporary_file_name, &renderer)?;
+    let icon_temporary_file_name = temp_files.track(convert_to_vector_drawable(
+        icon,
+        &icon_temporary_file_name,
+        renderer,
+    )?);
 
     // Create drawable dir in res dir of android project
-    renderer.render(View::IconDownloaded(
+    renderer.render(&View::IconDownloaded(
         icon.user_name.clone(),
         icon.drawable_dir_name(),
     ));
@@ -133,7 +195,7 @@ fn export_icon(
         .map_err(|e| AppError::CannotMoveToDrawableDir(icon.user_name.clone(), format!("{}", e)))?;
 
     // Tell the user that we are done
-    renderer.render(View::IconExported(
+    renderer.render(&View::IconExported(
         icon.user_name.clone(),
         icon.drawable_dir_name(),
     ));
@@ -143,17 +205,17 @@ fn export_icon(
 fn convert_to_vector_drawable(
     icon: &IconInfo,
     icon_file_name: &String,
-    renderer: &Renderer,
+    renderer: &dyn Render,
 ) -> Result<String, AppError> {
     match icon.format {
         IconFormat::Xml => {
-            renderer.render(View::ConvertingToXml(
+            renderer.render(&View::ConvertingToXml(
                 icon.user_name.clone(),
                 icon.drawable_dir_name(),
             ));
-            let new_icon_path =
-                convert_svg_to_xml(icon_file_name).map_err(AppError::CannotConvertToXml)?;
-            renderer.render(View::ConvertedToXml(
+            let new_icon_path = convert_svg_to_xml(icon_file_name)
+                .map_err(|e| AppError::CannotConvertToXml(icon.user_name.clone(), e))?;
+            renderer.render(&View::ConvertedToXml(
                 icon.user_name.clone(),
                 icon.drawable_dir_name(),
             ));
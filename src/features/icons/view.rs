@@ -14,6 +14,10 @@ pub enum View {
 }
 
 impl Renderable for View {
+    fn is_important(&self) -> bool {
+        matches!(self, View::Error(_) | View::Done { .. })
+    }
+
     fn render(&self) -> String {
         match self {
             View::FetchingIcon(image_name, dir_name) => format!(
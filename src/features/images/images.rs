@@ -1,28 +1,186 @@
 use crate::api::figma::FigmaApi;
+use crate::common::adaptive_icon::{adaptive_icon_source, ADAPTIVE_ICON_DIR};
+use crate::common::aliases::{aliases_source, read_aliases, Alias, ALIASES_FILE_NAME};
+#[cfg(feature = "avif")]
+use crate::common::avif;
+use crate::common::density::density_qualifier;
+use crate::common::dimensions::{downscale_image, image_dimensions, trim_image};
+use crate::common::disk_space::check_disk_space;
 use crate::common::error::AppError;
-use crate::common::fetching::{fetch, FetcherTarget};
-use crate::common::fileutils::{create_dir, move_file};
-use crate::common::gathering::gathering::gather_names;
-use crate::common::http_client::create_http_client;
-use crate::common::renderer::Renderer;
-use crate::common::res_name::to_res_name;
+use crate::common::exit_status::ExitStatus;
+use crate::common::fetching::{fetch, read_config, FetcherTarget};
+use crate::common::fileutils::{
+    create_dir, move_file, set_temp_files_dir, temp_file_name, temp_files_dir, TempFiles,
+};
+use crate::common::gathering::gathering::{
+    describe_frames, gather_names, pick_missing_names, StdinInput, SuggestionOptions,
+};
+use crate::common::hooks::{run_hook, run_transform};
+use crate::common::jpeg;
+use crate::common::kotlin::{drawables_object_source, write_source};
+use crate::common::lockfile::{content_hash, LockEntry, LockFile};
+use crate::common::manifest::{Manifest, ManifestEntry};
+use crate::common::names_file::read_names_file;
+use crate::common::node_url::parse_node_ref;
+use crate::common::patterns::{is_pattern, matches_pattern};
+use crate::common::png;
+use crate::common::renderer::{GroupingRenderer, Render, Renderer, WarningCounter};
+use crate::common::report::{ExportStats, ExportSummary, SummaryItem, SummaryStatus, WebpSavings};
+use crate::common::res_name::{is_valid_res_name, strip_nine_patch_suffix, to_res_name};
+use crate::common::suffixes::SuffixExt;
+use crate::common::svg;
+use crate::common::token::{resolve_token, TokenSource};
+use crate::common::vdtool::vdtool::convert_svg_to_xml;
 use crate::common::webp;
+use crate::common::workers::map_parallel;
+use crate::common::xcassets::{
+    create_catalog_if_missing, ios_scale_name, write_imageset_contents, ImageSetEntry,
+};
 use crate::feature_images::view::View;
-use crate::models::config::{AppConfig, ImageFormat};
+use crate::models::config::{
+    AndroidAdaptiveIconConfig, AndroidImagesAvifConfig, AndroidImagesConfig,
+    AndroidImagesPngConfig, AndroidImagesSvgConfig, AndroidImagesWebpConfig, AppConfig,
+    ImageFormat, ImageSizing, ImagesJpegConfig, ImagesLayout, Platform, ResDirType, ScaleNaming,
+    TransformConfig, WebpAlpha,
+};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fs;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::Instant;
+
+/// Options of the images export from the command line. Library users should fill only the
+/// options they need and take the rest from [ExportOptions::default], so new options
+/// don't break them.
+pub struct ExportOptions {
+    pub platform: Platform,
+    /// Export all images from the frame.
+    pub export_all: bool,
+    /// Only show what would be exported.
+    pub dry_run: bool,
+    /// Ask the user in the terminal to pick one of the suggested names for every missing image.
+    /// Ignored if stdin isn't a terminal.
+    pub interactive: bool,
+    /// Export images into temporary files and compare them with the files in the res directory,
+    /// without writing anything there.
+    pub diff: bool,
+    /// Export images even if they are up to date according to the lock file.
+    pub force: bool,
+    /// Compare hashes of Figma nodes with the lock file, to skip unchanged images
+    /// even if the version of Figma file has changed.
+    pub only_changed: bool,
+    /// Path to write JSON summary of the export to, `-` for stdout.
+    pub report: Option<String>,
+    /// Directory to export images to instead of the res directory
+    /// (or the asset catalog for iOS) from config.
+    pub output_dir: Option<String>,
+    /// Format to export images in instead of the format from config.
+    pub format: Option<ImageFormat>,
+    /// Scales to export images with instead of the scales from config.
+    pub scales: Option<Vec<f32>>,
+    /// Sizing of Android images instead of the sizing from config.
+    pub sizing: Option<ImageSizing>,
+    /// Show every HTTP request with its status and timing.
+    pub verbose: bool,
+    /// Link or id of the Figma node to export as the only requested image,
+    /// instead of looking for it in the frames from config.
+    pub node: Option<String>,
+    /// Don't check the token before the export, e.g. to work with the cached Figma file offline.
+    pub skip_token_check: bool,
+    /// Stop the export on the first image which has failed, instead of exporting the rest.
+    pub fail_fast: bool,
+    /// Consider the export incomplete, if there are warnings.
+    pub warnings_as_errors: bool,
+    /// Find the images in the result of the previous fetch instead of loading Figma file.
+    /// The images are still downloaded from Figma, unless it is a dry run.
+    pub offline: bool,
+}
+
+/// Export for Android, of the requested images only, the same as the command line does
+/// without any flags.
+impl Default for ExportOptions {
+    fn default() -> Self {
+        Self {
+            platform: Platform::Android,
+            export_all: false,
+            dry_run: false,
+            interactive: false,
+            diff: false,
+            force: false,
+            only_changed: false,
+            report: None,
+            output_dir: None,
+            format: None,
+            scales: None,
+            sizing: None,
+            verbose: false,
+            node: None,
+            skip_token_check: false,
+            fail_fast: false,
+            warnings_as_errors: false,
+            offline: false,
+        }
+    }
+}
+
+/// Rough size of a new raster image at scale 1, to estimate the space needed for the export.
+const ESTIMATED_IMAGE_BYTES: u64 = 128 * 1024;
+
+/// Lock file with what is known about the current state of Figma file,
+/// to skip images which haven't changed since the last export.
+#[derive(Clone, Copy)]
+struct LockState<'a> {
+    lock_file: &'a Mutex<LockFile>,
+    /// Versions of Figma files by their ids, if they are known.
+    file_versions: &'a HashMap<String, String>,
+    /// Hashes of the nodes by ids of files and nodes, fetched only with `--only-changed` flag.
+    node_hashes: &'a HashMap<(String, String), String>,
+}
 
 #[derive(Debug, Clone)]
 struct ImageInfo {
     id: String,
+    /// Id of Figma file with the node of the image.
+    file_id: String,
     user_name: String,
     format: ImageFormat,
+    platform: Platform,
+    /// Exported svg will be converted to android vector drawable xml.
+    vector_drawable: bool,
+    /// Downloaded png is kept instead of webp, because it is smaller.
+    kept_png: bool,
+    /// Raster image is exported once with its size in Figma, like svg.
+    intrinsic_size: bool,
+    /// Png image exported with its scale next to the svg one of the same node, for older
+    /// Android versions, see [add_raster_fallbacks].
+    raster_fallback: bool,
+    /// Android nine-patch png, which is written with `.9.png` extension.
+    nine_patch: bool,
+    /// Where to put the image for Android.
+    layout: ImagesLayout,
+    /// Type of the res directory for Android.
+    res_dir_type: ResDirType,
     res: ResourceInfo,
 }
 
+/// What has happened to one scale of the image, see [export_image].
+/// The summary of the export is built from the outcomes of all images.
+struct ExportOutcome {
+    image: ImageInfo,
+    result: Result<SummaryStatus, AppError>,
+    /// The error of the image stops the export with `--fail-fast` flag,
+    /// so it is shown by the caller instead of the worker.
+    stops_export: bool,
+}
+
 #[derive(Debug, Clone)]
 struct ResourceInfo {
     name: String,
     scale: ScaleInfo,
     night: bool,
+    /// Android locale qualifier of the localized variant, e.g. `de`.
+    locale: Option<String>,
 }
 
 #[derive(Debug, Clone)]
@@ -32,146 +190,2540 @@ struct ScaleInfo {
 }
 
 impl ImageInfo {
+    /// Name of the image variant to show to the user,
+    /// e.g. `drawable-night-xhdpi` for Android or `img_logo_dark@2x.png` for iOS, web
+    /// and flat Android layout.
+    fn variant_name(&self) -> String {
+        match (self.platform, self.layout) {
+            (Platform::Android, ImagesLayout::DrawableDirs) => self.drawable_dir_name(),
+            _ => self.output_file_name(),
+        }
+    }
+
+    /// Returns `true` if the image is exported once, without the scale in its directory or file name.
+    fn is_single_scale(&self) -> bool {
+        self.format.is_svg() || self.intrinsic_size
+    }
+
+    /// Name of the res directory, e.g. `drawable-night-xhdpi`, `drawable-de-xhdpi` or `mipmap-xxhdpi`.
     fn drawable_dir_name(&self) -> String {
-        match (self.format.is_svg(), self.res.night) {
-            (true, true) => "drawable-night".to_string(),
-            (true, false) => "drawable".to_string(),
-            (false, true) => format!("drawable-night-{}", self.res.scale.name),
-            (false, false) => format!("drawable-{}", self.res.scale.name),
+        let mut dir_name = self.res_dir_type.dir_name().to_string();
+        // Android requires the qualifiers in this order
+        if let Some(locale) = &self.res.locale {
+            dir_name.push_str(&format!("-{}", locale));
+        }
+        if self.res.night {
+            dir_name.push_str("-night");
+        }
+        if !self.is_single_scale() {
+            dir_name.push_str(&format!("-{}", self.res.scale.name));
+        }
+        dir_name
+    }
+
+    /// Directory of the android project, of the asset catalog or of web assets to put the image in.
+    fn output_dir(&self, app_config: &AppConfig) -> String {
+        match self.platform {
+            Platform::Android => {
+                let res_dir = app_config
+                    .main_res_images()
+                    .expect("Validation is done in fetcher");
+                match self.layout {
+                    ImagesLayout::DrawableDirs => {
+                        format!("{}/{}", &res_dir, self.drawable_dir_name())
+                    }
+                    ImagesLayout::SuffixedFlat => res_dir,
+                }
+            }
+            Platform::Ios => {
+                let xcassets_dir = &app_config
+                    .ios
+                    .as_ref()
+                    .expect("Validation is done in fetcher")
+                    .xcassets;
+                format!("{}/{}.imageset", xcassets_dir, self.res.name)
+            }
+            Platform::Web => app_config
+                .web
+                .as_ref()
+                .expect("Validation is done in fetcher")
+                .assets_dir
+                .clone(),
+        }
+    }
+
+    fn output_file_name(&self) -> String {
+        let extension = if self.vector_drawable {
+            "xml".to_string()
+        } else if self.nine_patch {
+            format!("9.{}", ImageFormat::Png.extension())
+        } else if self.kept_png {
+            ImageFormat::Png.extension()
+        } else {
+            self.format.extension()
+        };
+        match (self.platform, self.layout) {
+            (Platform::Android, ImagesLayout::DrawableDirs) => {
+                format!("{}.{}", &self.res.name, &extension)
+            }
+            (Platform::Android, ImagesLayout::SuffixedFlat) | (Platform::Web, _) => {
+                let locale_suffix = match &self.res.locale {
+                    Some(locale) => format!("_{}", locale),
+                    None => String::new(),
+                };
+                let theme_suffix = if self.res.night { "_dark" } else { "" };
+                let scale_suffix = if self.is_single_scale() {
+                    String::new()
+                } else {
+                    format!("@{}x", self.res.scale.value)
+                };
+                format!(
+                    "{}{}{}{}.{}",
+                    &self.res.name, locale_suffix, theme_suffix, scale_suffix, &extension
+                )
+            }
+            (Platform::Ios, _) => {
+                let theme_suffix = if self.res.night { "_dark" } else { "" };
+                let scale_suffix = if self.is_single_scale() {
+                    String::new()
+                } else {
+                    format!("@{}", self.res.scale.name)
+                };
+                format!(
+                    "{}{}{}.{}",
+                    &self.res.name, theme_suffix, scale_suffix, &extension
+                )
+            }
+        }
+    }
+
+    /// Returns `true` if the image has been exported with the same settings from the same
+    /// version of Figma file or the same node, so it doesn't have to be exported again.
+    fn is_up_to_date(
+        &self,
+        app_config: &AppConfig,
+        options: &ExportOptions,
+        lock: LockState,
+    ) -> bool {
+        match (lock.file_versions.get(&self.file_id), options.force) {
+            (Some(file_version), false) => lock.lock_file.lock().unwrap().is_up_to_date(
+                &format!(
+                    "{}/{}",
+                    self.output_dir(app_config),
+                    self.output_file_name()
+                ),
+                &self.id,
+                file_version,
+                lock.node_hashes
+                    .get(&(self.file_id.clone(), self.id.clone())),
+                &self.export_settings(app_config),
+            ),
+            _ => false,
+        }
+    }
+
+    /// Returns `true` if the image may be exported as the downloaded png instead of webp,
+    /// when the png is smaller.
+    fn may_keep_png(&self, app_config: &AppConfig) -> bool {
+        matches!(self.format, ImageFormat::Webp) && self.webp_options(app_config).keep_smaller
+    }
+
+    /// WebP options of the platform the image is exported for.
+    fn webp_options<'a>(&self, app_config: &'a AppConfig) -> &'a AndroidImagesWebpConfig {
+        match (self.platform, &app_config.web) {
+            (Platform::Web, Some(web)) => &web.images.webp_options,
+            _ => &app_config.android.images.webp_options,
+        }
+    }
+
+    /// JPEG options of the platform the image is exported for.
+    fn jpeg_options<'a>(&self, app_config: &'a AppConfig) -> &'a ImagesJpegConfig {
+        match (self.platform, &app_config.ios, &app_config.web) {
+            (Platform::Ios, Some(ios), _) => &ios.images.jpeg_options,
+            (Platform::Web, _, Some(web)) => &web.images.jpeg_options,
+            _ => &app_config.android.images.jpeg_options,
+        }
+    }
+
+    /// Settings which affect the content of the exported file, to store them in the lock file.
+    fn export_settings(&self, app_config: &AppConfig) -> String {
+        let mut settings = self.format_settings(app_config);
+        let images_config = &app_config.android.images;
+        if let (Platform::Android, false, true, Some(max_dimension)) = (
+            self.platform,
+            self.format.is_svg(),
+            images_config.downscale_oversized,
+            images_config.max_dimension,
+        ) {
+            settings.push_str(&format!(";maxDimension={}", max_dimension));
+        }
+        if self.is_trimmed(app_config) {
+            settings.push_str(";trim");
+        }
+        for transform in self.transforms(app_config) {
+            settings.push_str(&format!(";transform={}", transform.command));
+        }
+        settings
+    }
+
+    /// Whether the transparent borders of the image are cropped, see [trim_if_necessary].
+    fn is_trimmed(&self, app_config: &AppConfig) -> bool {
+        let trim = app_config.android.images.trim;
+        // Fallbacks must have the same bounds as their vector images
+        trim && self.platform == Platform::Android
+            && !self.format.is_svg()
+            && !self.nine_patch
+            && !self.raster_fallback
+    }
+
+    /// Transforms from config, which match the name of the image.
+    fn transforms<'a>(&self, app_config: &'a AppConfig) -> Vec<&'a TransformConfig> {
+        app_config
+            .hooks
+            .transforms
+            .iter()
+            .filter(|t| matches_pattern(&t.pattern, &self.user_name))
+            .collect()
+    }
+
+    /// Settings of the format of the exported file.
+    fn format_settings(&self, app_config: &AppConfig) -> String {
+        let images_config = &app_config.android.images;
+        let svg_options = &images_config.svg_options;
+        match self.format {
+            ImageFormat::Webp => {
+                let webp_options = self.webp_options(app_config);
+                let mut settings = if webp_options.lossless {
+                    format!("webp;scale={};lossless", self.res.scale.value)
+                } else {
+                    format!(
+                        "webp;scale={};quality={}",
+                        self.res.scale.value,
+                        webp_options.quality(self.res.scale.value)
+                    )
+                };
+                if webp_options.keep_smaller {
+                    settings.push_str(";keepSmaller");
+                }
+                match webp_options.alpha {
+                    WebpAlpha::Default => (),
+                    WebpAlpha::Exact => settings.push_str(";alpha=exact"),
+                    WebpAlpha::Bleed => settings.push_str(";alpha=bleed"),
+                }
+                settings
+            }
+            ImageFormat::Png if self.platform == Platform::Android => {
+                let mut settings = format!("png;scale={}", self.res.scale.value);
+                if images_config.png_options.optimize {
+                    settings.push_str(&format!(";optimize={}", images_config.png_options.level));
+                }
+                if images_config.strip_metadata {
+                    settings.push_str(";strip");
+                }
+                settings
+            }
+            ImageFormat::Png => format!("png;scale={}", self.res.scale.value),
+            ImageFormat::Jpeg => format!(
+                "jpeg;scale={};quality={}",
+                self.res.scale.value,
+                self.jpeg_options(app_config).quality
+            ),
+            ImageFormat::Avif => format!(
+                "avif;scale={};quality={}",
+                self.res.scale.value, images_config.avif_options.quality
+            ),
+            ImageFormat::Svg if self.platform == Platform::Android => {
+                let mut settings = match self.vector_drawable {
+                    true => "svg;xml".to_string(),
+                    false => "svg".to_string(),
+                };
+                if !self.vector_drawable && svg_options.optimize {
+                    settings.push_str(&format!(";optimize={}", svg_options.precision));
+                }
+                if let Some(tint) = &svg_options.tint {
+                    settings.push_str(&format!(";tint={}", tint));
+                }
+                settings
+            }
+            ImageFormat::Svg => "svg".to_string(),
         }
     }
 }
 
-pub fn export_images(token: &String, image_names: &[String], yaml_config_path: &String) {
+/// Export images for `images` command: read the config, export images with
+/// [export_images_with_config], then show the summary and write the report.
+pub fn export_images(
+    token_source: &TokenSource,
+    image_names: &[String],
+    yaml_config_path: &String,
+    profile: &Option<String>,
+    options: &ExportOptions,
+) -> ExitStatus {
     let renderer = Renderer();
-    let api = FigmaApi::new(create_http_client(&token));
+    // Nothing is requested from Figma offline with dry run, so the token isn't needed
+    let token = resolve_token(token_source).or_else(|e| match options.offline && options.dry_run {
+        true => Ok(String::new()),
+        false => Err(e),
+    });
+    let result = token.and_then(|token| {
+        let app_config = read_config(yaml_config_path, profile, &renderer)?;
+        export_images_with_config(
+            app_config,
+            yaml_config_path,
+            &token,
+            image_names,
+            options,
+            &renderer,
+        )
+    });
+    let (mut summary, stopped) = match result {
+        Ok(summary) => (summary, false),
+        // The report is written for the images exported before the stop
+        Err(AppError::ExportStopped(cause, summary)) => {
+            let e = AppError::ExportStopped(cause, Box::default());
+            renderer.render(&View::Error(format!("{}", e)));
+            (*summary, true)
+        }
+        Err(e) => {
+            renderer.render(&View::Error(format!("{}", e)));
+            return ExitStatus::ConfigError;
+        }
+    };
+    if !stopped {
+        let done = View::Done {
+            message: Some(summary.describe()),
+        };
+        match summary.stats().describe() {
+            Some(stats) => {
+                renderer.render_line(&done);
+                renderer.render(&View::Stats(stats));
+            }
+            None => renderer.render(&done),
+        }
+    }
 
-    let fetcher_entry = match fetch(&api, &yaml_config_path, FetcherTarget::Images, &renderer) {
-        Ok(fetcher_entry) => fetcher_entry,
+    if let Some(report_path) = &options.report {
+        if let Err(e) = summary.write(report_path) {
+            let e = AppError::CannotWriteReport(report_path.clone(), e.to_string());
+            renderer.render_line(&View::Error(e.to_string()));
+            summary.push_error(&e);
+        }
+    }
+
+    if stopped || summary.has_failures() || (options.warnings_as_errors && summary.warnings() > 0) {
+        ExitStatus::PartialFailure
+    } else {
+        ExitStatus::Success
+    }
+}
+
+/// Add the names from `--names-file` to the names from the command line. If the file
+/// can't be read, the error is shown and the exit status is returned instead.
+pub fn read_image_names(
+    names: Vec<String>,
+    names_file: &Option<String>,
+) -> Result<Vec<String>, ExitStatus> {
+    let path = match names_file {
+        Some(path) => path,
+        None => return Ok(names),
+    };
+    match read_names_file(path) {
+        Ok(file_names) => Ok([names, file_names].concat()),
         Err(e) => {
-            renderer.render(View::Error(format!("{}", e)));
-            return;
+            Renderer().render(&View::Error(e.to_string()));
+            Err(ExitStatus::ConfigError)
         }
+    }
+}
+
+/// Export images with already parsed config. This is the entry point for the programs
+/// which use the app as a library, e.g. build scripts. Returns an error only if nothing
+/// can be exported, problems with particular images are collected in the summary.
+/// With `fail_fast` option, the first problem with an image is returned as
+/// [AppError::ExportStopped] instead, with the summary of the images exported before it.
+/// Their lock file entries, manifest entries and `Contents.json` are written anyway.
+///
+/// # Arguments
+///
+/// * `app_config` - App config.
+/// * `yaml_config_path` - Path of the config, to show it in the errors. The lock file
+///   is stored next to it.
+/// * `token` - Figma personal access token.
+/// * `image_names` - Names of the images to export, can contain `*` and `?` wildcards.
+/// * `options` - Export options, `report` is ignored.
+/// * `renderer` - Renderer to show the progress with.
+pub fn export_images_with_config(
+    mut app_config: AppConfig,
+    yaml_config_path: &String,
+    token: &String,
+    image_names: &[String],
+    options: &ExportOptions,
+    renderer: &dyn Render,
+) -> Result<ExportSummary, AppError> {
+    let started_at = Instant::now();
+    // Warnings of all steps are counted for the summary
+    let warning_counter = WarningCounter::new(renderer);
+    let renderer: &dyn Render = &warning_counter;
+    let (platform, dry_run) = (options.platform, options.dry_run);
+    // Images are still downloaded to compare them, but nothing is written next to them
+    let read_only = dry_run || options.diff;
+    let mut api = FigmaApi::new(token)?;
+    api.set_verbose(options.verbose);
+
+    if let Some(format) = &options.format {
+        override_format(&mut app_config, platform, format);
+    }
+    if let Some(scales) = &options.scales {
+        override_scales(&mut app_config, platform, scales);
+    }
+    if let Some(sizing) = options.sizing {
+        app_config.android.images.sizing = sizing;
+    }
+    let fetcher_target = match &options.node {
+        Some(node) => node_fetcher_target(&mut app_config, platform, node, image_names)?,
+        None => FetcherTarget::Images(platform),
     };
-    let (app_config, names_to_ids) = (fetcher_entry.app_config, fetcher_entry.image_names_to_ids);
+    let fetcher_entry = fetch(
+        &mut api,
+        app_config,
+        yaml_config_path,
+        fetcher_target,
+        options.offline,
+        !options.skip_token_check,
+        renderer,
+    )?;
+    let (mut app_config, names_to_ids) =
+        (fetcher_entry.app_config, fetcher_entry.image_names_to_ids);
+    set_temp_files_dir(&app_config.common.temp_dir);
+    if let Some(output_dir) = &options.output_dir {
+        override_output_dir(&mut app_config, platform, output_dir);
+    }
+    let mut file_versions = fetcher_entry.source_file_versions;
+    if let Some(file_version) = fetcher_entry.file_version {
+        file_versions.insert(app_config.figma.file_id.clone(), file_version);
+    }
+    let names_to_file_ids = fetcher_entry.image_names_to_file_ids;
+    let main_file_id = &app_config.figma.file_id;
+    let dark_names_to_ids = fetcher_entry.dark_image_names_to_ids;
+    let locale_names_to_ids = fetcher_entry.locale_image_names_to_ids;
+    let lock_file = Mutex::new(LockFile::load(yaml_config_path));
+
+    let webp_options = &app_config.android.images.webp_options;
+    if let (Platform::Android, ImageFormat::Webp, true, Some(_)) = (
+        platform,
+        &app_config.android.images.format,
+        webp_options.lossless,
+        &webp_options.quality,
+    ) {
+        renderer.render_line(&View::Warning(
+            "`android.images.webpOptions.quality` is ignored, because `lossless` is enabled"
+                .to_string(),
+        ));
+    }
 
-    let frame_name = &app_config.common.images.figma_frame_name;
-    let format = &app_config.android.images.format;
-    let single_scale_format = format.is_svg();
+    let frame_names = &app_config.common.images.figma_frame_names;
+    let (format, scales) = match (platform, &app_config.ios, &app_config.web) {
+        (Platform::Ios, Some(ios), _) => (&ios.images.format, ios_scales(&ios.images.scales)),
+        (Platform::Web, _, Some(web)) => (&web.images.format, web_scales(&web.images.scales)),
+        _ => (
+            &app_config.android.images.format,
+            android_scales(&app_config.android.images, renderer),
+        ),
+    };
+    let image_names = if options.export_all || image_names.is_empty() {
+        // Every name is taken from the frame, so there is nothing to suggest
+        all_frame_names(&names_to_ids)
+    } else {
+        expand_name_patterns(image_names, frame_names, &names_to_ids, renderer)
+    };
+    let suggestion_options = SuggestionOptions {
+        threshold: app_config.common.images.suggestion_threshold,
+        limit: app_config.common.images.suggestion_limit,
+        interactive: options.interactive,
+    };
+    let image_names = pick_missing_names(
+        frame_names,
+        image_names,
+        &names_to_ids,
+        &suggestion_options,
+        &mut StdinInput,
+        renderer,
+    );
+    let (image_names, ignored_names) =
+        remove_ignored_names(image_names, &app_config.common.images.ignore);
+    if !ignored_names.is_empty() {
+        renderer.render_line(&View::Info(format!(
+            "{} ignored according to `common.images.ignore`: {}",
+            ignored_names.len(),
+            ignored_names.join(", ")
+        )));
+    }
+    let (no_scale_overrides, no_format_overrides) = (HashMap::new(), HashMap::new());
+    let (scale_overrides, format_overrides) = match platform {
+        Platform::Android => (
+            &app_config.android.images.scale_overrides,
+            &app_config.android.images.format_overrides,
+        ),
+        Platform::Ios | Platform::Web => (&no_scale_overrides, &no_format_overrides),
+    };
+    // Nine-patches must be png, Android doesn't support them in other formats
+    let nine_patch_name = |user_name: &String| match platform {
+        Platform::Android => strip_nine_patch_suffix(user_name).map(str::to_string),
+        Platform::Ios | Platform::Web => None,
+    };
+    let nine_patch_format = ImageFormat::Png;
+    // Mixed frames may have vector images exported as svg and raster ones in other formats
+    let format_for = |user_name: &String| match nine_patch_name(user_name) {
+        Some(_) => &nine_patch_format,
+        None => format_overrides.get(user_name).unwrap_or(format),
+    };
+    let vector_drawable = platform == Platform::Android
+        && app_config
+            .android
+            .images
+            .svg_options
+            .convert_to_vector_drawable;
+    let intrinsic_size =
+        platform == Platform::Android && app_config.android.images.sizing == ImageSizing::Intrinsic;
+    let (res_name_prefix, res_name_suffix) = match platform {
+        Platform::Android => (
+            app_config.android.images.res_name_prefix.as_str(),
+            app_config.android.images.res_name_suffix.as_str(),
+        ),
+        Platform::Ios | Platform::Web => ("", ""),
+    };
+    let images_config = &app_config.android.images;
+    let res_dir_type_for = |user_name: &String| {
+        *images_config
+            .res_dir_type_overrides
+            .get(user_name)
+            .unwrap_or(&images_config.res_dir_type)
+    };
+    let layout = match platform {
+        Platform::Android => app_config.android.images.layout,
+        Platform::Ios => ImagesLayout::DrawableDirs,
+        Platform::Web => ImagesLayout::SuffixedFlat,
+    };
     let images_for_export: Vec<ImageInfo> = gather_names(
-        &app_config,
-        &frame_name,
+        frame_names,
         &image_names,
         &names_to_ids,
-        single_scale_format,
+        &scales,
+        scale_overrides,
+        |user_name| format_for(user_name).is_svg() || intrinsic_size,
+        &suggestion_options,
+        renderer,
         |e| ImageInfo {
             id: e.figma_id,
+            file_id: names_to_file_ids
+                .get(&e.user_name)
+                .unwrap_or(main_file_id)
+                .clone(),
             user_name: e.user_name.clone(),
-            format: format.clone(),
+            format: format_for(&e.user_name).clone(),
+            platform,
+            vector_drawable: vector_drawable && format_for(&e.user_name).is_svg(),
+            kept_png: false,
+            intrinsic_size,
+            raster_fallback: false,
+            nine_patch: nine_patch_name(&e.user_name).is_some(),
+            layout,
+            res_dir_type: res_dir_type_for(&e.user_name),
             res: ResourceInfo {
-                name: to_res_name(&e.user_name),
+                name: format!(
+                    "{}{}{}",
+                    res_name_prefix,
+                    to_res_name(
+                        &nine_patch_name(&e.user_name).unwrap_or_else(|| e.user_name.clone())
+                    ),
+                    res_name_suffix
+                ),
                 scale: ScaleInfo {
                     name: e.scale_name.clone(),
                     value: e.scale_value,
                 },
                 night: e.night,
+                locale: None,
             },
         },
     );
+    let images_for_export = match (
+        &dark_names_to_ids,
+        &app_config.common.images.figma_dark_frame_name,
+    ) {
+        (Some(dark_names_to_ids), Some(dark_frame_name)) => add_dark_variants(
+            images_for_export,
+            main_file_id,
+            dark_frame_name,
+            dark_names_to_ids,
+            renderer,
+        ),
+        _ => images_for_export,
+    };
+    let images_for_export = add_locale_variants(
+        images_for_export,
+        main_file_id,
+        &app_config.common.images.figma_locale_frame_names,
+        &locale_names_to_ids,
+        renderer,
+    );
+    let images_for_export = match platform {
+        Platform::Android => add_raster_fallbacks(
+            images_for_export,
+            &app_config.android.images.svg_options.raster_fallback,
+        ),
+        Platform::Ios | Platform::Web => images_for_export,
+    };
 
-    for image in images_for_export {
-        let export_result = export_image(&api, &app_config, &image, &renderer);
+    let invalid_res_name = images_for_export
+        .iter()
+        .find(|i| platform == Platform::Android && !is_valid_res_name(&i.res.name));
+    if let Some(image) = invalid_res_name {
+        return Err(AppError::InvalidResName(
+            image.user_name.clone(),
+            image.res.name.clone(),
+        ));
+    }
+    check_res_name_collisions(&images_for_export)?;
 
-        match &export_result {
-            Err(e) => renderer.render(View::Error(e.to_string())),
-            Ok(()) => (),
+    if let (Platform::Ios, Some(ios), false) = (platform, &app_config.ios, read_only) {
+        create_catalog_if_missing(&ios.xcassets)
+            .map_err(|e| AppError::CannotWriteContentsJson(ios.xcassets.clone(), e.to_string()))?;
+    }
+
+    let mut summary = ExportSummary::default();
+    let webp_savings = Mutex::new(WebpSavings::default());
+    let stats = Mutex::new(ExportStats::default());
+    for image_name in &image_names {
+        if !images_for_export.iter().any(|i| &i.user_name == image_name) {
+            summary.push(SummaryItem::missing(image_name.clone()));
         }
+    }
 
-        renderer.new_line();
+    // Keep the order of the plan in dry run mode, there is nothing to download anyway
+    let concurrency = if dry_run {
+        1
+    } else {
+        app_config.android.images.concurrency
+    };
+    let node_hashes = match (options.only_changed, options.force, dry_run) {
+        (true, false, false) => fetch_node_hashes(&api, &images_for_export, renderer),
+        _ => HashMap::new(),
+    };
+    let lock = LockState {
+        lock_file: &lock_file,
+        file_versions: &file_versions,
+        node_hashes: &node_hashes,
+    };
+    if let (false, true) = (dry_run, app_config.common.images.check_disk_space) {
+        let requirements = estimate_disk_space(&app_config, &images_for_export, concurrency);
+        check_disk_space(&requirements)?;
+    }
+    let download_urls = if dry_run {
+        DownloadUrls::new()
+    } else {
+        fetch_download_urls(
+            &api,
+            &app_config,
+            &images_for_export,
+            options,
+            lock,
+            renderer,
+        )
+    };
+    let images_groups = group_by_node(images_for_export);
+    renderer.start_progress(
+        images_groups.len(),
+        images_groups.iter().map(|g| g.len()).sum(),
+    );
+    let stopped = AtomicBool::new(false);
+    let group_output = app_config.android.images.group_output;
+    let outcomes = map_parallel(images_groups, concurrency, |scales| {
+        let scales_total = scales.len();
+        let mut scales_exported = 0usize;
+        // Lines of the image are shown together, when all its scales are done
+        let grouping = GroupingRenderer::new(renderer);
+        let renderer: &dyn Render = match group_output {
+            true => &grouping,
+            false => renderer,
+        };
+        let mut trim_reference = None;
+        let mut outcomes = Vec::with_capacity(scales_total);
+        for image in scales {
+            if stopped.load(Ordering::Relaxed) {
+                grouping.flush();
+                return outcomes;
+            }
+            let (urls, savings) = (&download_urls, &webp_savings);
+            let mut outcome = export_image(
+                &api,
+                &app_config,
+                image,
+                options,
+                lock,
+                urls,
+                savings,
+                &stats,
+                &mut trim_reference,
+                renderer,
+            );
+            if let Err(e) = &outcome.result {
+                // Other workers will stop before their next image
+                let stops_export = options.fail_fast && !stopped.swap(true, Ordering::Relaxed);
+                if !stops_export {
+                    renderer.render_line(&View::Error(e.to_string()));
+                }
+                outcome.stops_export = stops_export;
+                outcomes.push(outcome);
+                // Don't export the remaining scales of the image which has failed
+                break;
+            }
+            scales_exported += 1;
+            renderer.advance_progress(0, 1);
+            outcomes.push(outcome);
+        }
+        // Remaining scales are done too, if the image has failed
+        grouping.flush();
+        renderer.advance_progress(1, scales_total - scales_exported);
+        outcomes
+    });
+    renderer.finish_progress();
+
+    let mut first_error = None;
+    let mut exported_images = Vec::new();
+    for outcome in outcomes.into_iter().flatten() {
+        summary.push(summary_item(&app_config, &outcome.image, &outcome.result));
+        match outcome.result {
+            Ok(_) => exported_images.push(outcome.image),
+            Err(e) if outcome.stops_export => first_error = Some(e),
+            Err(_) => (),
+        }
+    }
+    // The files of the exported images are written even if the export has been stopped,
+    // but the generated sources would lose the images which haven't been exported
+    let stopped = first_error.is_some();
+
+    // Problems with the files written after the export make it incomplete too,
+    // so they are collected in the summary as well
+    summary.set_webp_savings(webp_savings.into_inner().unwrap());
+    if let (Platform::Ios, false) = (platform, read_only) {
+        if let Err(e) = write_imagesets_contents(&app_config, &exported_images) {
+            renderer.render_line(&View::Error(e.to_string()));
+            summary.push_error(&e);
+        }
+    }
+
+    // Duplicates still have their resources, so they are kept for the Kotlin object
+    // and the adaptive icon, but they have no files anymore
+    let mut deduplicated_names = HashSet::new();
+    let (layout, dedup) = (
+        app_config.android.images.layout,
+        app_config.android.images.dedup,
+    );
+    if let (Platform::Android, false, ImagesLayout::DrawableDirs, true) =
+        (platform, read_only || stopped, layout, dedup)
+    {
+        let mut lock_file = lock_file.lock().unwrap();
+        match deduplicate_images(&app_config, &exported_images, &mut lock_file) {
+            Ok((names, path)) => {
+                if !names.is_empty() {
+                    renderer.render_line(&View::Deduplicated(names.len(), path));
+                }
+                deduplicated_names = names;
+            }
+            Err(e) => {
+                renderer.render_line(&View::Error(e.to_string()));
+                summary.push_error(&e);
+            }
+        }
+    }
+
+    let compose = &app_config.android.images.compose;
+    if let (Platform::Android, false, true) = (platform, read_only || stopped, compose.generate) {
+        if let Err(e) = write_compose_object(&app_config, &exported_images, renderer) {
+            renderer.render_line(&View::Error(e.to_string()));
+            summary.push_error(&e);
+        }
+    }
+
+    let adaptive_icon = &app_config.android.images.adaptive_icon;
+    if let (Platform::Android, false, Some(adaptive_icon)) =
+        (platform, read_only || stopped, adaptive_icon)
+    {
+        match write_adaptive_icon(&app_config, adaptive_icon, &image_names, &exported_images) {
+            Ok(Some(path)) => renderer.render_line(&View::GeneratedAdaptiveIcon(path)),
+            Ok(None) => (),
+            Err(e) => {
+                renderer.render_line(&View::Error(e.to_string()));
+                summary.push_error(&e);
+            }
+        }
+    }
+
+    if let (false, true) = (read_only, app_config.common.images.manifest) {
+        let images_with_files = exported_images
+            .iter()
+            .filter(|image| !deduplicated_names.contains(&image.res.name))
+            .cloned()
+            .collect::<Vec<ImageInfo>>();
+        if let Err(e) = write_manifest(yaml_config_path, &app_config, &images_with_files, renderer)
+        {
+            renderer.render_line(&View::Error(e.to_string()));
+            summary.push_error(&e);
+        }
+    }
+
+    if !read_only {
+        let lock_file = lock_file.into_inner().unwrap();
+        if let Err(e) = lock_file.save() {
+            let e = AppError::CannotWriteLockFile(lock_file.path().clone(), e.to_string());
+            renderer.render_line(&View::Error(e.to_string()));
+            summary.push_error(&e);
+        }
+    }
+
+    let post_export = &app_config.hooks.post_export;
+    if let (false, Some(command)) = (read_only || stopped, post_export) {
+        if summary.count(SummaryStatus::Exported) > 0 {
+            renderer.render_line(&View::RunningHook(command.clone()));
+            if let Err(e) = run_post_export_hook(command, &app_config, platform, &summary) {
+                renderer.render_line(&View::Error(e.to_string()));
+                summary.push_error(&e);
+            }
+        }
     }
 
-    renderer.render(View::Done { message: None });
+    summary.set_warnings(warning_counter.count());
+    summary.set_stats(ExportStats {
+        api: api.stats(),
+        total_time: started_at.elapsed(),
+        ..stats.into_inner().unwrap()
+    });
+    match first_error {
+        Some(e) => Err(AppError::ExportStopped(Box::new(e), Box::new(summary))),
+        None => Ok(summary),
+    }
 }
 
-fn export_image(
-    api: &FigmaApi,
+/// Add dark variants from the dark frame for every image which has no its own dark variant
+/// with `_dark` suffix in the main frame. Images without dark variant are exported as is.
+fn add_dark_variants(
+    images: Vec<ImageInfo>,
+    file_id: &String,
+    dark_frame_name: &String,
+    dark_names_to_ids: &HashMap<String, String>,
+    renderer: &dyn Render,
+) -> Vec<ImageInfo> {
+    let mut output = Vec::with_capacity(images.len() * 2);
+    let mut missing_names: Vec<String> = Vec::new();
+    for image in &images {
+        output.push(image.clone());
+        let themed = images
+            .iter()
+            .any(|i| i.user_name == image.user_name && i.res.night);
+        if themed {
+            continue;
+        }
+        match dark_names_to_ids.get(&image.user_name) {
+            Some(dark_id) => output.push(ImageInfo {
+                id: dark_id.clone(),
+                file_id: file_id.clone(),
+                res: ResourceInfo {
+                    night: true,
+                    ..image.res.clone()
+                },
+                ..image.clone()
+            }),
+            None if !missing_names.contains(&image.user_name) => {
+                missing_names.push(image.user_name.clone());
+                renderer.render_line(&View::NoDarkVariant(
+                    image.user_name.clone(),
+                    dark_frame_name.clone(),
+                ));
+            }
+            None => (),
+        }
+    }
+    output
+}
+
+/// Add localized variants from the locale frames for every light image. Images without
+/// the localized variant are exported only to the default res directories.
+fn add_locale_variants(
+    images: Vec<ImageInfo>,
+    file_id: &String,
+    locale_frame_names: &BTreeMap<String, String>,
+    locale_names_to_ids: &BTreeMap<String, HashMap<String, String>>,
+    renderer: &dyn Render,
+) -> Vec<ImageInfo> {
+    let mut output = images.clone();
+    for (locale, names_to_ids) in locale_names_to_ids {
+        let mut missing_names: Vec<String> = Vec::new();
+        for image in images.iter().filter(|i| !i.res.night) {
+            match names_to_ids.get(&image.user_name) {
+                Some(locale_id) => output.push(ImageInfo {
+                    id: locale_id.clone(),
+                    file_id: file_id.clone(),
+                    res: ResourceInfo {
+                        locale: Some(locale.clone()),
+                        ..image.res.clone()
+                    },
+                    ..image.clone()
+                }),
+                None if !missing_names.contains(&image.user_name) => {
+                    missing_names.push(image.user_name.clone());
+                    renderer.render_line(&View::NoLocaleVariant(
+                        image.user_name.clone(),
+                        locale.clone(),
+                        locale_frame_names[locale].clone(),
+                    ));
+                }
+                None => (),
+            }
+        }
+    }
+    output
+}
+
+/// Add png variants of every svg image at the scales of `svgOptions.rasterFallback`, for older
+/// Android versions, which render vector drawables poorly. Png images are put into the res
+/// directories with density qualifiers, the svg image stays in the one without it.
+fn add_raster_fallbacks(images: Vec<ImageInfo>, scales: &[f32]) -> Vec<ImageInfo> {
+    let mut output = Vec::with_capacity(images.len());
+    for image in &images {
+        output.push(image.clone());
+        let fallback_scales = match image.format.is_svg() {
+            true => scales,
+            false => &[],
+        };
+        for &scale in fallback_scales {
+            let scale_name = density_qualifier(scale).expect("Validation is done in fetcher");
+            output.push(ImageInfo {
+                format: ImageFormat::Png,
+                vector_drawable: false,
+                intrinsic_size: false,
+                raster_fallback: true,
+                res: ResourceInfo {
+                    scale: ScaleInfo {
+                        name: scale_name,
+                        value: scale,
+                    },
+                    ..image.res.clone()
+                },
+                ..image.clone()
+            });
+        }
+    }
+    output
+}
+
+fn summary_item(
     app_config: &AppConfig,
     image: &ImageInfo,
-    renderer: &Renderer,
+    result: &Result<SummaryStatus, AppError>,
+) -> SummaryItem {
+    let path = format!(
+        "{}/{}",
+        image.output_dir(app_config),
+        image.output_file_name()
+    );
+    // Skipped and failed images haven't been converted during this export
+    let exported = matches!(result, Ok(SummaryStatus::Exported));
+    SummaryItem {
+        name: image.user_name.clone(),
+        scale: Some(image.variant_name()),
+        bytes: match exported {
+            true => fs::metadata(&path).ok().map(|m| m.len()),
+            false => None,
+        },
+        path: Some(path),
+        converted_to_webp: exported && matches!(image.format, ImageFormat::Webp) && !image.kept_png,
+        status: match result {
+            Ok(status) => *status,
+            Err(_) => SummaryStatus::Error,
+        },
+        error: result.as_ref().err().map(|e| e.to_string()),
+    }
+}
+
+/// Fetcher target for the node from `--node` option. The link to the node may point
+/// to another Figma file, in that case it replaces the file from config.
+fn node_fetcher_target(
+    app_config: &mut AppConfig,
+    platform: Platform,
+    node: &String,
+    image_names: &[String],
+) -> Result<FetcherTarget, AppError> {
+    let node_ref = parse_node_ref(node).ok_or_else(|| AppError::InvalidNodeRef(node.clone()))?;
+    let name = match image_names {
+        [name] => name.clone(),
+        _ => return Err(AppError::NodeNeedsOneName),
+    };
+    if let Some(file_id) = node_ref.file_id {
+        app_config.figma.file_id = file_id;
+    }
+    Ok(FetcherTarget::Node {
+        platform,
+        name,
+        node_id: node_ref.node_id,
+    })
+}
+
+/// Run the hook with the results of the export in `FXN_*` environment variables.
+fn run_post_export_hook(
+    command: &String,
+    app_config: &AppConfig,
+    platform: Platform,
+    summary: &ExportSummary,
 ) -> Result<(), AppError> {
-    let file_id = &app_config.figma.file_id;
-    let quality = app_config.android.images.webp_options.quality;
-    let node_id = &image.id;
+    let output_dir = match (platform, &app_config.ios, &app_config.web) {
+        (Platform::Ios, Some(ios), _) => ios.xcassets.clone(),
+        (Platform::Web, _, Some(web)) => web.assets_dir.clone(),
+        _ => app_config
+            .main_res_images()
+            .expect("Validation is done in fetcher"),
+    };
+    let envs = [
+        ("FXN_EXPORTED", SummaryStatus::Exported),
+        ("FXN_SKIPPED", SummaryStatus::Skipped),
+        ("FXN_MISSING", SummaryStatus::Missing),
+        ("FXN_FAILED", SummaryStatus::Error),
+    ]
+    .map(|(key, status)| (key, summary.count(status).to_string()));
+    let envs = [envs.as_slice(), &[("FXN_OUTPUT_DIR", output_dir)]].concat();
+    run_hook(command, &envs)
+}
 
-    // Get download url for exported image
-    renderer.render(View::FetchingImage(
-        image.user_name.clone(),
-        image.drawable_dir_name(),
+/// Replace the base directory of exported images in config: `mainRes` for Android,
+/// `xcassets` for iOS or `assetsDir` for web. Drawable and image set directories are
+/// created inside it as usual.
+fn override_output_dir(app_config: &mut AppConfig, platform: Platform, output_dir: &String) {
+    match platform {
+        Platform::Android => app_config.android.images.main_res = Some(output_dir.clone()),
+        Platform::Ios => {
+            if let Some(ios) = app_config.ios.as_mut() {
+                ios.xcassets = output_dir.clone();
+            }
+        }
+        Platform::Web => {
+            if let Some(web) = app_config.web.as_mut() {
+                web.assets_dir = output_dir.clone();
+            }
+        }
+    }
+}
+
+/// Replace the format of exported images in config, before the config is validated,
+/// so the format is checked for the platform as usual. `formatOverrides` still apply.
+fn override_format(app_config: &mut AppConfig, platform: Platform, format: &ImageFormat) {
+    match platform {
+        Platform::Android => app_config.android.images.format = format.clone(),
+        Platform::Ios => {
+            if let Some(ios) = app_config.ios.as_mut() {
+                ios.images.format = format.clone();
+            }
+        }
+        Platform::Web => {
+            if let Some(web) = app_config.web.as_mut() {
+                web.images.format = format.clone();
+            }
+        }
+    }
+}
+
+/// Replace the scales in config, before the config is validated, so invalid scales are reported
+/// as usual. Android scales keep their names from config, other ones are named by their density
+/// qualifiers. `scaleOverrides` are ignored, because the scales are requested explicitly.
+fn override_scales(app_config: &mut AppConfig, platform: Platform, scales: &[f32]) {
+    match platform {
+        Platform::Android => {
+            let images_config = &mut app_config.android.images;
+            let scales = scales
+                .iter()
+                .map(|&scale| {
+                    let name = images_config
+                        .scales
+                        .iter()
+                        .find(|(_, &value)| value == scale)
+                        .map(|(name, _)| name.clone())
+                        .or_else(|| density_qualifier(scale))
+                        .unwrap_or_else(|| scale.to_string());
+                    (name, scale)
+                })
+                .collect();
+            images_config.scales = scales;
+            images_config.scale_overrides.clear();
+        }
+        Platform::Ios => {
+            if let Some(ios) = app_config.ios.as_mut() {
+                ios.images.scales = scales.to_vec();
+            }
+        }
+        Platform::Web => {
+            if let Some(web) = app_config.web.as_mut() {
+                web.images.scales = scales.to_vec();
+            }
+        }
+    }
+}
+
+/// Replace every name with glob wildcards (`*`, `?`) by all matching names from the frame.
+/// Themed images (`_light` and `_dark` pairs) are replaced by their common name, so
+/// they are exported as one themed image. Names without wildcards are kept as is.
+fn expand_name_patterns(
+    image_names: &[String],
+    frame_names: &[String],
+    names_to_ids: &HashMap<String, String>,
+    renderer: &dyn Render,
+) -> Vec<String> {
+    let mut available_names = names_to_ids.keys().cloned().collect::<Vec<String>>();
+    available_names.sort();
+    let mut expanded_names: Vec<String> = Vec::new();
+
+    for image_name in image_names {
+        if !is_pattern(image_name) {
+            expanded_names.push(image_name.clone());
+            continue;
+        }
+        let matched_names = available_names
+            .iter()
+            .filter(|name| matches_pattern(image_name, name))
+            .map(|name| themed_base_name(name, names_to_ids).unwrap_or_else(|| name.clone()))
+            .collect::<Vec<String>>();
+        if matched_names.is_empty() {
+            let frames = describe_frames(frame_names);
+            let e = AppError::NothingMatchesPattern(image_name.clone(), frames);
+            renderer.render_line(&View::Error(e.to_string()));
+        }
+        push_unique(&mut expanded_names, matched_names);
+    }
+    expanded_names
+}
+
+/// Split the names into the ones to export and the ones matching `ignore` names or patterns.
+fn remove_ignored_names(image_names: Vec<String>, ignore: &[String]) -> (Vec<String>, Vec<String>) {
+    image_names
+        .into_iter()
+        .partition(|name| !ignore.iter().any(|pattern| matches_pattern(pattern, name)))
+}
+
+/// Returns names of all images in the frame, themed images are returned by their common name.
+fn all_frame_names(names_to_ids: &HashMap<String, String>) -> Vec<String> {
+    let mut available_names = names_to_ids.keys().cloned().collect::<Vec<String>>();
+    available_names.sort();
+    let mut names: Vec<String> = Vec::new();
+    push_unique(
+        &mut names,
+        available_names
+            .iter()
+            .map(|name| themed_base_name(name, names_to_ids).unwrap_or_else(|| name.clone())),
+    );
+    names
+}
+
+fn push_unique<I: IntoIterator<Item = String>>(names: &mut Vec<String>, new_names: I) {
+    for name in new_names {
+        if !names.contains(&name) {
+            names.push(name);
+        }
+    }
+}
+
+/// Returns the name without `_light`/`_dark` suffix if both themed variants are in the frame.
+fn themed_base_name(name: &str, names_to_ids: &HashMap<String, String>) -> Option<String> {
+    let base_name = name
+        .strip_suffix("_light")
+        .or_else(|| name.strip_suffix("_dark"))?
+        .to_string();
+    let themed = names_to_ids.contains_key(&base_name.with_light_suffix())
+        && names_to_ids.contains_key(&base_name.with_dark_suffix())
+        && !names_to_ids.contains_key(&base_name);
+    if themed {
+        Some(base_name)
+    } else {
+        None
+    }
+}
+
+/// Turn iOS scales from config into the names and values of scales for [gather_names].
+fn ios_scales(scales: &[f32]) -> HashMap<String, f32> {
+    scales
+        .iter()
+        .map(|&s| (ios_scale_name(s).expect("Validation is done in fetcher"), s))
+        .collect()
+}
+
+/// Turn web scales from config into the names and values of scales for [gather_names].
+/// Web images have only the value of the scale in their names, e.g. `img_logo@1.5x.png`.
+fn web_scales(scales: &[f32]) -> HashMap<String, f32> {
+    scales.iter().map(|&s| (s.to_string(), s)).collect()
+}
+
+/// Turn android scales from config into the names and values of scales for [gather_names],
+/// naming them according to `scaleNaming`.
+fn android_scales(
+    images_config: &AndroidImagesConfig,
+    renderer: &dyn Render,
+) -> HashMap<String, f32> {
+    let scales = &images_config.scales;
+    if images_config.scale_naming == ScaleNaming::Raw {
+        return scales.clone();
+    }
+    let mut scale_names = scales.keys().collect::<Vec<&String>>();
+    scale_names.sort();
+    let mut output = HashMap::with_capacity(scales.len());
+    for scale_name in scale_names {
+        let scale = scales[scale_name];
+        let name = density_qualifier(scale).unwrap_or_else(|| {
+            renderer.render_line(&View::Warning(format!(
+                "There is no density qualifier for scale `{}: {}`, `drawable-{}` will be used",
+                scale_name, scale, scale_name
+            )));
+            scale_name.clone()
+        });
+        output.insert(name, scale);
+    }
+    output
+}
+
+/// Generate Kotlin object with references to every successfully exported drawable.
+fn write_compose_object(
+    app_config: &AppConfig,
+    images: &[ImageInfo],
+    renderer: &dyn Render,
+) -> Result<(), AppError> {
+    let compose = &app_config.android.images.compose;
+    let output_path = compose
+        .output_path
+        .as_ref()
+        .expect("Validation is done in fetcher");
+    let package_name = compose
+        .package_name
+        .as_ref()
+        .expect("Validation is done in fetcher");
+
+    let mut res_names = images
+        .iter()
+        .filter(|image| image.res_dir_type == ResDirType::Drawable)
+        .map(|image| image.res.name.clone())
+        .collect::<Vec<String>>();
+    res_names.sort();
+    res_names.dedup();
+
+    let source = drawables_object_source(
+        package_name,
+        &compose.r_package_name,
+        &compose.object_name,
+        &res_names,
+    );
+    write_source(output_path, &source)
+        .map_err(|e| AppError::CannotWriteKotlinFile(output_path.clone(), e.to_string()))?;
+    renderer.render_line(&View::GeneratedKotlinFile(output_path.clone()));
+    Ok(())
+}
+
+/// Write the adaptive icon with the exported foreground and background layers, if at least one
+/// of them has been requested. Returns the path of the written icon.
+fn write_adaptive_icon(
+    app_config: &AppConfig,
+    adaptive_icon: &AndroidAdaptiveIconConfig,
+    image_names: &[String],
+    images: &[ImageInfo],
+) -> Result<Option<String>, AppError> {
+    let (foreground, background) = (&adaptive_icon.foreground, &adaptive_icon.background);
+    if !image_names.contains(foreground) && !image_names.contains(background) {
+        return Ok(None);
+    }
+    let res_dir = app_config
+        .main_res_images()
+        .expect("Validation is done in fetcher");
+    let path = format!(
+        "{}/{}/{}.xml",
+        res_dir, ADAPTIVE_ICON_DIR, adaptive_icon.name
+    );
+    // Both layers are required, otherwise the icon would reference a missing resource
+    let layer_ref = |layer: &str, name: &String| {
+        images
+            .iter()
+            .find(|image| {
+                &image.user_name == name && !image.res.night && image.res.locale.is_none()
+            })
+            .map(|image| format!("@{}/{}", image.res_dir_type.dir_name(), image.res.name))
+            .ok_or_else(|| {
+                AppError::AdaptiveIconLayerMissing(path.clone(), layer.to_string(), name.clone())
+            })
+    };
+    let source = adaptive_icon_source(
+        &layer_ref("foreground", foreground)?,
+        &layer_ref("background", background)?,
+    );
+    write_source(&path, &source)
+        .map_err(|e| AppError::CannotWriteAdaptiveIcon(path.clone(), e.to_string()))?;
+    Ok(Some(path))
+}
+
+/// Replace every resource, which has the same content in all its variants as another exported
+/// resource of the same type, with an alias to that resource in `values/fxn_aliases.xml`,
+/// and remove its files together with their entries in `lock_file`. Returns the names of the
+/// replaced resources and the path of the aliases.
+fn deduplicate_images(
+    app_config: &AppConfig,
+    images: &[ImageInfo],
+    lock_file: &mut LockFile,
+) -> Result<(HashSet<String>, String), AppError> {
+    let res_dir = app_config
+        .main_res_images()
+        .expect("Validation is done in fetcher");
+    let path = format!("{}/values/{}", res_dir, ALIASES_FILE_NAME);
+
+    // Content of every variant of every resource, by type and name of the resource
+    let mut fingerprints: BTreeMap<(&str, &String), Vec<(String, String)>> = BTreeMap::new();
+    let mut unreadable = HashSet::new();
+    for image in images {
+        let file_path = format!(
+            "{}/{}",
+            image.output_dir(app_config),
+            image.output_file_name()
+        );
+        let key = (image.res_dir_type.dir_name(), &image.res.name);
+        match content_hash(&file_path) {
+            Ok(hash) => fingerprints
+                .entry(key)
+                .or_default()
+                .push((image.variant_name(), hash)),
+            Err(_) => {
+                unreadable.insert(key);
+            }
+        }
+    }
+
+    // The first resource by name is kept, the others with the same content become its aliases
+    let mut originals: HashMap<(&str, String), &String> = HashMap::new();
+    let mut new_aliases: BTreeMap<String, Alias> = BTreeMap::new();
+    for ((res_type, name), mut variants) in fingerprints {
+        if unreadable.contains(&(res_type, name)) {
+            continue;
+        }
+        variants.sort();
+        let content = variants
+            .iter()
+            .map(|(variant, hash)| format!("{}:{}", variant, hash))
+            .collect::<Vec<String>>()
+            .join(";");
+        match originals.get(&(res_type, content.clone())) {
+            Some(&original) => {
+                let alias = Alias {
+                    res_type: res_type.to_string(),
+                    target: original.clone(),
+                };
+                new_aliases.insert(name.clone(), alias);
+            }
+            None => {
+                originals.insert((res_type, content), name);
+            }
+        }
+    }
+
+    for image in images {
+        if new_aliases.contains_key(&image.res.name) {
+            let file_path = format!(
+                "{}/{}",
+                image.output_dir(app_config),
+                image.output_file_name()
+            );
+            fs::remove_file(&file_path)
+                .map_err(|e| AppError::CannotWriteAliases(file_path.clone(), e.to_string()))?;
+            lock_file.remove(&file_path);
+        }
+    }
+
+    // Aliases of the resources, which haven't been exported now, are kept
+    let exported_names = images
+        .iter()
+        .map(|image| &image.res.name)
+        .collect::<HashSet<&String>>();
+    let mut aliases = read_aliases(&path);
+    aliases.retain(|name, _| !exported_names.contains(name));
+    for alias in aliases.values_mut() {
+        if let Some(new_alias) = new_aliases.get(&alias.target) {
+            alias.target = new_alias.target.clone();
+        }
+    }
+    let names = new_aliases.keys().cloned().collect::<HashSet<String>>();
+    aliases.append(&mut new_aliases);
+
+    let result = if aliases.is_empty() {
+        match fs::remove_file(&path) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e),
+            _ => Ok(()),
+        }
+    } else {
+        write_source(&path, &aliases_source(&aliases))
+    };
+    result.map_err(|e| AppError::CannotWriteAliases(path.clone(), e.to_string()))?;
+    Ok((names, path))
+}
+
+/// Add every successfully exported image to the manifest next to the config.
+fn write_manifest(
+    yaml_config_path: &String,
+    app_config: &AppConfig,
+    images: &[ImageInfo],
+    renderer: &dyn Render,
+) -> Result<(), AppError> {
+    let mut manifest = Manifest::load(yaml_config_path);
+    for image in images {
+        manifest.insert(
+            format!(
+                "{}/{}",
+                image.output_dir(app_config),
+                image.output_file_name()
+            ),
+            ManifestEntry {
+                figma_name: image.user_name.clone(),
+                res_name: image.res.name.clone(),
+                node_id: image.id.clone(),
+            },
+        );
+    }
+    manifest
+        .save()
+        .map_err(|e| AppError::CannotWriteManifest(manifest.path().clone(), e.to_string()))?;
+    renderer.render_line(&View::WroteManifest(manifest.path().clone()));
+    Ok(())
+}
+
+/// Write `Contents.json` for every image set with successfully exported images.
+fn write_imagesets_contents(app_config: &AppConfig, images: &[ImageInfo]) -> Result<(), AppError> {
+    let mut imagesets: HashMap<String, Vec<ImageSetEntry>> = HashMap::new();
+    for image in images {
+        imagesets
+            .entry(image.output_dir(app_config))
+            .or_default()
+            .push(ImageSetEntry {
+                file_name: image.output_file_name(),
+                scale: if image.is_single_scale() {
+                    None
+                } else {
+                    Some(image.res.scale.value)
+                },
+                dark: image.res.night,
+            });
+    }
+    for (imageset_dir, mut entries) in imagesets {
+        // Keep Contents.json the same between runs
+        entries.sort_by(|a, b| a.file_name.cmp(&b.file_name));
+        write_imageset_contents(&imageset_dir, &entries)
+            .map_err(|e| AppError::CannotWriteContentsJson(imageset_dir.clone(), e.to_string()))?;
+    }
+    Ok(())
+}
+
+/// Make sure that different Figma names are not turned into the same resource name,
+/// otherwise one image would silently overwrite another one.
+fn check_res_name_collisions(images: &[ImageInfo]) -> Result<(), AppError> {
+    let mut user_names: BTreeMap<(&String, bool, &Option<String>), Vec<&String>> = BTreeMap::new();
+    for image in images {
+        let names = user_names
+            .entry((&image.res.name, image.res.night, &image.res.locale))
+            .or_default();
+        if !names.contains(&&image.user_name) {
+            names.push(&image.user_name);
+        }
+    }
+    match user_names.into_iter().find(|(_, names)| names.len() > 1) {
+        Some(((res_name, _, _), names)) => Err(AppError::ResNameCollision(
+            res_name.clone(),
+            names
+                .iter()
+                .map(|name| format!("\"{}\"", name))
+                .collect::<Vec<String>>()
+                .join(", "),
+        )),
+        None => Ok(()),
+    }
+}
+
+/// Estimate how many bytes the export is going to write to the output directories and to the
+/// directory for temporary files. Exported images are expected to stay the same size, new ones
+/// are expected to be [ESTIMATED_IMAGE_BYTES] at scale 1.
+fn estimate_disk_space(
+    app_config: &AppConfig,
+    images: &[ImageInfo],
+    concurrency: usize,
+) -> Vec<(String, u64)> {
+    let mut requirements = Vec::new();
+    let mut largest_image_bytes = 0u64;
+    for image in images {
+        let output_dir = image.output_dir(app_config);
+        let path = format!("{}/{}", &output_dir, image.output_file_name());
+        let (bytes, new_bytes) = match fs::metadata(&path) {
+            // The old file is replaced, so the export takes no more space than it does
+            Ok(metadata) => (metadata.len(), 0),
+            Err(_) => {
+                let scale = image.res.scale.value as f64;
+                let bytes = (ESTIMATED_IMAGE_BYTES as f64 * scale * scale) as u64;
+                (bytes, bytes)
+            }
+        };
+        largest_image_bytes = largest_image_bytes.max(bytes);
+        requirements.push((output_dir, new_bytes));
+    }
+    // Every image being exported has the downloaded and the converted temporary files at most
+    requirements.push((
+        temp_files_dir(),
+        largest_image_bytes * 2 * concurrency as u64,
     ));
-    let image_download_url =
-        api.get_image_download_url(file_id, node_id, image.res.scale.value, &image.format)?;
+    requirements
+}
+
+/// Split the export queue into groups of images with the same figma node,
+/// so every group contains all scales of one image.
+fn group_by_node(images: Vec<ImageInfo>) -> Vec<Vec<ImageInfo>> {
+    let mut groups: Vec<Vec<ImageInfo>> = Vec::new();
+    for image in images {
+        let same_node = |group: &&mut Vec<ImageInfo>| {
+            group[0].id == image.id && group[0].file_id == image.file_id
+        };
+        match groups.iter_mut().find(same_node) {
+            Some(group) => group.push(image),
+            None => groups.push(vec![image]),
+        }
+    }
+    groups
+}
+
+/// Download urls of images by file id, node id, format and scale, see [fetch_download_urls].
+type DownloadUrls = HashMap<(String, String, String, u32), String>;
+
+fn download_url_key(image: &ImageInfo) -> (String, String, String, u32) {
+    (
+        image.file_id.clone(),
+        image.id.clone(),
+        image.format.extension(),
+        image.res.scale.value.to_bits(),
+    )
+}
+
+/// Get download urls of all images which are not up to date, with one request for every
+/// Figma file and scale.
+/// If a request fails, urls of its images will be requested again one by one during the export.
+fn fetch_download_urls(
+    api: &FigmaApi,
+    app_config: &AppConfig,
+    images: &[ImageInfo],
+    options: &ExportOptions,
+    lock: LockState,
+    renderer: &dyn Render,
+) -> DownloadUrls {
+    // Images of different files and formats can't be requested at once
+    let mut batches: BTreeMap<(&String, String, u32), Vec<&ImageInfo>> = BTreeMap::new();
+    for image in images {
+        if !image.is_up_to_date(app_config, options, lock) {
+            let scale = image.res.scale.value.to_bits();
+            batches
+                .entry((&image.file_id, image.format.extension(), scale))
+                .or_default()
+                .push(image);
+        }
+    }
+    let mut download_urls = DownloadUrls::new();
+    for batch in batches.values() {
+        let (scale, format) = (batch[0].res.scale.value, &batch[0].format);
+        let mut node_ids: Vec<String> = Vec::with_capacity(batch.len());
+        push_unique(&mut node_ids, batch.iter().map(|image| image.id.clone()));
+        renderer.render(&View::FetchingImages(
+            node_ids.len(),
+            batch[0].variant_name(),
+        ));
+        let file_id = &batch[0].file_id;
+        match api.get_image_download_urls(file_id, &node_ids, scale, format, renderer) {
+            Ok(urls) => {
+                for (node_id, url) in urls {
+                    let key = (
+                        file_id.clone(),
+                        node_id,
+                        format.extension(),
+                        scale.to_bits(),
+                    );
+                    download_urls.insert(key, url);
+                }
+            }
+            Err(e) => renderer.render_line(&View::Warning(e.to_string())),
+        }
+    }
+    download_urls
+}
+
+/// Fetch hashes of Figma nodes of all images at once for every Figma file. If the request fails,
+/// warn the user and compare the images of the file with the lock file only by the file version.
+fn fetch_node_hashes(
+    api: &FigmaApi,
+    images: &[ImageInfo],
+    renderer: &dyn Render,
+) -> HashMap<(String, String), String> {
+    let mut files: BTreeMap<&String, Vec<String>> = BTreeMap::new();
+    for image in images {
+        push_unique(files.entry(&image.file_id).or_default(), [image.id.clone()]);
+    }
+    let mut node_hashes = HashMap::new();
+    for (file_id, node_ids) in files {
+        renderer.render(&View::FetchingNodeHashes(node_ids.len()));
+        match api.get_node_hashes(file_id, &node_ids, renderer) {
+            Ok(hashes) => node_hashes.extend(
+                hashes
+                    .into_iter()
+                    .map(|(node_id, hash)| ((file_id.clone(), node_id), hash)),
+            ),
+            Err(e) => renderer.render_line(&View::Warning(e.to_string())),
+        }
+    }
+    node_hashes
+}
+
+/// Export one scale of the image. Lock file with the current version of Figma file is passed
+/// as `lock`, to skip images which are up to date. Download url is taken from `download_urls`,
+/// or requested separately if it is missing there. Sizes of the images converted to WEBP
+/// are added to `webp_savings`. If the downloaded png is kept instead of webp, the image of
+/// the outcome is marked with it, so it points to the png file. Time of the conversion and
+/// of writing the file is added to `stats`. The other scales of the image are trimmed the same
+/// way as the one in `trim_reference`. Returns what has been done with the image.
+#[allow(clippy::too_many_arguments)]
+fn export_image(
+    api: &FigmaApi,
+    app_config: &AppConfig,
+    mut image: ImageInfo,
+    options: &ExportOptions,
+    lock: LockState,
+    download_urls: &DownloadUrls,
+    webp_savings: &Mutex<WebpSavings>,
+    stats: &Mutex<ExportStats>,
+    trim_reference: &mut TrimReference,
+    renderer: &dyn Render,
+) -> ExportOutcome {
+    let result = export_image_files(
+        api,
+        app_config,
+        &mut image,
+        options,
+        lock,
+        download_urls,
+        webp_savings,
+        stats,
+        trim_reference,
+        renderer,
+    );
+    ExportOutcome {
+        image,
+        result,
+        stops_export: false,
+    }
+}
+
+/// Write the files of one scale of the image for [export_image].
+#[allow(clippy::too_many_arguments)]
+fn export_image_files(
+    api: &FigmaApi,
+    app_config: &AppConfig,
+    image: &mut ImageInfo,
+    options: &ExportOptions,
+    lock: LockState,
+    download_urls: &DownloadUrls,
+    webp_savings: &Mutex<WebpSavings>,
+    stats: &Mutex<ExportStats>,
+    trim_reference: &mut TrimReference,
+    renderer: &dyn Render,
+) -> Result<SummaryStatus, AppError> {
+    let file_id = image.file_id.clone();
+    let webp_options = image.webp_options(app_config);
+    let node_id = image.id.clone();
+    let full_final_image_dir = image.output_dir(app_config);
+    let full_final_image_path = format!("{}/{}", full_final_image_dir, image.output_file_name());
+
+    // Only tell the user what would be done
+    if options.dry_run {
+        renderer.render_line(&View::DryRunPlan(
+            image.user_name.clone(),
+            image.variant_name(),
+            node_id.clone(),
+            full_final_image_path,
+        ));
+        return Ok(SummaryStatus::Planned);
+    }
+
+    // Don't export image again if nothing has changed since the last export.
+    // The png may have been kept instead of webp at the last export
+    let mut up_to_date = image.is_up_to_date(app_config, options, lock);
+    if !up_to_date && image.may_keep_png(app_config) {
+        image.kept_png = true;
+        up_to_date = image.is_up_to_date(app_config, options, lock);
+        image.kept_png = up_to_date;
+    }
+    if up_to_date && options.diff {
+        renderer.render_line(&View::DiffUnchanged(
+            image.user_name.clone(),
+            image.variant_name(),
+            full_final_image_path,
+        ));
+        return Ok(SummaryStatus::Unchanged);
+    }
+    if up_to_date {
+        renderer.render_line(&View::Skipped(
+            image.user_name.clone(),
+            image.variant_name(),
+        ));
+        return Ok(SummaryStatus::Skipped);
+    }
+    let settings = image.export_settings(app_config);
+
+    // Get download url for exported image, if it hasn't been fetched with the others
+    let image_download_url = match download_urls.get(&download_url_key(image)) {
+        Some(url) => url.clone(),
+        None => {
+            renderer.render(&View::FetchingImage(
+                image.user_name.clone(),
+                image.variant_name(),
+            ));
+            let scale = image.res.scale.value;
+            api.get_image_download_url(&file_id, &node_id, scale, &image.format, renderer)?
+        }
+    };
 
     // Download image from gotten url to app's TEMPORARY dir
-    renderer.render(View::DownloadingImage(
+    renderer.render(&View::DownloadingImage(
         image.user_name.clone(),
-        image.drawable_dir_name(),
+        image.variant_name(),
     ));
-    let image_format = &app_config.android.images.format;
-    let image_temporary_file_name = api.get_image(
+    // Every temporary file is removed at the end, even if the export fails
+    let mut temp_files = TempFiles::default();
+    let downloaded_image = api.get_image(
         &image_download_url,
         &image.res.name,
-        &image.res.scale.name,
-        &image_format,
+        &image.variant_name(),
+        &image.format,
+        renderer,
+    )?;
+    temp_files.track(downloaded_image.file_name.clone());
+    let image_temporary_file_name = downloaded_image.verified(&image.user_name)?;
+    let conversion_started_at = Instant::now();
+    trim_if_necessary(
+        image,
+        &image_temporary_file_name,
+        app_config,
+        trim_reference,
+        renderer,
+    )?;
+    limit_dimensions_if_necessary(
+        image,
+        &image_temporary_file_name,
+        &app_config.android.images,
+        renderer,
     )?;
 
     // So... Convert if necessary :)
-    let image_temporary_file_name =
-        convert_to_webp_if_necessary(&image, image_temporary_file_name, quality, &renderer)?;
+    let image_temporary_file_name = temp_files.track(convert_to_webp_if_necessary(
+        image,
+        image_temporary_file_name,
+        webp_options,
+        webp_savings,
+        renderer,
+    )?);
+    // The image is kept in png, if it is smaller, so the final file may have another extension
+    let full_final_image_path = format!("{}/{}", full_final_image_dir, image.output_file_name());
+    let jpeg_options = image.jpeg_options(app_config);
+    let image_temporary_file_name = temp_files.track(convert_to_jpeg_if_necessary(
+        image,
+        image_temporary_file_name,
+        jpeg_options,
+        renderer,
+    )?);
+    let avif_options = &app_config.android.images.avif_options;
+    let image_temporary_file_name = temp_files.track(convert_to_avif_if_necessary(
+        image,
+        image_temporary_file_name,
+        avif_options,
+        renderer,
+    )?);
+    let svg_options = &app_config.android.images.svg_options;
+    tint_svg_if_necessary(image, &image_temporary_file_name, svg_options, renderer);
+    let image_temporary_file_name = temp_files.track(convert_to_vector_drawable_if_necessary(
+        image,
+        image_temporary_file_name,
+        renderer,
+    )?);
+    let images_config = &app_config.android.images;
+    strip_metadata_if_necessary(image, &image_temporary_file_name, images_config, renderer);
+    let png_options = &images_config.png_options;
+    optimize_png_if_necessary(image, &image_temporary_file_name, png_options, renderer);
+    optimize_svg_if_necessary(image, &image_temporary_file_name, svg_options, renderer);
+    let mut image_temporary_file_name = image_temporary_file_name;
+    for transform in image.transforms(app_config) {
+        image_temporary_file_name = temp_files.track(transform_image(
+            image,
+            image_temporary_file_name,
+            transform,
+            renderer,
+        )?);
+    }
+    stats.lock().unwrap().conversion_time += conversion_started_at.elapsed();
 
-    // Create drawable-XXXX dir in res dir of android project
-    let res_dir = &app_config
-        .main_res_images()
-        .expect("Validation is done in fetcher");
-    let full_final_image_dir = format!("{}/{}", &res_dir, image.drawable_dir_name());
+    // Compare the image with the existing file instead of replacing it
+    if options.diff {
+        return Ok(diff_image(
+            image,
+            &image_temporary_file_name,
+            full_final_image_path,
+            renderer,
+        ));
+    }
+
+    // Create drawable-XXXX dir in res dir of android project or imageset dir in asset catalog
+    let io_started_at = Instant::now();
     create_dir(&full_final_image_dir)
         .map_err(|e| AppError::CannotCreateDrawableDir(e.to_string()))?;
 
-    // Move image from temporary dir to drawable dir of android project
-    let extension = image.format.extension();
-    let full_final_image_path = format!(
-        "{}/{}.{}",
-        full_final_image_dir, &image.res.name, &extension
-    );
+    // Remove the image kept in the other format at the last export, Android doesn't allow
+    // resources with the same name in one directory
+    if image.may_keep_png(app_config) {
+        let other_image = ImageInfo {
+            kept_png: !image.kept_png,
+            ..image.clone()
+        };
+        let other_path = format!(
+            "{}/{}",
+            full_final_image_dir,
+            other_image.output_file_name()
+        );
+        let _ = fs::remove_file(&other_path);
+    }
+
+    // Move image from temporary dir to its final dir
     move_file(&image_temporary_file_name, &full_final_image_path)
         .map_err(|e| AppError::CannotMoveToDrawableDir(image.user_name.clone(), e.to_string()))?;
+    stats.lock().unwrap().io_time += io_started_at.elapsed();
+
+    // Remember exported image, so it will not be exported again while it is up to date
+    let file_version = lock.file_versions.get(&image.file_id);
+    if let (Some(file_version), Ok(hash)) = (file_version, content_hash(&full_final_image_path)) {
+        let entry = LockEntry {
+            node_id: node_id.clone(),
+            file_version: file_version.clone(),
+            settings,
+            hash,
+            node_hash: lock
+                .node_hashes
+                .get(&(image.file_id.clone(), node_id.clone()))
+                .cloned(),
+        };
+        lock.lock_file
+            .lock()
+            .unwrap()
+            .insert(full_final_image_path.clone(), entry);
+    }
 
     // Tell the user that we are done exporting image for this scale
-    renderer.render(View::ImageExported(
+    renderer.render_line(&View::ImageExported(
         image.user_name.clone(),
-        image.drawable_dir_name(),
+        image.variant_name(),
     ));
-    Ok(())
+    Ok(SummaryStatus::Exported)
 }
 
 fn convert_to_webp_if_necessary(
-    image: &ImageInfo,
+    image: &mut ImageInfo,
     image_file_name: String,
-    quality: f32,
-    renderer: &Renderer,
+    webp_options: &AndroidImagesWebpConfig,
+    webp_savings: &Mutex<WebpSavings>,
+    renderer: &dyn Render,
 ) -> Result<String, AppError> {
     match image.format {
         ImageFormat::Webp => {
-            renderer.render(View::ConvertingToWebp(
+            let lossless = webp_options.lossless;
+            renderer.render(&View::ConvertingToWebp(
+                image.user_name.clone(),
+                image.variant_name(),
+                lossless,
+            ));
+            let quality = webp_options.quality(image.res.scale.value);
+            if !lossless
+                && quality < webp::HARD_EDGES_QUALITY_THRESHOLD
+                && webp::has_hard_edges(&image_file_name)
+            {
+                renderer.render_line(&View::Warning(format!(
+                    "`{}` ({}) looks like an icon with hard edges, lossy webp with quality {} may blur it. Consider `lossless: true` or `quality: {}` and higher in `android.images.webpOptions`",
+                    image.user_name,
+                    image.variant_name(),
+                    quality,
+                    webp::HARD_EDGES_QUALITY_THRESHOLD,
+                )));
+            }
+            let file_size = |path: &String| fs::metadata(path).map(|m| m.len()).unwrap_or(0);
+            let started_at = Instant::now();
+            let new_image_path = if lossless {
+                webp::image_to_webp_lossless(&image_file_name, webp_options.alpha)?
+            } else {
+                webp::image_to_webp(&image_file_name, quality, webp_options.alpha)?
+            };
+            let (png_size, webp_size) = (file_size(&image_file_name), file_size(&new_image_path));
+            if webp_options.keep_smaller && webp_size >= png_size {
+                // The png will be moved to the final directory, so it isn't tracked as temporary
+                let _ = fs::remove_file(&new_image_path);
+                image.kept_png = true;
+                renderer.render_line(&View::KeptPng(
+                    image.user_name.clone(),
+                    image.variant_name(),
+                    png_size,
+                    webp_size,
+                ));
+                return Ok(image_file_name);
+            }
+            webp_savings
+                .lock()
+                .unwrap()
+                .add(png_size, webp_size, started_at.elapsed());
+            renderer.render(&View::ConvertedToWebp(
+                image.user_name.clone(),
+                image.variant_name(),
+                lossless,
+            ));
+            Ok(new_image_path)
+        }
+        _ => Ok(image_file_name),
+    }
+}
+
+/// Run the transform command from config for the image. If the command prints the new
+/// image to stdout, it is saved to a new temporary file. Otherwise, the image is considered
+/// changed in place. Returns the path to the transformed image.
+fn transform_image(
+    image: &ImageInfo,
+    image_file_name: String,
+    transform: &TransformConfig,
+    renderer: &dyn Render,
+) -> Result<String, AppError> {
+    renderer.render(&View::Transforming(
+        image.user_name.clone(),
+        image.variant_name(),
+        transform.command.clone(),
+    ));
+    let output = run_transform(&transform.command, &image_file_name, &image.user_name)?;
+    let new_image_path = if output.is_empty() {
+        image_file_name
+    } else {
+        let extension = Path::new(&image_file_name)
+            .extension()
+            .map(|e| e.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let new_image_path = temp_file_name(&image.res.name, &image.variant_name(), &extension);
+        fs::write(&new_image_path, output).map_err(|e| {
+            AppError::CannotWriteTransformedImage(image.user_name.clone(), e.to_string())
+        })?;
+        new_image_path
+    };
+    renderer.render(&View::Transformed(
+        image.user_name.clone(),
+        image.variant_name(),
+        transform.command.clone(),
+    ));
+    Ok(new_image_path)
+}
+
+fn convert_to_jpeg_if_necessary(
+    image: &ImageInfo,
+    image_file_name: String,
+    jpeg_options: &ImagesJpegConfig,
+    renderer: &dyn Render,
+) -> Result<String, AppError> {
+    match image.format {
+        ImageFormat::Jpeg => {
+            renderer.render(&View::ConvertingToJpeg(
+                image.user_name.clone(),
+                image.variant_name(),
+            ));
+            let new_image_path = jpeg::image_to_jpeg(&image_file_name, jpeg_options.quality)?;
+            renderer.render(&View::ConvertedToJpeg(
+                image.user_name.clone(),
+                image.variant_name(),
+            ));
+            Ok(new_image_path)
+        }
+        _ => Ok(image_file_name),
+    }
+}
+
+#[cfg(feature = "avif")]
+fn convert_to_avif_if_necessary(
+    image: &ImageInfo,
+    image_file_name: String,
+    avif_options: &AndroidImagesAvifConfig,
+    renderer: &dyn Render,
+) -> Result<String, AppError> {
+    match image.format {
+        ImageFormat::Avif => {
+            renderer.render(&View::ConvertingToAvif(
                 image.user_name.clone(),
-                image.drawable_dir_name(),
+                image.variant_name(),
             ));
-            let new_image_path = webp::image_to_webp(&image_file_name, quality)?;
-            renderer.render(View::ConvertedToWebp(
+            let new_image_path = avif::image_to_avif(&image_file_name, avif_options.quality)?;
+            renderer.render(&View::ConvertedToAvif(
                 image.user_name.clone(),
-                image.drawable_dir_name(),
+                image.variant_name(),
             ));
             Ok(new_image_path)
         }
         _ => Ok(image_file_name),
     }
 }
+
+/// Without `avif` feature the format is rejected by the config validation.
+#[cfg(not(feature = "avif"))]
+fn convert_to_avif_if_necessary(
+    _image: &ImageInfo,
+    image_file_name: String,
+    _avif_options: &AndroidImagesAvifConfig,
+    _renderer: &dyn Render,
+) -> Result<String, AppError> {
+    Ok(image_file_name)
+}
+
+fn convert_to_vector_drawable_if_necessary(
+    image: &ImageInfo,
+    image_file_name: String,
+    renderer: &dyn Render,
+) -> Result<String, AppError> {
+    if !image.vector_drawable {
+        return Ok(image_file_name);
+    }
+    renderer.render(&View::ConvertingToXml(
+        image.user_name.clone(),
+        image.variant_name(),
+    ));
+    let new_image_path = convert_svg_to_xml(&image_file_name)
+        .map_err(|e| AppError::CannotConvertToXml(image.user_name.clone(), e))?;
+    renderer.render(&View::ConvertedToXml(
+        image.user_name.clone(),
+        image.variant_name(),
+    ));
+    Ok(new_image_path)
+}
+
+/// Compare the exported temporary file with the existing file of the image, and tell the user
+/// if the export would add, change or leave the file unchanged.
+fn diff_image(
+    image: &ImageInfo,
+    image_temporary_file_name: &String,
+    full_final_image_path: String,
+    renderer: &dyn Render,
+) -> SummaryStatus {
+    let (name, variant) = (image.user_name.clone(), image.variant_name());
+    if !Path::new(&full_final_image_path).exists() {
+        renderer.render_line(&View::DiffAdded(name, variant, full_final_image_path));
+        return SummaryStatus::Added;
+    }
+    let new_hash = content_hash(image_temporary_file_name).ok();
+    if new_hash.is_some() && new_hash == content_hash(&full_final_image_path).ok() {
+        renderer.render_line(&View::DiffUnchanged(name, variant, full_final_image_path));
+        SummaryStatus::Unchanged
+    } else {
+        renderer.render_line(&View::DiffChanged(name, variant, full_final_image_path));
+        SummaryStatus::Changed
+    }
+}
+
+/// Check that the downloaded raster image isn't larger than `maxDimension` from config, and
+/// downscale it if it is enabled in config. Otherwise, the export of the image fails.
+/// Nine-patches are never downscaled, because their one pixel borders would be blurred.
+fn limit_dimensions_if_necessary(
+    image: &ImageInfo,
+    image_file_name: &String,
+    images_config: &AndroidImagesConfig,
+    renderer: &dyn Render,
+) -> Result<(), AppError> {
+    let max_dimension = match images_config.max_dimension {
+        Some(max_dimension) if image.platform == Platform::Android => max_dimension,
+        _ => return Ok(()),
+    };
+    if image.format.is_svg() {
+        return Ok(());
+    }
+    let (width, height) = image_dimensions(image_file_name)?;
+    if width.max(height) <= max_dimension {
+        return Ok(());
+    }
+    if !images_config.downscale_oversized || image.nine_patch {
+        return Err(AppError::ImageTooLarge(
+            image.user_name.clone(),
+            image.variant_name(),
+            width,
+            height,
+            max_dimension,
+        ));
+    }
+    let new_dimensions = downscale_image(image_file_name, max_dimension)?;
+    renderer.render_line(&View::Downscaled(
+        image.user_name.clone(),
+        image.variant_name(),
+        (width, height),
+        new_dimensions,
+    ));
+    Ok(())
+}
+
+/// Insets trimmed from the first exported scale of the image, divided by its scale.
+type TrimReference = Option<[f32; 4]>;
+
+/// Crop the fully transparent borders of the downloaded raster image if it is enabled in config.
+/// Every scale is trimmed by its own pixels, so the user is warned if the insets of the scale
+/// don't match the insets of the first scale, `trim_reference`, and the scales may not line up.
+/// Nine-patches are never trimmed, because their one pixel borders are transparent.
+fn trim_if_necessary(
+    image: &ImageInfo,
+    image_file_name: &String,
+    app_config: &AppConfig,
+    trim_reference: &mut TrimReference,
+    renderer: &dyn Render,
+) -> Result<(), AppError> {
+    if !image.is_trimmed(app_config) {
+        return Ok(());
+    }
+    let insets = trim_image(image_file_name)?;
+    let scale = image.res.scale.value;
+    let normalized_insets = insets.to_array().map(|inset| inset as f32 / scale);
+    match trim_reference {
+        // Insets of the scales may differ by a pixel because of rounding
+        Some(reference) => {
+            let differs = reference
+                .iter()
+                .zip(normalized_insets)
+                .any(|(reference, inset)| ((reference - inset) * scale).abs() > 1f32);
+            if differs {
+                renderer.render_line(&View::Warning(format!(
+                    "Transparent borders of `{}` ({}) differ from its other scales, so the trimmed scales may not line up. Remove the margins of the frame in Figma",
+                    image.user_name,
+                    image.variant_name(),
+                )));
+            }
+        }
+        None => *trim_reference = Some(normalized_insets),
+    }
+    if !insets.is_empty() {
+        renderer.render_line(&View::Trimmed(
+            image.user_name.clone(),
+            image.variant_name(),
+            insets,
+        ));
+    }
+    Ok(())
+}
+
+/// Strip metadata of PNG image if it is enabled in config. Other formats are encoded by the app
+/// from the pixels only, so they have no metadata. If stripping fails, warn the user and keep
+/// the original image.
+fn strip_metadata_if_necessary(
+    image: &ImageInfo,
+    image_file_name: &String,
+    images_config: &AndroidImagesConfig,
+    renderer: &dyn Render,
+) {
+    if !images_config.strip_metadata || image.platform != Platform::Android {
+        return;
+    }
+    if let ImageFormat::Png = image.format {
+        renderer.render(&View::StrippingMetadata(
+            image.user_name.clone(),
+            image.variant_name(),
+        ));
+        match png::strip_png_metadata(image_file_name) {
+            Ok(()) => renderer.render(&View::StrippedMetadata(
+                image.user_name.clone(),
+                image.variant_name(),
+            )),
+            Err(e) => renderer.render_line(&View::Warning(format!(
+                "{}, the original image will be used",
+                e
+            ))),
+        }
+    }
+}
+
+/// Optimize PNG image if it is enabled in config. If optimization fails, warn the user
+/// and keep the original image.
+fn optimize_png_if_necessary(
+    image: &ImageInfo,
+    image_file_name: &String,
+    png_options: &AndroidImagesPngConfig,
+    renderer: &dyn Render,
+) {
+    if !png_options.optimize || image.platform != Platform::Android {
+        return;
+    }
+    if let ImageFormat::Png = image.format {
+        renderer.render(&View::OptimizingPng(
+            image.user_name.clone(),
+            image.variant_name(),
+        ));
+        match png::optimize_png(image_file_name, png_options.level) {
+            Ok(()) => renderer.render(&View::OptimizedPng(
+                image.user_name.clone(),
+                image.variant_name(),
+            )),
+            Err(e) => renderer.render_line(&View::Warning(format!(
+                "{}, the original image will be used",
+                e
+            ))),
+        }
+    }
+}
+
+/// Paint SVG image with the single color if it is enabled in config, before it is converted
+/// to vector drawable. Warn the user if the image has several colors and is kept as is.
+fn tint_svg_if_necessary(
+    image: &ImageInfo,
+    image_file_name: &String,
+    svg_options: &AndroidImagesSvgConfig,
+    renderer: &dyn Render,
+) {
+    let tint = match (&svg_options.tint, image.platform) {
+        (Some(tint), Platform::Android) if image.format.is_svg() => tint,
+        _ => return,
+    };
+    renderer.render(&View::TintingSvg(
+        image.user_name.clone(),
+        image.variant_name(),
+    ));
+    match svg::tint_svg(image_file_name, tint) {
+        Ok(true) => renderer.render(&View::TintedSvg(
+            image.user_name.clone(),
+            image.variant_name(),
+        )),
+        Ok(false) => renderer.render_line(&View::Warning(format!(
+            "Image {} has several colors or gradients, so it isn't tinted with {}",
+            image.user_name, tint
+        ))),
+        Err(e) => renderer.render_line(&View::Warning(format!(
+            "{}, the original image will be used",
+            e
+        ))),
+    }
+}
+
+/// Minify SVG image if it is enabled in config and the image isn't converted to vector drawable.
+/// If optimization fails, warn the user and keep the original image.
+fn optimize_svg_if_necessary(
+    image: &ImageInfo,
+    image_file_name: &String,
+    svg_options: &AndroidImagesSvgConfig,
+    renderer: &dyn Render,
+) {
+    if !svg_options.optimize || image.platform != Platform::Android {
+        return;
+    }
+    if image.format.is_svg() && !image.vector_drawable {
+        renderer.render(&View::OptimizingSvg(
+            image.user_name.clone(),
+            image.variant_name(),
+        ));
+        match svg::optimize_svg(image_file_name, svg_options.precision) {
+            Ok(()) => renderer.render(&View::OptimizedSvg(
+                image.user_name.clone(),
+                image.variant_name(),
+            )),
+            Err(e) => renderer.render_line(&View::Warning(format!(
+                "{}, the original image will be used",
+                e
+            ))),
+        }
+    }
+}
+
+#[cfg(test)]
+fn test_image(user_name: &str, res_name: &str) -> ImageInfo {
+    ImageInfo {
+        id: format!("1:{}", user_name.len()),
+        file_id: "main".to_string(),
+        user_name: user_name.to_string(),
+        format: ImageFormat::Png,
+        platform: Platform::Android,
+        vector_drawable: false,
+        kept_png: false,
+        intrinsic_size: false,
+        raster_fallback: false,
+        nine_patch: false,
+        layout: ImagesLayout::DrawableDirs,
+        res_dir_type: ResDirType::Drawable,
+        res: ResourceInfo {
+            name: res_name.to_string(),
+            scale: ScaleInfo {
+                name: "xhdpi".to_string(),
+                value: 2f32,
+            },
+            night: false,
+            locale: None,
+        },
+    }
+}
+
+#[test]
+fn test_check_res_name_collisions() {
+    let logo = test_image("img_logo", "img_logo");
+    let logo_scales = [
+        logo.clone(),
+        ImageInfo {
+            res: ResourceInfo {
+                night: true,
+                ..logo.res.clone()
+            },
+            ..logo.clone()
+        },
+    ];
+    assert!(check_res_name_collisions(&logo_scales).is_ok());
+
+    let images = [logo, test_image("Img Logo", "img_logo")];
+    assert!(matches!(
+        check_res_name_collisions(&images),
+        Err(AppError::ResNameCollision(name, names))
+            if name == "img_logo" && names == "\"img_logo\", \"Img Logo\""
+    ));
+}
+
+#[test]
+fn test_expand_name_patterns() {
+    use crate::common::renderer::{BufferingRenderer, Renderable};
+
+    let names_to_ids = ["img_logo_light", "img_logo_dark", "img_banner", "ic_back"]
+        .into_iter()
+        .map(|name| (name.to_string(), format!("1:{}", name.len())))
+        .collect::<HashMap<String, String>>();
+    let names = ["img_*", "ic_back", "ill_*"].map(String::from);
+    let renderer = BufferingRenderer::default();
+
+    let expanded = expand_name_patterns(&names, &["Images".to_string()], &names_to_ids, &renderer);
+
+    assert_eq!(expanded, ["img_banner", "img_logo", "ic_back"]);
+    let e = AppError::NothingMatchesPattern(names[2].clone(), "frame `Images`".to_string());
+    assert_eq!(renderer.views(), vec![View::Error(e.to_string()).render()]);
+}
+
+#[test]
+fn test_add_dark_variants() {
+    use crate::common::renderer::{BufferingRenderer, Renderable};
+
+    let themed = test_image("img_themed", "img_themed");
+    let themed_dark = ImageInfo {
+        res: ResourceInfo {
+            night: true,
+            ..themed.res.clone()
+        },
+        ..themed.clone()
+    };
+    let images = vec![
+        test_image("img_logo", "img_logo"),
+        test_image("img_photo", "img_photo"),
+        themed,
+        themed_dark,
+    ];
+    let dark_names_to_ids = HashMap::from([
+        ("img_logo".to_string(), "2:1".to_string()),
+        ("img_themed".to_string(), "2:2".to_string()),
+    ]);
+    let renderer = BufferingRenderer::default();
+
+    let images = add_dark_variants(
+        images,
+        &"dark".to_string(),
+        &"Images Dark".to_string(),
+        &dark_names_to_ids,
+        &renderer,
+    );
+
+    let variants = images
+        .iter()
+        .map(|i| {
+            (
+                i.user_name.as_str(),
+                i.id.as_str(),
+                i.file_id.as_str(),
+                i.res.night,
+            )
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(
+        variants,
+        [
+            ("img_logo", "1:8", "main", false),
+            ("img_logo", "2:1", "dark", true),
+            ("img_photo", "1:9", "main", false),
+            ("img_themed", "1:10", "main", false),
+            ("img_themed", "1:10", "main", true),
+        ]
+    );
+    let no_dark = View::NoDarkVariant("img_photo".to_string(), "Images Dark".to_string());
+    assert_eq!(renderer.views(), vec![no_dark.render()]);
+}
+
+#[test]
+fn test_remove_ignored_names() {
+    let names = ["img_logo", "img_draft_1", "ic_wip"]
+        .map(String::from)
+        .to_vec();
+    let ignore = ["img_draft_*", "ic_wip"].map(String::from);
+    let (names, ignored) = remove_ignored_names(names, &ignore);
+    assert_eq!(names, ["img_logo"]);
+    assert_eq!(ignored, ["img_draft_1", "ic_wip"]);
+}
+
+#[test]
+fn test_add_locale_variants() {
+    use crate::common::renderer::{BufferingRenderer, Renderable};
+
+    let logo = test_image("img_logo", "img_logo");
+    let logo_dark = ImageInfo {
+        res: ResourceInfo {
+            night: true,
+            ..logo.res.clone()
+        },
+        ..logo.clone()
+    };
+    let images = vec![logo, logo_dark, test_image("img_photo", "img_photo")];
+    let frame_names = BTreeMap::from([("de".to_string(), "Images DE".to_string())]);
+    let names_to_ids = BTreeMap::from([(
+        "de".to_string(),
+        HashMap::from([("img_logo".to_string(), "3:1".to_string())]),
+    )]);
+    let renderer = BufferingRenderer::default();
+
+    let images = add_locale_variants(
+        images,
+        &"de".to_string(),
+        &frame_names,
+        &names_to_ids,
+        &renderer,
+    );
+
+    assert_eq!(images.len(), 4);
+    let localized = &images[3];
+    assert_eq!((localized.id.as_str(), localized.res.night), ("3:1", false));
+    assert_eq!(localized.drawable_dir_name(), "drawable-de-xhdpi");
+    let no_locale = View::NoLocaleVariant(
+        "img_photo".to_string(),
+        "de".to_string(),
+        "Images DE".to_string(),
+    );
+    assert_eq!(renderer.views(), vec![no_locale.render()]);
+}
+
+#[test]
+fn test_add_raster_fallbacks() {
+    let icon = ImageInfo {
+        format: ImageFormat::Svg,
+        vector_drawable: true,
+        ..test_image("ic_star", "ic_star")
+    };
+    let images = vec![icon, test_image("img_photo", "img_photo")];
+
+    let images = add_raster_fallbacks(images, &[1f32, 3f32]);
+
+    let variants = images
+        .iter()
+        .map(|i| (i.user_name.as_str(), i.variant_name(), i.output_file_name()))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        variants,
+        [
+            ("ic_star", "drawable".to_string(), "ic_star.xml".to_string()),
+            (
+                "ic_star",
+                "drawable-mdpi".to_string(),
+                "ic_star.png".to_string()
+            ),
+            (
+                "ic_star",
+                "drawable-xxhdpi".to_string(),
+                "ic_star.png".to_string()
+            ),
+            (
+                "img_photo",
+                "drawable-xhdpi".to_string(),
+                "img_photo.png".to_string()
+            ),
+        ]
+    );
+    assert!(images[1].raster_fallback && !images[0].raster_fallback);
+}
+
+#[test]
+fn test_ios_file_name() {
+    let image = test_image("img_logo", "img_logo");
+    let ios = ImageInfo {
+        platform: Platform::Ios,
+        res: ResourceInfo {
+            night: true,
+            ..image.res.clone()
+        },
+        ..image.clone()
+    };
+    assert_eq!(ios.output_file_name(), "img_logo_dark@xhdpi.png");
+    let ios_svg = ImageInfo {
+        format: ImageFormat::Svg,
+        ..ios
+    };
+    assert_eq!(ios_svg.output_file_name(), "img_logo_dark.svg");
+}
+
+#[test]
+fn test_suffixed_flat_file_name() {
+    let image = test_image("img_logo", "img_logo");
+    assert_eq!(image.output_file_name(), "img_logo.png");
+    let flat = ImageInfo {
+        layout: ImagesLayout::SuffixedFlat,
+        res: ResourceInfo {
+            night: true,
+            locale: Some("de".to_string()),
+            ..image.res.clone()
+        },
+        ..image
+    };
+    assert_eq!(flat.output_file_name(), "img_logo_de_dark@2x.png");
+    assert_eq!(flat.variant_name(), "img_logo_de_dark@2x.png");
+}
+
+#[test]
+fn test_kept_png_file_name() {
+    let webp = ImageInfo {
+        format: ImageFormat::Webp,
+        ..test_image("img_logo", "img_logo")
+    };
+    assert_eq!(webp.output_file_name(), "img_logo.webp");
+    let kept_png = ImageInfo {
+        kept_png: true,
+        ..webp
+    };
+    assert_eq!(kept_png.output_file_name(), "img_logo.png");
+}
+
+#[test]
+fn test_nine_patch_file_name() {
+    let nine_patch = ImageInfo {
+        nine_patch: true,
+        ..test_image("bg_bubble.9", "bg_bubble")
+    };
+    assert_eq!(nine_patch.output_file_name(), "bg_bubble.9.png");
+}
+
+#[test]
+fn test_web_file_name() {
+    let web = ImageInfo {
+        platform: Platform::Web,
+        res: ResourceInfo {
+            night: true,
+            ..test_image("img_logo", "img_logo").res
+        },
+        ..test_image("img_logo", "img_logo")
+    };
+    assert_eq!(web.output_file_name(), "img_logo_dark@2x.png");
+    let web_svg = ImageInfo {
+        format: ImageFormat::Svg,
+        ..web
+    };
+    assert_eq!(web_svg.output_file_name(), "img_logo_dark.svg");
+}
@@ -1,4 +1,6 @@
 pub mod images;
 pub mod view;
+pub mod watch;
 
-pub use images::export_images;
+pub use images::{export_images, export_images_with_config, read_image_names, ExportOptions};
+pub use watch::watch_images;
@@ -1,20 +1,109 @@
 use crossterm::style::Stylize;
 
+use crate::common::dimensions::Insets;
 use crate::common::renderer::{Indentable, Renderable};
 
 pub enum View {
+    /// Number of images and scale name.
+    FetchingImages(usize, String),
+    FetchingNodeHashes(usize),
     FetchingImage(String, String),
     DownloadingImage(String, String),
-    ConvertingToWebp(String, String),
-    ConvertedToWebp(String, String),
+    /// Image name, scale name and lossless mode.
+    ConvertingToWebp(String, String, bool),
+    ConvertedToWebp(String, String, bool),
+    /// Image name, scale name, sizes of png and webp images.
+    KeptPng(String, String, u64, u64),
+    ConvertingToJpeg(String, String),
+    ConvertedToJpeg(String, String),
+    #[cfg(feature = "avif")]
+    ConvertingToAvif(String, String),
+    #[cfg(feature = "avif")]
+    ConvertedToAvif(String, String),
+    ConvertingToXml(String, String),
+    ConvertedToXml(String, String),
+    OptimizingPng(String, String),
+    OptimizedPng(String, String),
+    OptimizingSvg(String, String),
+    OptimizedSvg(String, String),
+    TintingSvg(String, String),
+    TintedSvg(String, String),
+    /// Image name, scale name and command of the transform.
+    Transforming(String, String, String),
+    Transformed(String, String, String),
+    StrippingMetadata(String, String),
+    StrippedMetadata(String, String),
+    /// Image name, variant, and the original and new width and height.
+    Downscaled(String, String, (u32, u32), (u32, u32)),
+    /// Image name, variant, and the cropped transparent borders.
+    Trimmed(String, String, Insets),
     ImageExported(String, String),
+    Skipped(String, String),
+    NoDarkVariant(String, String),
+    /// Image name, locale and frame name.
+    NoLocaleVariant(String, String, String),
+    GeneratedKotlinFile(String),
+    /// Path to the adaptive icon.
+    GeneratedAdaptiveIcon(String),
+    /// Number of the images replaced with aliases and path to the file with them.
+    Deduplicated(usize, String),
+    WroteManifest(String),
+    /// Command of the hook.
+    RunningHook(String),
+    DryRunPlan(String, String, String, String),
+    /// Image name, variant and path of the file which would be added, changed or left unchanged.
+    DiffAdded(String, String, String),
+    DiffChanged(String, String, String),
+    DiffUnchanged(String, String, String),
+    /// Path to the config.
+    Watching(String),
+    WatchReloaded(String),
+    WatchStopped,
+    Info(String),
+    Warning(String),
     Error(String),
-    Done { message: Option<String> },
+    Done {
+        message: Option<String>,
+    },
+    /// Requests made and time spent by the export.
+    Stats(String),
 }
 
 impl Renderable for View {
+    fn is_important(&self) -> bool {
+        matches!(
+            self,
+            View::Warning(_)
+                | View::NoDarkVariant(..)
+                | View::NoLocaleVariant(..)
+                | View::DiffAdded(..)
+                | View::DiffChanged(..)
+                | View::Error(_)
+                | View::Done { .. }
+                | View::Stats(_)
+        )
+    }
+
+    fn is_warning(&self) -> bool {
+        matches!(
+            self,
+            View::Warning(_) | View::NoDarkVariant(..) | View::NoLocaleVariant(..)
+        )
+    }
+
     fn render(&self) -> String {
         match self {
+            View::FetchingNodeHashes(count) => format!(
+                "{} {} nodes to find changed images",
+                "Fetching".indent().bold().cyan(),
+                &count,
+            ),
+            View::FetchingImages(count, scale) => format!(
+                "{} download urls for {} images ({})",
+                "Fetching".indent().bold().cyan(),
+                &count,
+                &scale,
+            ),
             View::FetchingImage(image_name, scale) => format!(
                 "{} download url for image {} ({})",
                 "Fetching".indent().bold().cyan(),
@@ -27,27 +116,255 @@ impl Renderable for View {
                 &image_name,
                 &scale,
             ),
-            View::ConvertingToWebp(image_name, scale) => format!(
-                "{} to WEBP image {} ({})...",
+            View::ConvertingToWebp(image_name, scale, lossless) => format!(
+                "{} to {}WEBP image {} ({})...",
+                "Converting".indent().bold().cyan(),
+                if *lossless { "lossless " } else { "" },
+                &image_name,
+                &scale,
+            ),
+            View::ConvertedToWebp(image_name, scale, lossless) => format!(
+                "{} to {}WEBP image {} ({})",
+                "Converted".indent().bold().green(),
+                if *lossless { "lossless " } else { "" },
+                &image_name,
+                &scale,
+            ),
+            View::KeptPng(image_name, scale, png_size, webp_size) => format!(
+                "{} PNG image {} ({}), because it is smaller than WEBP: {} vs {} bytes",
+                "Kept".indent().bold().green(),
+                &image_name,
+                &scale,
+                png_size,
+                webp_size,
+            ),
+            View::ConvertingToJpeg(image_name, scale) => format!(
+                "{} to JPEG image {} ({})...",
+                "Converting".indent().bold().cyan(),
+                &image_name,
+                &scale,
+            ),
+            View::ConvertedToJpeg(image_name, scale) => format!(
+                "{} to JPEG image {} ({})",
+                "Converted".indent().bold().green(),
+                &image_name,
+                &scale,
+            ),
+            #[cfg(feature = "avif")]
+            View::ConvertingToAvif(image_name, scale) => format!(
+                "{} to AVIF image {} ({})...",
                 "Converting".indent().bold().cyan(),
                 &image_name,
                 &scale,
             ),
-            View::ConvertedToWebp(image_name, scale) => format!(
-                "{} to WEBP image {} ({})",
+            #[cfg(feature = "avif")]
+            View::ConvertedToAvif(image_name, scale) => format!(
+                "{} to AVIF image {} ({})",
                 "Converted".indent().bold().green(),
                 &image_name,
                 &scale,
             ),
+            View::ConvertingToXml(image_name, scale) => format!(
+                "{} to XML image {} ({})...",
+                "Converting".indent().bold().cyan(),
+                &image_name,
+                &scale,
+            ),
+            View::ConvertedToXml(image_name, scale) => format!(
+                "{} to XML image {} ({})",
+                "Converted".indent().bold().green(),
+                &image_name,
+                &scale,
+            ),
+            View::OptimizingPng(image_name, scale) => format!(
+                "{} PNG image {} ({})...",
+                "Optimizing".indent().bold().cyan(),
+                &image_name,
+                &scale,
+            ),
+            View::OptimizedPng(image_name, scale) => format!(
+                "{} PNG image {} ({})",
+                "Optimized".indent().bold().green(),
+                &image_name,
+                &scale,
+            ),
+            View::OptimizingSvg(image_name, scale) => format!(
+                "{} SVG image {} ({})...",
+                "Optimizing".indent().bold().cyan(),
+                &image_name,
+                &scale,
+            ),
+            View::OptimizedSvg(image_name, scale) => format!(
+                "{} SVG image {} ({})",
+                "Optimized".indent().bold().green(),
+                &image_name,
+                &scale,
+            ),
+            View::TintingSvg(image_name, scale) => format!(
+                "{} SVG image {} ({})...",
+                "Tinting".indent().bold().cyan(),
+                &image_name,
+                &scale,
+            ),
+            View::TintedSvg(image_name, scale) => format!(
+                "{} SVG image {} ({})",
+                "Tinted".indent().bold().green(),
+                &image_name,
+                &scale,
+            ),
+            View::Transforming(image_name, scale, command) => format!(
+                "{} image {} ({}) with `{}`...",
+                "Transforming".indent().bold().cyan(),
+                &image_name,
+                &scale,
+                &command,
+            ),
+            View::Transformed(image_name, scale, command) => format!(
+                "{} image {} ({}) with `{}`",
+                "Transformed".indent().bold().green(),
+                &image_name,
+                &scale,
+                &command,
+            ),
+            View::StrippingMetadata(image_name, scale) => format!(
+                "{} metadata of image {} ({})...",
+                "Stripping".indent().bold().cyan(),
+                &image_name,
+                &scale,
+            ),
+            View::StrippedMetadata(image_name, scale) => format!(
+                "{} metadata of image {} ({})",
+                "Stripped".indent().bold().green(),
+                &image_name,
+                &scale,
+            ),
+            View::Downscaled(image_name, scale, (width, height), (new_width, new_height)) => {
+                format!(
+                    "{} image {} ({}) from {}x{} to {}x{} px",
+                    "Downscaled".indent().bold().green(),
+                    &image_name,
+                    &scale,
+                    width,
+                    height,
+                    new_width,
+                    new_height,
+                )
+            }
+            View::Trimmed(image_name, scale, insets) => format!(
+                "{} image {} ({}) by {}, {}, {}, {} px from left, top, right and bottom",
+                "Trimmed".indent().bold().green(),
+                &image_name,
+                &scale,
+                insets.left,
+                insets.top,
+                insets.right,
+                insets.bottom,
+            ),
             View::ImageExported(image_name, scale) => format!(
                 "{} image {} ({})",
                 "Exported".indent().bold().green(),
                 &image_name,
                 &scale,
             ),
+            View::Skipped(image_name, scale) => format!(
+                "{} image {} ({}), it is up to date",
+                "Skipped".indent().bold().green(),
+                &image_name,
+                &scale,
+            ),
+            View::NoDarkVariant(image_name, frame_name) => format!(
+                "{} dark variant of image {} in frame {}, only light variant will be exported",
+                "Not found".indent().bold().yellow(),
+                &image_name,
+                &frame_name,
+            ),
+            View::GeneratedAdaptiveIcon(path) => format!(
+                "{} adaptive icon {}",
+                "Generated".indent().bold().green(),
+                &path,
+            ),
+            View::Deduplicated(count, path) => format!(
+                "{} {} duplicate images with aliases in {}",
+                "Replaced".indent().bold().green(),
+                count,
+                &path,
+            ),
+            View::NoLocaleVariant(image_name, locale, frame_name) => format!(
+                "{} `{}` variant of image {} in frame {}, only default variant will be exported",
+                "Not found".indent().bold().yellow(),
+                &locale,
+                &image_name,
+                &frame_name,
+            ),
+            View::GeneratedKotlinFile(path) => format!(
+                "{} Kotlin object with images {}",
+                "Generated".indent().bold().green(),
+                &path,
+            ),
+            View::WroteManifest(path) => format!(
+                "{} manifest with Figma names of the images {}",
+                "Written".indent().bold().green(),
+                &path,
+            ),
+            View::RunningHook(command) => format!(
+                "{} post export hook `{}`",
+                "Running".indent().bold().cyan(),
+                &command,
+            ),
+            View::DryRunPlan(image_name, scale, node_id, path) => format!(
+                "{} image {} ({}) from node {} to {}",
+                "Would export".indent().bold().cyan(),
+                &image_name,
+                &scale,
+                &node_id,
+                &path,
+            ),
+            View::DiffAdded(image_name, scale, path) => format!(
+                "{} image {} ({}) {}",
+                "+ Added".indent().bold().green(),
+                &image_name,
+                &scale,
+                &path,
+            ),
+            View::DiffChanged(image_name, scale, path) => format!(
+                "{} image {} ({}) {}",
+                "~ Changed".indent().bold().yellow(),
+                &image_name,
+                &scale,
+                &path,
+            ),
+            View::DiffUnchanged(image_name, scale, path) => format!(
+                "{} image {} ({}) {}",
+                "= Unchanged".indent().bold(),
+                &image_name,
+                &scale,
+                &path,
+            ),
+            View::Info(description) => {
+                format!("{} {}", "Info".indent().bold().cyan(), &description)
+            }
+            View::Warning(description) => {
+                format!("{} {}", "Warning".indent().bold().yellow(), &description)
+            }
             View::Error(description) => {
                 format!("{} {}", "Error".indent().bold().red(), &description)
             }
+            View::Watching(config_path) => format!(
+                "{} config file {} for changes, press Ctrl-C to stop",
+                "Watching".indent().bold().cyan(),
+                &config_path,
+            ),
+            View::WatchReloaded(config_path) => format!(
+                "{} config file {}, exporting images again",
+                "Reloaded".indent().bold().green(),
+                &config_path,
+            ),
+            View::WatchStopped => {
+                format!("{} watching config file", "Stopped".indent().bold().green(),)
+            }
+            View::Stats(description) => {
+                format!("{} {}", "Stats".indent().bold().green(), &description)
+            }
             View::Done { message } => {
                 if let Some(m) = message {
                     format!("{} {}", "Done".indent().bold().green(), &m)
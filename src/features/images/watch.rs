@@ -0,0 +1,105 @@
+use notify::{Event, RecursiveMode, Watcher};
+use std::fs;
+use std::path::PathBuf;
+use std::process;
+use std::sync::mpsc;
+use std::time::Duration;
+
+use crate::common::error::AppError;
+use crate::common::exit_status::ExitStatus;
+use crate::common::renderer::{Render, Renderer};
+use crate::common::token::TokenSource;
+use crate::feature_images::images::{export_images, ExportOptions};
+use crate::feature_images::view::View;
+use crate::models::config::CONFIG_FROM_STDIN;
+
+/// Editors often save the file in several steps, so changes are collected for this time
+/// before the export starts.
+const DEBOUNCE_DELAY: Duration = Duration::from_millis(300);
+
+/// Export images, then export them again every time the YAML config changes on disk,
+/// until the user presses Ctrl-C.
+///
+/// # Arguments
+///
+/// Same as for [export_images].
+pub fn watch_images(
+    token_source: &TokenSource,
+    image_names: &[String],
+    yaml_config_path: &String,
+    profile: &Option<String>,
+    options: &ExportOptions,
+) -> ExitStatus {
+    let renderer = Renderer();
+    let (sender, receiver) = mpsc::channel::<()>();
+    let (mut watcher, watched_path) = match watch_file(yaml_config_path, sender) {
+        Ok(watched) => watched,
+        Err(e) => {
+            renderer.render(&View::Error(format!("{}", e)));
+            return ExitStatus::ConfigError;
+        }
+    };
+    let _ = ctrlc::set_handler(|| {
+        let renderer = Renderer();
+        renderer.new_line();
+        renderer.render_line(&View::WatchStopped);
+        process::exit(0);
+    });
+
+    export_images(
+        token_source,
+        image_names,
+        yaml_config_path,
+        profile,
+        options,
+    );
+    loop {
+        renderer.new_line();
+        renderer.render_line(&View::Watching(yaml_config_path.clone()));
+        if receiver.recv().is_err() {
+            break;
+        }
+        while receiver.recv_timeout(DEBOUNCE_DELAY).is_ok() {}
+        renderer.render_line(&View::WatchReloaded(yaml_config_path.clone()));
+        export_images(
+            token_source,
+            image_names,
+            yaml_config_path,
+            profile,
+            options,
+        );
+    }
+    let _ = watcher.unwatch(&watched_path);
+    ExitStatus::Success
+}
+
+/// Send to `sender` every time the file changes. The parent directory is watched instead of
+/// the file itself, because many editors save the file by replacing it with a new one.
+/// Returns the watcher and the watched directory, to unwatch it later.
+fn watch_file(
+    file_path: &String,
+    sender: mpsc::Sender<()>,
+) -> Result<(notify::RecommendedWatcher, PathBuf), AppError> {
+    let cannot_watch = |e: String| AppError::CannotWatchConfig(file_path.clone(), e);
+    if file_path == CONFIG_FROM_STDIN {
+        return Err(cannot_watch("config is read from stdin".to_string()));
+    }
+    let file_path = fs::canonicalize(file_path).map_err(|e| cannot_watch(e.to_string()))?;
+    let dir_path = file_path
+        .parent()
+        .ok_or_else(|| cannot_watch("config has no parent directory".to_string()))?
+        .to_path_buf();
+    let mut watcher = notify::recommended_watcher(move |event: notify::Result<Event>| {
+        if let Ok(event) = event {
+            let changed = event.kind.is_create() || event.kind.is_modify();
+            if changed && event.paths.iter().any(|p| p == &file_path) {
+                let _ = sender.send(());
+            }
+        }
+    })
+    .map_err(|e| cannot_watch(e.to_string()))?;
+    watcher
+        .watch(&dir_path, RecursiveMode::NonRecursive)
+        .map_err(|e| cannot_watch(e.to_string()))?;
+    Ok((watcher, dir_path))
+}
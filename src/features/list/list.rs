@@ -0,0 +1,94 @@
+use crate::api::figma::FigmaApi;
+use crate::common::exit_status::ExitStatus;
+use crate::common::fetching::{fetch, read_config, FetcherTarget};
+use crate::common::renderer::{Render, Renderer};
+use crate::common::res_name::to_res_name;
+use crate::common::token::{resolve_token, TokenSource};
+use crate::feature_list::view::View;
+use crate::models::config::Platform;
+
+/// Options of `list` command.
+pub struct ListOptions {
+    /// Platform to check the config for.
+    pub platform: Platform,
+    /// List icons from the icons frame instead of images.
+    pub icons: bool,
+    /// Show ids of Figma nodes of the resources.
+    pub node_ids: bool,
+    /// Show resource names the resources would be exported with.
+    pub res_names: bool,
+    /// Show every HTTP request with its status and timing.
+    pub verbose: bool,
+    /// Use the result of the previous fetch instead of loading Figma file.
+    pub offline: bool,
+}
+
+/// Print the names of all images (or icons) in the frames from config, sorted.
+/// Only the Figma file is loaded, nothing is downloaded or written.
+pub fn list_resources(
+    token_source: &TokenSource,
+    yaml_config_path: &String,
+    profile: &Option<String>,
+    options: &ListOptions,
+) -> ExitStatus {
+    let renderer = Renderer();
+    // Nothing is requested from Figma offline, so the token isn't needed
+    let token = match resolve_token(token_source) {
+        Ok(token) => token,
+        Err(_) if options.offline => String::new(),
+        Err(e) => {
+            renderer.render(&View::Error(format!("{}", e)));
+            return ExitStatus::ConfigError;
+        }
+    };
+    let mut api = match FigmaApi::new(&token) {
+        Ok(api) => api,
+        Err(e) => {
+            renderer.render(&View::Error(format!("{}", e)));
+            return ExitStatus::ConfigError;
+        }
+    };
+    api.set_verbose(options.verbose);
+
+    let fetcher_target = match options.icons {
+        true => FetcherTarget::Icons,
+        false => FetcherTarget::Images(options.platform),
+    };
+    let fetcher_entry =
+        match read_config(yaml_config_path, profile, &renderer).and_then(|app_config| {
+            fetch(
+                &mut api,
+                app_config,
+                yaml_config_path,
+                fetcher_target,
+                options.offline,
+                false,
+                &renderer,
+            )
+        }) {
+            Ok(fetcher_entry) => fetcher_entry,
+            Err(e) => {
+                renderer.render(&View::Error(format!("{}", e)));
+                return ExitStatus::ConfigError;
+            }
+        };
+
+    let mut names_to_ids = fetcher_entry
+        .image_names_to_ids
+        .into_iter()
+        .collect::<Vec<(String, String)>>();
+    names_to_ids.sort();
+    renderer.new_line();
+    for (name, node_id) in &names_to_ids {
+        renderer.render_line(&View::Resource {
+            name: name.clone(),
+            node_id: options.node_ids.then(|| node_id.clone()),
+            res_name: options.res_names.then(|| to_res_name(name)),
+        });
+    }
+    renderer.render(&View::Done {
+        message: Some(format!("{} found", names_to_ids.len())),
+    });
+    renderer.new_line();
+    ExitStatus::Success
+}
@@ -0,0 +1,4 @@
+pub mod list;
+pub mod view;
+
+pub use list::{list_resources, ListOptions};
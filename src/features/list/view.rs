@@ -0,0 +1,51 @@
+use crossterm::style::Stylize;
+
+use crate::common::renderer::{Indentable, Renderable};
+
+pub enum View {
+    /// Figma name of the resource, with its node id and resource name if they are requested.
+    Resource {
+        name: String,
+        node_id: Option<String>,
+        res_name: Option<String>,
+    },
+    Error(String),
+    Done {
+        message: Option<String>,
+    },
+}
+
+impl Renderable for View {
+    fn is_important(&self) -> bool {
+        matches!(
+            self,
+            View::Resource { .. } | View::Error(_) | View::Done { .. }
+        )
+    }
+
+    fn render(&self) -> String {
+        match self {
+            View::Resource {
+                name,
+                node_id,
+                res_name,
+            } => {
+                let mut line = format!("{}{}", "".indent(), name);
+                if let Some(node_id) = node_id {
+                    line.push_str(&format!("  {}", node_id.as_str().dark_grey()));
+                }
+                if let Some(res_name) = res_name {
+                    line.push_str(&format!("  {}", res_name.as_str().cyan()));
+                }
+                line
+            }
+            View::Error(description) => {
+                format!("{} {}", "Error".indent().bold().red(), &description)
+            }
+            View::Done { message } => match message {
+                Some(msg) => format!("{} {}", "Done".indent().bold().green(), &msg),
+                None => format!("{}", "Done".indent().bold().green()),
+            },
+        }
+    }
+}
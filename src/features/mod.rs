@@ -1,4 +1,7 @@
+pub mod check;
 pub mod cleanup;
+pub mod colors;
 pub mod config;
 pub mod icons;
 pub mod images;
+pub mod list;
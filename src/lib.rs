@@ -0,0 +1,47 @@
+//! Export images and icons from Figma to Android and iOS projects.
+//!
+//! The `fxa` executable is a thin wrapper over this library. To export images from another
+//! program, e.g. a build script, parse the config and call [export_images_with_config]:
+//!
+//! ```no_run
+//! use fxa::{export_images_with_config, AppConfig, ExportOptions, Platform, Renderer};
+//!
+//! let config_path = "fxn.yaml".to_string();
+//! let app_config = AppConfig::from_file(&config_path).unwrap();
+//! let options = ExportOptions {
+//!     platform: Platform::Android,
+//!     export_all: true,
+//!     ..Default::default()
+//! };
+//! let token = std::env::var("FXN_FIGMA_TOKEN").unwrap();
+//! let summary =
+//!     export_images_with_config(app_config, &config_path, &token, &[], &options, &Renderer())
+//!         .unwrap();
+//! for item in summary.items() {
+//!     println!("{} {:?}: {:?}", item.name, item.scale, item.status);
+//! }
+//! assert!(!summary.has_failures());
+//! ```
+//!
+//! Every item of the [ExportSummary] tells what has happened to one variant of the image,
+//! e.g. to one scale, so a wrapper can make its own summary instead of the JSON report.
+#![allow(clippy::module_inception, clippy::ptr_arg)]
+
+pub mod api;
+pub mod common;
+pub mod features;
+pub mod models;
+
+use crate::features::check as feature_check;
+use crate::features::cleanup as feature_cleanup;
+use crate::features::colors as feature_colors;
+use crate::features::config as feature_config;
+use crate::features::icons as feature_icons;
+use crate::features::images as feature_images;
+use crate::features::list as feature_list;
+
+pub use crate::common::error::AppError;
+pub use crate::common::renderer::{BufferingRenderer, Render, Renderer};
+pub use crate::common::report::{ExportSummary, SummaryItem, SummaryStatus};
+pub use crate::feature_images::{export_images_with_config, ExportOptions};
+pub use crate::models::config::{AppConfig, Platform};
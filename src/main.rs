@@ -1,32 +1,171 @@
-mod api;
-mod common;
-mod features;
-mod models;
-
 use clap::Parser;
+use std::process;
 
-use crate::features::cleanup as feature_cleanup;
-use crate::features::config as feature_config;
-use crate::features::icons as feature_icons;
-use crate::features::images as feature_images;
-use crate::models::entrypoint::{Args, Command};
+use fxa::common::renderer::Renderer;
+use fxa::common::token::TokenSource;
+use fxa::features::check as feature_check;
+use fxa::features::cleanup as feature_cleanup;
+use fxa::features::colors as feature_colors;
+use fxa::features::config as feature_config;
+use fxa::features::icons as feature_icons;
+use fxa::features::images as feature_images;
+use fxa::features::list as feature_list;
+use fxa::models::entrypoint::{Args, Command};
 
 fn main() {
     let args = Args::parse();
-    match args.subcommand {
+    let exit_status = match args.subcommand {
         Command::Config {
             new_config_filename,
         } => feature_config::create_default_config(&new_config_filename),
         Command::Images {
             token,
+            token_file,
             path_to_config,
+            profile,
+            platform,
+            all,
+            dry_run,
+            diff,
+            interactive,
+            force,
+            only_changed,
+            report,
+            output_dir,
+            format,
+            scales,
+            sizing,
+            node,
+            skip_token_check,
+            fail_fast,
+            warnings_as_errors,
+            offline,
+            verbose,
+            quiet,
+            watch,
+            names_file,
             names,
-        } => feature_images::export_images(&token, &names, &path_to_config),
+        } => {
+            Renderer::set_quiet(quiet);
+            let names = match feature_images::read_image_names(names, &names_file) {
+                Ok(names) => names,
+                Err(exit_status) => process::exit(exit_status.code()),
+            };
+            let token_source = TokenSource { token, token_file };
+            let options = feature_images::ExportOptions {
+                platform,
+                export_all: all,
+                dry_run,
+                diff,
+                interactive,
+                force,
+                only_changed,
+                report,
+                output_dir,
+                format,
+                scales,
+                sizing,
+                verbose,
+                node,
+                skip_token_check,
+                fail_fast,
+                warnings_as_errors,
+                offline,
+            };
+            if watch {
+                feature_images::watch_images(
+                    &token_source,
+                    &names,
+                    &path_to_config,
+                    &profile,
+                    &options,
+                )
+            } else {
+                feature_images::export_images(
+                    &token_source,
+                    &names,
+                    &path_to_config,
+                    &profile,
+                    &options,
+                )
+            }
+        }
         Command::Icons {
             token,
+            token_file,
             path_to_config,
+            profile,
+            verbose,
+            quiet,
             names,
-        } => feature_icons::export_icons(&token, &names, &path_to_config),
+        } => {
+            Renderer::set_quiet(quiet);
+            feature_icons::export_icons(
+                &TokenSource { token, token_file },
+                &names,
+                &path_to_config,
+                &profile,
+                verbose,
+            )
+        }
+        Command::Colors {
+            token,
+            token_file,
+            path_to_config,
+            profile,
+            verbose,
+            quiet,
+        } => {
+            Renderer::set_quiet(quiet);
+            feature_colors::export_colors(
+                &TokenSource { token, token_file },
+                &path_to_config,
+                &profile,
+                verbose,
+            )
+        }
+        Command::List {
+            token,
+            token_file,
+            path_to_config,
+            profile,
+            platform,
+            icons,
+            ids,
+            res_names,
+            offline,
+            verbose,
+        } => feature_list::list_resources(
+            &TokenSource { token, token_file },
+            &path_to_config,
+            &profile,
+            &feature_list::ListOptions {
+                platform,
+                icons,
+                node_ids: ids,
+                res_names,
+                verbose,
+                offline,
+            },
+        ),
+        Command::Check {
+            token,
+            token_file,
+            path_to_config,
+            profile,
+            platform,
+            verbose,
+        } => feature_check::check_environment(
+            &TokenSource { token, token_file },
+            &path_to_config,
+            &profile,
+            &feature_check::CheckOptions { platform, verbose },
+        ),
+        Command::Init {
+            new_config_filename,
+            force,
+        } => feature_config::init_config(&new_config_filename, force),
         Command::Cleanup => feature_cleanup::cleanup(),
-    }
+    };
+    process::exit(exit_status.code());
 }
@@ -1,46 +1,176 @@
-use serde::Deserialize;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use serde_yaml::Value;
+use std::collections::BTreeMap;
 use std::collections::HashMap;
 use std::fs::File;
+use std::io;
 
 use crate::common::error::AppError;
+use crate::common::paths::resolve_config_path;
+
+/// Path of the config, which means that the config is read from stdin.
+/// Relative paths in such config are resolved against the working directory.
+pub const CONFIG_FROM_STDIN: &str = "-";
 
 /// App config from YAML:
 /// ```yaml
 /// figma:
 ///     fileId: "..."
 ///     pageName: "..."
+///     retry:
+///         maxRetries: 3
+///         baseDelayMs: 1000
+///     requestsPerMinute: 60
+///     baseUrl: "https://api.figma.com/"
+/// http:
+///     proxy: "http://proxy.example.com:8080"
+///     timeoutSecs: 30
+///     downloadTimeoutSecs: 300
+///     headers:
+///         X-Gateway-Key: "..."
+/// hooks:
+///     postExport: "git add app/src/main/res"
+///     transforms:
+///         - match: "img_*"
+///           command: "pngquant --quality 80 - < \"$1\""
 /// common:
+///     tempDir: /mnt/fast/fxa
 ///     images:
-///         figmaFrameName: Images
+///         figmaFrameName: Images | [Images, Illustrations]
+///         figmaDarkFrameName: ImagesDark
+///         figmaLocaleFrameNames:
+///             de: ImagesDe
+///         figmaSources:
+///             - fileId: shPilWnVdJfo10YF12345
+///               frameName: Illustrations
+///         suggestionThreshold: 0.0..1.0
+///         suggestionLimit: 5
+///         manifest: true | false
+///         checkDiskSpace: true | false
+///         expandVariants: true | false
+///         ignore: [img_draft_*, img_annotation]
 ///     icons:
 ///         figmaFrameName: Icons
 /// android:
 ///     mainRes: "./main/res"
 ///     images:
 ///         mainRes: "./main/res"
-///         scales:
+///         scales: # or auto
 ///             mdpi: 1.0
 ///             hdpi: 1.5
 ///             xhdpi: 2.0
 ///             xxhdpi: 3.0
-///         format: svg | png | webp
+///         scaleNaming: raw | density
+///         layout: drawableDirs | suffixedFlat
+///         sizing: density | intrinsic
+///         format: svg | png | webp | jpeg | avif
 ///         webpOptions:
+///             quality: 0..100 | {1: 90, 4: 75}
+///             lossless: true | false
+///             keepSmaller: true | false
+///             alpha: default | exact | bleed
+///         jpegOptions:
+///             quality: 1..100
+///         avifOptions:
 ///             quality: 0..100
+///         svgOptions:
+///             convertToVectorDrawable: true | false
+///             optimize: true | false
+///             precision: 3
+///             tint: "#1C1B1F"
+///             rasterFallback: [1, 2, 3]
+///         pngOptions:
+///             optimize: true | false
+///             level: 0..6
+///         stripMetadata: true | false
+///         maxDimension: 4096
+///         downscaleOversized: true | false
+///         trim: true | false
+///         concurrency: 4
+///         groupOutput: true
+///         scaleOverrides:
+///             ic_logo: [1.0, 2.0]
+///         formatOverrides:
+///             ic_logo: svg
+///         resDirType: drawable | mipmap
+///         resDirTypeOverrides:
+///             ic_launcher: mipmap
+///         compose:
+///             generate: true | false
+///             packageName: "com.example.ui"
+///             rPackageName: "com.example"
+///             objectName: AppImages
+///             outputPath: "./main/kotlin/com/example/ui/AppImages.kt"
+///         adaptiveIcon:
+///             name: ic_launcher
+///             foreground: ic_launcher_foreground
+///             background: ic_launcher_background
+///         dedup: true | false
+///         resNamePrefix: ds_
+///         resNameSuffix: ""
 ///     icons:
 ///         mainRes: "./main/res"
 ///         format: svg | xml
+///     colors:
+///         mainRes: "./main/res"
+///         fileName: colors.xml
+///         compose:
+///             generate: true | false
+///             packageName: "com.example.ui"
+///             objectName: AppColors
+///             outputPath: "./main/kotlin/com/example/ui/AppColors.kt"
+/// ios:
+///     xcassets: "./App/Assets.xcassets"
+///     images:
+///         scales: auto | [1, 2, 3]
+///         format: svg | png | jpeg
+///         jpegOptions:
+///             quality: 1..100
+/// web:
+///     assetsDir: "./src/assets/images"
+///     images:
+///         scales: [1, 2, 3]
+///         format: svg | png | webp | jpeg
+///         webpOptions:
+///             quality: 0..100
+///         jpegOptions:
+///             quality: 1..100
+/// profiles:
+///     release: # any part of this config, overlaid on it with `--profile release`
+///         common:
+///             images:
+///                 figmaFrameName: ReleaseImages
 /// ```
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct AppConfig {
     pub figma: FigmaConfig,
+    #[serde(default = "default_http_config")]
+    pub http: HttpConfig,
+    #[serde(default = "default_hooks_config")]
+    pub hooks: HooksConfig,
     #[serde(default = "default_common_config")]
     pub common: CommonConfig,
+    #[serde(default = "default_android_config")]
     pub android: AndroidConfig,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ios: Option<IosConfig>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub web: Option<WebConfig>,
+}
+
+/// Target platform of the exported resources.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum Platform {
+    Android,
+    Ios,
+    /// Flat directory of images with the scale in their names, e.g. for React Native or web.
+    Web,
 }
 
 fn default_common_config() -> CommonConfig {
     CommonConfig {
+        temp_dir: None,
         images: default_common_images_config(),
         icons: default_common_icons_config(),
     }
@@ -51,24 +181,179 @@ fn default_common_config() -> CommonConfig {
 /// figma:
 ///     fileId: "..."
 ///     pageName: "..."
+///     retry:
+///         maxRetries: 3
+///         baseDelayMs: 1000
+///     requestsPerMinute: 60
+///     baseUrl: "https://api.figma.com/"
+///     authType: auto | personal | oauth
 /// ```
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct FigmaConfig {
     pub file_id: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub page_name: Option<String>,
+    #[serde(default = "default_retry_config")]
+    pub retry: FigmaRetryConfig,
+    /// Maximum rate of the requests to Figma API. If not specified, the requests aren't paced.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub requests_per_minute: Option<u32>,
+    /// Url of Figma API to send the requests to instead of `https://api.figma.com/`,
+    /// e.g. for Figma for enterprise or a mock server.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub base_url: Option<String>,
+    /// How the token is sent to Figma API.
+    #[serde(default = "default_auth_type")]
+    pub auth_type: AuthType,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum AuthType {
+    /// Detect the type of the token by its prefix.
+    Auto,
+    /// Personal access token, sent in `X-Figma-Token` header.
+    Personal,
+    /// OAuth access token, sent in `Authorization: Bearer` header.
+    #[serde(rename = "oauth")]
+    OAuth,
+}
+
+fn default_auth_type() -> AuthType {
+    AuthType::Auto
+}
+
+/// Part of App config from YAML:
+/// ```yaml
+/// retry:
+///     maxRetries: 3
+///     baseDelayMs: 1000
+/// ```
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FigmaRetryConfig {
+    #[serde(default = "default_max_retries")]
+    pub max_retries: u32,
+    #[serde(default = "default_base_delay_ms")]
+    pub base_delay_ms: u64,
+}
+
+fn default_retry_config() -> FigmaRetryConfig {
+    FigmaRetryConfig {
+        max_retries: default_max_retries(),
+        base_delay_ms: default_base_delay_ms(),
+    }
+}
+
+/// Part of App config from YAML:
+/// ```yaml
+/// http:
+///     proxy: "http://proxy.example.com:8080"
+///     timeoutSecs: 30
+///     downloadTimeoutSecs: 300
+///     headers:
+///         X-Gateway-Key: "..."
+/// ```
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HttpConfig {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub proxy: Option<String>,
+    /// Timeout of the requests to Figma API, also used to connect to the hosts.
+    #[serde(default = "default_timeout_secs")]
+    pub timeout_secs: u64,
+    /// Timeout of the downloads of the Figma file and images, which may be large.
+    #[serde(default = "default_download_timeout_secs")]
+    pub download_timeout_secs: u64,
+    /// Additional headers of all requests, e.g. `User-Agent` required by a corporate proxy.
+    #[serde(default, serialize_with = "sorted_map")]
+    pub headers: HashMap<String, String>,
+}
+
+fn default_http_config() -> HttpConfig {
+    HttpConfig {
+        proxy: None,
+        timeout_secs: default_timeout_secs(),
+        download_timeout_secs: default_download_timeout_secs(),
+        headers: HashMap::new(),
+    }
+}
+
+/// Part of App config from YAML:
+/// ```yaml
+/// hooks:
+///     postExport: "git add app/src/main/res"
+///     transforms:
+///         - match: "img_*"
+///           command: "pngquant --quality 80 - < \"$1\""
+/// ```
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HooksConfig {
+    /// Shell command to run after the export of images, if at least one image has been exported.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub post_export: Option<String>,
+    /// Commands to process every exported image before it is moved to the output directory.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub transforms: Vec<TransformConfig>,
+}
+
+/// Part of App config from YAML:
+/// ```yaml
+/// match: "img_*"
+/// command: "pngquant --quality 80 - < \"$1\""
+/// ```
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct TransformConfig {
+    /// Names of the images in Figma to transform, can contain `*` and `?` wildcards.
+    #[serde(rename = "match")]
+    pub pattern: String,
+    /// Shell command, which gets the path of the image as `$1` and in `FXN_FILE` environment
+    /// variable. Its stdout replaces the image, if it isn't empty.
+    pub command: String,
+}
+
+fn default_hooks_config() -> HooksConfig {
+    HooksConfig {
+        post_export: None,
+        transforms: Vec::new(),
+    }
+}
+
+fn default_timeout_secs() -> u64 {
+    30
+}
+
+fn default_download_timeout_secs() -> u64 {
+    300
+}
+
+fn default_max_retries() -> u32 {
+    3
+}
+
+fn default_base_delay_ms() -> u64 {
+    1000
 }
 
 /// Part of App config from YAML:
 /// ```yaml
 /// common:
+///     tempDir: /mnt/fast/fxa
 ///     images:
-///         figmaFrameName: Images
+///         figmaFrameName: Images | [Images, Illustrations]
+///         figmaDarkFrameName: ImagesDark
+///         suggestionThreshold: 0.0..1.0
+///         suggestionLimit: 5
 ///     icons:
 ///         figmaFrameName: Icons
 /// ```
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Serialize)]
 pub struct CommonConfig {
+    /// Directory for the intermediate files of the export, `.fxa` in the working directory by default.
+    #[serde(rename = "tempDir", skip_serializing_if = "Option::is_none")]
+    pub temp_dir: Option<String>,
     #[serde(default = "default_common_images_config")]
     pub images: CommonImagesConfig,
     #[serde(default = "default_common_icons_config")]
@@ -77,10 +362,27 @@ pub struct CommonConfig {
 
 fn default_common_images_config() -> CommonImagesConfig {
     CommonImagesConfig {
-        figma_frame_name: "Images".to_string(),
+        figma_frame_names: vec!["Images".to_string()],
+        figma_dark_frame_name: None,
+        figma_locale_frame_names: BTreeMap::new(),
+        figma_sources: Vec::new(),
+        suggestion_threshold: default_suggestion_threshold(),
+        suggestion_limit: default_suggestion_limit(),
+        manifest: false,
+        check_disk_space: false,
+        expand_variants: false,
+        ignore: Vec::new(),
     }
 }
 
+fn default_suggestion_threshold() -> f64 {
+    0.0
+}
+
+fn default_suggestion_limit() -> usize {
+    5
+}
+
 fn default_common_icons_config() -> CommonIconsConfig {
     CommonIconsConfig {
         figma_frame_name: "Icons".to_string(),
@@ -90,12 +392,87 @@ fn default_common_icons_config() -> CommonIconsConfig {
 /// Part of App config from YAML:
 /// ```yaml
 /// images:
-///     figmaFrameName: Images
+///     figmaFrameName: Images | [Images, Illustrations]
+///     figmaDarkFrameName: ImagesDark
+///     figmaLocaleFrameNames:
+///         de: ImagesDe
+///     figmaSources:
+///         - fileId: shPilWnVdJfo10YF12345
+///           frameName: Illustrations
+///     suggestionThreshold: 0.0..1.0
+///     suggestionLimit: 5
+///     manifest: true | false
+///     checkDiskSpace: true | false
+///     expandVariants: true | false
+///     ignore: [img_draft_*, img_annotation]
 /// ```
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct CommonImagesConfig {
-    pub figma_frame_name: String,
+    /// Frames with images. If there are images with the same name in several frames,
+    /// the image from the first of them is used.
+    #[serde(rename = "figmaFrameName", deserialize_with = "one_or_many")]
+    pub figma_frame_names: Vec<String>,
+    /// Frame with dark variants of the images, which have the same names as in `figma_frame_names`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub figma_dark_frame_name: Option<String>,
+    /// Frames with localized variants of the images by Android locale qualifiers, e.g. `de`.
+    /// Images in them have the same names as in `figma_frame_names`.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub figma_locale_frame_names: BTreeMap<String, String>,
+    /// Frames with images in other Figma files, whose images are exported along with the images
+    /// of `figma_frame_names`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub figma_sources: Vec<FigmaSourceConfig>,
+    /// Minimum similarity of the suggested name to the missing one.
+    #[serde(default = "default_suggestion_threshold")]
+    pub suggestion_threshold: f64,
+    /// Maximum number of suggestions, 0 to disable suggestions.
+    #[serde(default = "default_suggestion_limit")]
+    pub suggestion_limit: usize,
+    /// Write `fxn-manifest.json` with Figma names of the exported images next to the config.
+    #[serde(default)]
+    pub manifest: bool,
+    /// Check that there is enough free space for the exported images before downloading them.
+    #[serde(default)]
+    pub check_disk_space: bool,
+    /// Export every variant of the component sets in the frames as a separate image, named
+    /// by the component set and the values of the variant properties, e.g. `ic_button_pressed`.
+    #[serde(default)]
+    pub expand_variants: bool,
+    /// Names of the images, which are never exported, can contain `*` and `?` wildcards.
+    #[serde(default)]
+    pub ignore: Vec<String>,
+}
+
+/// Allow to specify a single string where the list of strings is expected.
+fn one_or_many<'de, D>(deserializer: D) -> Result<Vec<String>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum OneOrMany {
+        One(String),
+        Many(Vec<String>),
+    }
+    Ok(match OneOrMany::deserialize(deserializer)? {
+        OneOrMany::One(value) => vec![value],
+        OneOrMany::Many(values) => values,
+    })
+}
+
+/// Part of App config from YAML:
+/// ```yaml
+/// figmaSources:
+///     - fileId: shPilWnVdJfo10YF12345
+///       frameName: Illustrations
+/// ```
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FigmaSourceConfig {
+    pub file_id: String,
+    pub frame_name: String,
 }
 
 /// Part of App config from YAML:
@@ -103,7 +480,7 @@ pub struct CommonImagesConfig {
 /// images:
 ///     figmaFrameName: Images
 /// ```
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct CommonIconsConfig {
     pub figma_frame_name: String,
@@ -115,34 +492,121 @@ pub struct CommonIconsConfig {
 ///     mainRes: "./main/res"
 ///     images:
 ///         mainRes: "./main/res"
-///         scales:
+///         scales: # or auto
 ///             mdpi: 1.0
 ///             hdpi: 1.5
 ///             xhdpi: 2.0
 ///             xxhdpi: 3.0
-///         format: svg | png | webp
+///         scaleNaming: raw | density
+///         layout: drawableDirs | suffixedFlat
+///         sizing: density | intrinsic
+///         format: svg | png | webp | jpeg | avif
 ///         webpOptions:
+///             quality: 0..100 | {1: 90, 4: 75}
+///             lossless: true | false
+///             keepSmaller: true | false
+///             alpha: default | exact | bleed
+///         jpegOptions:
+///             quality: 1..100
+///         avifOptions:
 ///             quality: 0..100
+///         svgOptions:
+///             convertToVectorDrawable: true | false
+///             optimize: true | false
+///             precision: 3
+///             tint: "#1C1B1F"
+///             rasterFallback: [1, 2, 3]
+///         pngOptions:
+///             optimize: true | false
+///             level: 0..6
+///         stripMetadata: true | false
+///         maxDimension: 4096
+///         downscaleOversized: true | false
+///         trim: true | false
+///         concurrency: 4
+///         groupOutput: true
+///         scaleOverrides:
+///             ic_logo: [1.0, 2.0]
+///         formatOverrides:
+///             ic_logo: svg
+///         resDirType: drawable | mipmap
+///         resDirTypeOverrides:
+///             ic_launcher: mipmap
+///         compose:
+///             generate: true | false
+///             packageName: "com.example.ui"
+///             rPackageName: "com.example"
+///             objectName: AppImages
+///             outputPath: "./main/kotlin/com/example/ui/AppImages.kt"
+///         adaptiveIcon:
+///             name: ic_launcher
+///             foreground: ic_launcher_foreground
+///             background: ic_launcher_background
+///         dedup: true | false
+///         resNamePrefix: ds_
+///         resNameSuffix: ""
 ///     icons:
 ///         mainRes: "./main/res"
 ///         format: svg | xml
+///     colors:
+///         mainRes: "./main/res"
+///         fileName: colors.xml
+///         compose:
+///             generate: true | false
+///             packageName: "com.example.ui"
+///             objectName: AppColors
+///             outputPath: "./main/kotlin/com/example/ui/AppColors.kt"
 /// ```
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct AndroidConfig {
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub main_res: Option<String>,
     #[serde(default = "default_android_images_config")]
     pub images: AndroidImagesConfig,
     #[serde(default = "default_android_icons_config")]
     pub icons: AndroidIconsConfig,
+    #[serde(default = "default_android_colors_config")]
+    pub colors: AndroidColorsConfig,
+}
+
+fn default_android_config() -> AndroidConfig {
+    AndroidConfig {
+        main_res: None,
+        images: default_android_images_config(),
+        icons: default_android_icons_config(),
+        colors: default_android_colors_config(),
+    }
 }
 
 fn default_android_images_config() -> AndroidImagesConfig {
     AndroidImagesConfig {
         main_res: None,
         scales: default_scales(),
+        scale_naming: default_scale_naming(),
+        layout: default_images_layout(),
+        sizing: default_image_sizing(),
         format: default_image_format(),
         webp_options: default_webp_options(),
+        jpeg_options: default_jpeg_options(),
+        avif_options: default_avif_options(),
+        svg_options: default_svg_options(),
+        png_options: default_png_options(),
+        strip_metadata: false,
+        max_dimension: None,
+        downscale_oversized: false,
+        trim: false,
+        concurrency: default_concurrency(),
+        group_output: default_group_output(),
+        scale_overrides: HashMap::new(),
+        format_overrides: HashMap::new(),
+        res_dir_type: default_res_dir_type(),
+        res_dir_type_overrides: HashMap::new(),
+        compose: default_compose_options(),
+        adaptive_icon: None,
+        dedup: false,
+        res_name_prefix: String::new(),
+        res_name_suffix: String::new(),
     }
 }
 
@@ -153,29 +617,210 @@ fn default_android_icons_config() -> AndroidIconsConfig {
     }
 }
 
+fn default_android_colors_config() -> AndroidColorsConfig {
+    AndroidColorsConfig {
+        main_res: None,
+        file_name: default_colors_file_name(),
+        compose: default_colors_compose_options(),
+    }
+}
+
 /// Part of App config from YAML:
 /// ```yaml
 /// images:
 ///     mainRes: "./main/res"
-///     scales:
+///     scales: # or auto
 ///         mdpi: 1.0
 ///         hdpi: 1.5
 ///         xhdpi: 2.0
 ///         xxhdpi: 3.0
-///     format: svg | png | webp
+///     scaleNaming: raw | density
+///     layout: drawableDirs | suffixedFlat
+///     sizing: density | intrinsic
+///     format: svg | png | webp | jpeg | avif
 ///     webpOptions:
+///         quality: 0..100 | {1: 90, 4: 75}
+///         lossless: true | false
+///         keepSmaller: true | false
+///         alpha: default | exact | bleed
+///     jpegOptions:
+///         quality: 1..100
+///     avifOptions:
 ///         quality: 0..100
+///     svgOptions:
+///         convertToVectorDrawable: true | false
+///     pngOptions:
+///         optimize: true | false
+///         level: 0..6
+///     stripMetadata: true | false
+///     maxDimension: 4096
+///     downscaleOversized: true | false
+///     trim: true | false
+///     concurrency: 4
+///     groupOutput: true
+///     scaleOverrides:
+///         ic_logo: [1.0, 2.0]
+///     formatOverrides:
+///         ic_logo: svg
+///     resDirType: drawable | mipmap
+///     resDirTypeOverrides:
+///         ic_launcher: mipmap
+///     compose:
+///         generate: true | false
+///         packageName: "com.example.ui"
+///         rPackageName: "com.example"
+///         objectName: AppImages
+///         outputPath: "./main/kotlin/com/example/ui/AppImages.kt"
+///     adaptiveIcon:
+///         name: ic_launcher
+///         foreground: ic_launcher_foreground
+///         background: ic_launcher_background
+///     dedup: true | false
+///     resNamePrefix: ds_
+///     resNameSuffix: ""
 /// ```
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct AndroidImagesConfig {
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub main_res: Option<String>,
-    #[serde(default = "default_scales")]
+    /// `auto` is expanded to all densities from `mdpi` to `xxxhdpi`.
+    #[serde(
+        default = "default_scales",
+        deserialize_with = "scales_or_auto",
+        serialize_with = "sorted_map"
+    )]
     pub scales: HashMap<String, f32>,
+    /// How to name `drawable-*` directories for the `scales`.
+    #[serde(default = "default_scale_naming")]
+    pub scale_naming: ScaleNaming,
+    /// Where to put the images of every scale.
+    #[serde(default = "default_images_layout")]
+    pub layout: ImagesLayout,
+    /// How the size of the exported raster images is chosen.
+    #[serde(default = "default_image_sizing")]
+    pub sizing: ImageSizing,
     #[serde(default = "default_image_format")]
     pub format: ImageFormat,
     #[serde(default = "default_webp_options")]
     pub webp_options: AndroidImagesWebpConfig,
+    #[serde(default = "default_jpeg_options")]
+    pub jpeg_options: ImagesJpegConfig,
+    #[serde(default = "default_avif_options")]
+    pub avif_options: AndroidImagesAvifConfig,
+    #[serde(default = "default_svg_options")]
+    pub svg_options: AndroidImagesSvgConfig,
+    #[serde(default = "default_png_options")]
+    pub png_options: AndroidImagesPngConfig,
+    /// Remove metadata chunks, which don't affect the rendering, from png images.
+    #[serde(default)]
+    pub strip_metadata: bool,
+    /// Maximum width and height of raster images in pixels, e.g. to stay within
+    /// the texture size limits of Android.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_dimension: Option<u32>,
+    /// Downscale the raster images larger than `max_dimension` instead of failing their export.
+    #[serde(default)]
+    pub downscale_oversized: bool,
+    /// Crop the fully transparent borders of the raster images, e.g. margins of the frames.
+    #[serde(default)]
+    pub trim: bool,
+    #[serde(default = "default_concurrency")]
+    pub concurrency: usize,
+    /// Show the lines of every image together, when it is done, instead of as soon as they
+    /// are rendered, so the lines of the images exported in parallel don't interleave.
+    #[serde(default = "default_group_output")]
+    pub group_output: bool,
+    /// Values of `scales` to export the image with, instead of all `scales`.
+    #[serde(default, serialize_with = "sorted_map")]
+    pub scale_overrides: HashMap<String, Vec<f32>>,
+    /// Format to export the image with, instead of `format`.
+    #[serde(default, serialize_with = "sorted_map")]
+    pub format_overrides: HashMap<String, ImageFormat>,
+    /// Type of the res directories, e.g. `mipmap` for `mipmap-xhdpi`.
+    #[serde(default = "default_res_dir_type")]
+    pub res_dir_type: ResDirType,
+    /// Type of the res directories for the image, instead of `res_dir_type`.
+    #[serde(default, serialize_with = "sorted_map")]
+    pub res_dir_type_overrides: HashMap<String, ResDirType>,
+    #[serde(default = "default_compose_options")]
+    pub compose: AndroidImagesComposeConfig,
+    /// Adaptive launcher icon to generate from the exported images.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub adaptive_icon: Option<AndroidAdaptiveIconConfig>,
+    /// Replace the images identical to other exported images with aliases to them.
+    #[serde(default)]
+    pub dedup: bool,
+    /// Added to the resource name of every image, e.g. `ds_` for `ds_img_logo`.
+    #[serde(default)]
+    pub res_name_prefix: String,
+    #[serde(default)]
+    pub res_name_suffix: String,
+}
+
+/// Write the map sorted by keys, so the written config doesn't change from run to run.
+fn sorted_map<K, V, S>(map: &HashMap<K, V>, serializer: S) -> Result<S::Ok, S::Error>
+where
+    K: Serialize + Ord,
+    V: Serialize,
+    S: Serializer,
+{
+    map.iter()
+        .collect::<BTreeMap<&K, &V>>()
+        .serialize(serializer)
+}
+
+/// Value of the field, which can be computed by the app, if `auto` is specified.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum AutoOr<T> {
+    Auto(String),
+    Value(T),
+}
+
+impl<T> AutoOr<T> {
+    fn expand<'de, D: Deserializer<'de>>(self, auto: fn() -> T) -> Result<T, D::Error> {
+        match self {
+            AutoOr::Auto(value) if value == "auto" => Ok(auto()),
+            AutoOr::Auto(value) => Err(serde::de::Error::custom(format!(
+                "expected `auto` or list of scales, found `{}`",
+                value
+            ))),
+            AutoOr::Value(value) => Ok(value),
+        }
+    }
+}
+
+fn scales_or_auto<'de, D>(deserializer: D) -> Result<HashMap<String, f32>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    AutoOr::deserialize(deserializer)?.expand::<D>(auto_scales)
+}
+
+fn ios_scales_or_auto<'de, D>(deserializer: D) -> Result<Vec<f32>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    AutoOr::deserialize(deserializer)?.expand::<D>(default_ios_scales)
+}
+
+/// Scales of all android densities. Vector images ignore scales anyway.
+fn auto_scales() -> HashMap<String, f32> {
+    [
+        ("mdpi", 1.0f32),
+        ("hdpi", 1.5f32),
+        ("xhdpi", 2.0f32),
+        ("xxhdpi", 3.0f32),
+        ("xxxhdpi", 4.0f32),
+    ]
+    .into_iter()
+    .map(|(k, v)| (k.to_string(), v))
+    .collect()
+}
+
+fn default_scale_naming() -> ScaleNaming {
+    ScaleNaming::Raw
 }
 
 fn default_scales() -> HashMap<String, f32> {
@@ -195,15 +840,149 @@ fn default_image_format() -> ImageFormat {
 }
 
 fn default_webp_options() -> AndroidImagesWebpConfig {
-    AndroidImagesWebpConfig { quality: 85f32 }
+    AndroidImagesWebpConfig {
+        quality: None,
+        lossless: false,
+        keep_smaller: false,
+        alpha: WebpAlpha::Default,
+    }
+}
+
+fn default_jpeg_options() -> ImagesJpegConfig {
+    ImagesJpegConfig {
+        quality: default_jpeg_quality(),
+    }
+}
+
+fn default_jpeg_quality() -> u8 {
+    85
+}
+
+fn default_avif_options() -> AndroidImagesAvifConfig {
+    AndroidImagesAvifConfig {
+        quality: default_avif_quality(),
+    }
+}
+
+fn default_avif_quality() -> u8 {
+    80
+}
+
+fn default_svg_options() -> AndroidImagesSvgConfig {
+    AndroidImagesSvgConfig {
+        convert_to_vector_drawable: false,
+        optimize: false,
+        precision: default_svg_precision(),
+        tint: None,
+        raster_fallback: vec![],
+    }
+}
+
+fn default_svg_precision() -> u8 {
+    3
+}
+
+fn default_compose_options() -> AndroidImagesComposeConfig {
+    AndroidImagesComposeConfig {
+        generate: false,
+        package_name: None,
+        r_package_name: None,
+        object_name: default_compose_object_name(),
+        output_path: None,
+    }
+}
+
+fn default_compose_object_name() -> String {
+    "AppImages".to_string()
+}
+
+fn default_png_options() -> AndroidImagesPngConfig {
+    AndroidImagesPngConfig {
+        optimize: false,
+        level: default_png_level(),
+    }
+}
+
+fn default_res_dir_type() -> ResDirType {
+    ResDirType::Drawable
 }
 
-#[derive(Debug, Deserialize, Clone)]
+fn default_images_layout() -> ImagesLayout {
+    ImagesLayout::DrawableDirs
+}
+
+fn default_image_sizing() -> ImageSizing {
+    ImageSizing::Density
+}
+
+fn default_png_level() -> u8 {
+    2
+}
+
+fn default_concurrency() -> usize {
+    4
+}
+
+fn default_group_output() -> bool {
+    true
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum ScaleNaming {
+    /// Names of `scales` are used as is, e.g. `drawable-x2` for `x2: 2.0`.
+    Raw,
+    /// Density qualifiers are used for values of `scales`, e.g. `drawable-xhdpi` for `x2: 2.0`.
+    Density,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum ResDirType {
+    /// `drawable-*` directories for regular images.
+    Drawable,
+    /// `mipmap-*` directories for launcher icons.
+    Mipmap,
+}
+
+impl ResDirType {
+    /// Name of the res directory without qualifiers, e.g. `drawable`.
+    pub fn dir_name(&self) -> &'static str {
+        match self {
+            ResDirType::Drawable => "drawable",
+            ResDirType::Mipmap => "mipmap",
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum ImagesLayout {
+    /// Every scale in its own directory, e.g. `drawable-xhdpi/img_logo.png`.
+    DrawableDirs,
+    /// All scales in `mainRes` with the scale in the name, e.g. `img_logo@2x.png`.
+    SuffixedFlat,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+#[serde(rename_all = "camelCase")]
+pub enum ImageSizing {
+    /// Every image is exported with every scale from `scales`, e.g. to `drawable-xhdpi`.
+    Density,
+    /// Every image is exported once with its size in Figma, like svg, e.g. to `drawable`.
+    Intrinsic,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, clap::ValueEnum)]
 #[serde(rename_all = "camelCase")]
 pub enum ImageFormat {
     Webp,
     Png,
     Svg,
+    #[serde(alias = "jpg")]
+    #[clap(alias = "jpg")]
+    Jpeg,
+    Avif,
 }
 
 impl ImageFormat {
@@ -212,25 +991,266 @@ impl ImageFormat {
             ImageFormat::Png => "png".to_string(),
             ImageFormat::Svg => "svg".to_string(),
             ImageFormat::Webp => "webp".to_string(),
+            ImageFormat::Jpeg => "jpg".to_string(),
+            ImageFormat::Avif => "avif".to_string(),
         }
     }
 
     pub fn is_svg(&self) -> bool {
-        match &self {
-            ImageFormat::Svg => true,
-            _ => false,
-        }
+        matches!(self, ImageFormat::Svg)
     }
 }
 
 /// Part of App config from YAML:
 /// ```yaml
 /// webpOptions:
-///     quality: 0..100
+///     quality: 0..100 | {1: 90, 2: 85, 4: 75}
+///     lossless: true | false
+///     keepSmaller: true | false
+///     alpha: default | exact | bleed
 /// ```
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
 pub struct AndroidImagesWebpConfig {
-    pub quality: f32,
+    /// Ignored for lossless encoding.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub quality: Option<WebpQuality>,
+    #[serde(default)]
+    pub lossless: bool,
+    /// Keep the downloaded png, if the webp image turns out to be larger.
+    #[serde(default, alias = "keep_smaller")]
+    pub keep_smaller: bool,
+    #[serde(default = "default_webp_alpha")]
+    pub alpha: WebpAlpha,
+}
+
+/// How the colors of the transparent pixels are encoded to WEBP. Figma exports
+/// semi-transparent edges with straight alpha over transparent black pixels, which are
+/// mixed into the edges by lossy encoding, so the edges may get dark fringes.
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum WebpAlpha {
+    /// The encoder may change the colors of fully transparent pixels to compress them better.
+    Default,
+    /// Colors of fully transparent pixels are kept exactly as in the png from Figma.
+    Exact,
+    /// Fully transparent pixels get the colors of the closest visible pixels before encoding,
+    /// so nothing dark is mixed into the semi-transparent edges.
+    Bleed,
+}
+
+fn default_webp_alpha() -> WebpAlpha {
+    WebpAlpha::Default
+}
+
+impl AndroidImagesWebpConfig {
+    /// Returns the encoding quality for the value of the scale from config or the default one.
+    pub fn quality(&self, scale: f32) -> f32 {
+        match &self.quality {
+            Some(quality) => quality.for_scale(scale),
+            None => 85f32,
+        }
+    }
+}
+
+/// WEBP quality from config: one for all scales, or by the values of scales, e.g. `{1: 90, 4: 75}`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum WebpQuality {
+    Single(f32),
+    /// Values of scales and their qualities, sorted by the scales.
+    PerScale(Vec<(f32, f32)>),
+}
+
+impl WebpQuality {
+    /// Quality for the value of the scale. Scales missing in the map use the quality
+    /// of the closest scale, the lower one if two scales are equally close.
+    pub fn for_scale(&self, scale: f32) -> f32 {
+        match self {
+            WebpQuality::Single(quality) => *quality,
+            WebpQuality::PerScale(qualities) => qualities
+                .iter()
+                .min_by(|(a, _), (b, _)| (a - scale).abs().total_cmp(&(b - scale).abs()))
+                .map(|(_, quality)| *quality)
+                .unwrap_or(85f32),
+        }
+    }
+
+    /// Values of scales and their qualities, a single quality has no scale.
+    pub fn entries(&self) -> Vec<(Option<f32>, f32)> {
+        match self {
+            WebpQuality::Single(quality) => vec![(None, *quality)],
+            WebpQuality::PerScale(qualities) => qualities
+                .iter()
+                .map(|(scale, quality)| (Some(*scale), *quality))
+                .collect(),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for WebpQuality {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        use serde::de::Error;
+        // Keys of YAML maps may be numbers or strings, e.g. `1.5: 80` or `"1.5": 80`
+        let number = |value: &Value| {
+            value
+                .as_f64()
+                .or_else(|| value.as_str().and_then(|s| s.trim().parse().ok()))
+                .map(|n| n as f32)
+        };
+        let expected = "a number or a map of scales to numbers, e.g. {1: 90, 4: 75}";
+        match Value::deserialize(deserializer)? {
+            Value::Mapping(mapping) => {
+                let mut qualities = mapping
+                    .iter()
+                    .map(|(scale, quality)| match (number(scale), number(quality)) {
+                        (Some(scale), Some(quality)) => Ok((scale, quality)),
+                        _ => Err(D::Error::custom(format!("expected {}", expected))),
+                    })
+                    .collect::<Result<Vec<(f32, f32)>, D::Error>>()?;
+                qualities.sort_by(|(a, _), (b, _)| a.total_cmp(b));
+                Ok(WebpQuality::PerScale(qualities))
+            }
+            value => number(&value)
+                .map(WebpQuality::Single)
+                .ok_or_else(|| D::Error::custom(format!("expected {}", expected))),
+        }
+    }
+}
+
+impl Serialize for WebpQuality {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        use serde::ser::SerializeMap;
+        match self {
+            WebpQuality::Single(quality) => serializer.serialize_f32(*quality),
+            WebpQuality::PerScale(qualities) => {
+                let mut map = serializer.serialize_map(Some(qualities.len()))?;
+                for (scale, quality) in qualities {
+                    map.serialize_entry(scale, quality)?;
+                }
+                map.end()
+            }
+        }
+    }
+}
+
+/// Part of App config from YAML:
+/// ```yaml
+/// jpegOptions:
+///     quality: 1..100
+/// ```
+#[derive(Debug, Deserialize, Serialize)]
+pub struct ImagesJpegConfig {
+    #[serde(default = "default_jpeg_quality")]
+    pub quality: u8,
+}
+
+/// Part of App config from YAML:
+/// ```yaml
+/// avifOptions:
+///     quality: 0..100
+/// ```
+#[derive(Debug, Deserialize, Serialize)]
+pub struct AndroidImagesAvifConfig {
+    #[serde(default = "default_avif_quality")]
+    pub quality: u8,
+}
+
+/// Part of App config from YAML:
+/// ```yaml
+/// svgOptions:
+///     convertToVectorDrawable: true | false
+///     optimize: true | false
+///     precision: 3
+///     tint: "#1C1B1F"
+///     rasterFallback: [1, 2, 3]
+/// ```
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AndroidImagesSvgConfig {
+    #[serde(default)]
+    pub convert_to_vector_drawable: bool,
+    /// Minify SVG images, which are not converted to vector drawables.
+    #[serde(default)]
+    pub optimize: bool,
+    /// Digits after the decimal point to keep in coordinates of optimized SVG images.
+    #[serde(default = "default_svg_precision")]
+    pub precision: u8,
+    /// Hex color to replace all fill and stroke colors of single-color SVG images with.
+    #[serde(default)]
+    pub tint: Option<String>,
+    /// Scales of png images to export next to every SVG image, for older Android versions.
+    #[serde(default)]
+    pub raster_fallback: Vec<f32>,
+}
+
+/// Part of App config from YAML:
+/// ```yaml
+/// pngOptions:
+///     optimize: true | false
+///     level: 0..6
+/// ```
+#[derive(Debug, Deserialize, Serialize)]
+pub struct AndroidImagesPngConfig {
+    #[serde(default)]
+    pub optimize: bool,
+    #[serde(default = "default_png_level")]
+    pub level: u8,
+}
+
+/// Part of App config from YAML:
+/// ```yaml
+/// compose:
+///     generate: true | false
+///     packageName: "com.example.ui"
+///     rPackageName: "com.example"
+///     objectName: AppImages
+///     outputPath: "./main/kotlin/com/example/ui/AppImages.kt"
+/// ```
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AndroidImagesComposeConfig {
+    #[serde(default)]
+    pub generate: bool,
+    /// Required if `generate` is true.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub package_name: Option<String>,
+    /// Package of the `R` class, if it differs from `package_name`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub r_package_name: Option<String>,
+    #[serde(default = "default_compose_object_name")]
+    pub object_name: String,
+    /// Required if `generate` is true.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub output_path: Option<String>,
+}
+
+/// Part of App config from YAML:
+/// ```yaml
+/// adaptiveIcon:
+///     name: ic_launcher
+///     foreground: ic_launcher_foreground
+///     background: ic_launcher_background
+/// ```
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AndroidAdaptiveIconConfig {
+    /// Resource name of the generated `mipmap-anydpi-v26/{name}.xml`.
+    #[serde(default = "default_adaptive_icon_name")]
+    pub name: String,
+    /// Name of the image in Figma with the foreground layer.
+    pub foreground: String,
+    /// Name of the image in Figma with the background layer.
+    pub background: String,
+}
+
+fn default_adaptive_icon_name() -> String {
+    "ic_launcher".to_string()
 }
 
 /// Part of App config from YAML:
@@ -239,9 +1259,10 @@ pub struct AndroidImagesWebpConfig {
 ///     mainRes: "./main/res"
 ///     format: svg | xml
 /// ```
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct AndroidIconsConfig {
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub main_res: Option<String>,
     #[serde(default = "default_icons_format")]
     pub format: IconFormat,
@@ -251,7 +1272,70 @@ fn default_icons_format() -> IconFormat {
     IconFormat::Xml
 }
 
-#[derive(Debug, Deserialize, Clone)]
+/// Part of App config from YAML:
+/// ```yaml
+/// colors:
+///     mainRes: "./main/res"
+///     fileName: colors.xml
+///     compose:
+///         generate: true | false
+///         packageName: "com.example.ui"
+///         objectName: AppColors
+///         outputPath: "./main/kotlin/com/example/ui/AppColors.kt"
+/// ```
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AndroidColorsConfig {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub main_res: Option<String>,
+    /// Name of the file in `values` directory.
+    #[serde(default = "default_colors_file_name")]
+    pub file_name: String,
+    #[serde(default = "default_colors_compose_options")]
+    pub compose: AndroidColorsComposeConfig,
+}
+
+fn default_colors_file_name() -> String {
+    "colors.xml".to_string()
+}
+
+fn default_colors_compose_options() -> AndroidColorsComposeConfig {
+    AndroidColorsComposeConfig {
+        generate: false,
+        package_name: None,
+        object_name: default_colors_compose_object_name(),
+        output_path: None,
+    }
+}
+
+fn default_colors_compose_object_name() -> String {
+    "AppColors".to_string()
+}
+
+/// Part of App config from YAML:
+/// ```yaml
+/// compose:
+///     generate: true | false
+///     packageName: "com.example.ui"
+///     objectName: AppColors
+///     outputPath: "./main/kotlin/com/example/ui/AppColors.kt"
+/// ```
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AndroidColorsComposeConfig {
+    #[serde(default)]
+    pub generate: bool,
+    /// Required if `generate` is true.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub package_name: Option<String>,
+    #[serde(default = "default_colors_compose_object_name")]
+    pub object_name: String,
+    /// Required if `generate` is true.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub output_path: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
 #[serde(rename_all = "camelCase")]
 pub enum IconFormat {
     Svg,
@@ -267,15 +1351,200 @@ impl IconFormat {
     }
 }
 
+/// Part of App config from YAML:
+/// ```yaml
+/// ios:
+///     xcassets: "./App/Assets.xcassets"
+///     images:
+///         scales: auto | [1, 2, 3]
+///         format: svg | png | jpeg
+///         jpegOptions:
+///             quality: 1..100
+/// ```
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IosConfig {
+    pub xcassets: String,
+    #[serde(default = "default_ios_images_config")]
+    pub images: IosImagesConfig,
+}
+
+fn default_ios_images_config() -> IosImagesConfig {
+    IosImagesConfig {
+        scales: default_ios_scales(),
+        format: default_ios_image_format(),
+        jpeg_options: default_jpeg_options(),
+    }
+}
+
+/// Part of App config from YAML:
+/// ```yaml
+/// images:
+///     scales: auto | [1, 2, 3]
+///     format: svg | png | jpeg
+///     jpegOptions:
+///         quality: 1..100
+/// ```
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IosImagesConfig {
+    /// `auto` is expanded to all scales supported by asset catalogs.
+    #[serde(
+        default = "default_ios_scales",
+        deserialize_with = "ios_scales_or_auto"
+    )]
+    pub scales: Vec<f32>,
+    #[serde(default = "default_ios_image_format")]
+    pub format: ImageFormat,
+    #[serde(default = "default_jpeg_options")]
+    pub jpeg_options: ImagesJpegConfig,
+}
+
+fn default_ios_scales() -> Vec<f32> {
+    vec![1.0f32, 2.0f32, 3.0f32]
+}
+
+fn default_ios_image_format() -> ImageFormat {
+    ImageFormat::Png
+}
+
+/// Part of App config from YAML:
+/// ```yaml
+/// web:
+///     assetsDir: "./src/assets/images"
+///     images:
+///         scales: [1, 2, 3]
+///         format: svg | png | webp | jpeg
+///         webpOptions:
+///             quality: 0..100
+///         jpegOptions:
+///             quality: 1..100
+/// ```
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WebConfig {
+    /// Directory to put all images in, e.g. `img_logo@2x.png`.
+    pub assets_dir: String,
+    #[serde(default = "default_web_images_config")]
+    pub images: WebImagesConfig,
+}
+
+fn default_web_images_config() -> WebImagesConfig {
+    WebImagesConfig {
+        scales: default_web_scales(),
+        format: default_web_image_format(),
+        webp_options: default_webp_options(),
+        jpeg_options: default_jpeg_options(),
+    }
+}
+
+/// Part of App config from YAML:
+/// ```yaml
+/// images:
+///     scales: [1, 2, 3]
+///     format: svg | png | webp | jpeg
+///     webpOptions:
+///         quality: 0..100
+///     jpegOptions:
+///         quality: 1..100
+/// ```
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WebImagesConfig {
+    #[serde(default = "default_web_scales")]
+    pub scales: Vec<f32>,
+    #[serde(default = "default_web_image_format")]
+    pub format: ImageFormat,
+    #[serde(default = "default_webp_options")]
+    pub webp_options: AndroidImagesWebpConfig,
+    #[serde(default = "default_jpeg_options")]
+    pub jpeg_options: ImagesJpegConfig,
+}
+
+fn default_web_scales() -> Vec<f32> {
+    vec![1.0f32, 2.0f32, 3.0f32]
+}
+
+fn default_web_image_format() -> ImageFormat {
+    ImageFormat::Png
+}
+
 impl AppConfig {
+    /// Config with the default values and placeholders for the required fields,
+    /// to start a new project with.
+    pub fn template() -> Self {
+        AppConfig {
+            figma: FigmaConfig {
+                file_id: "YOUR_FIGMA_FILE_ID".to_string(),
+                page_name: None,
+                retry: default_retry_config(),
+                requests_per_minute: None,
+                base_url: None,
+                auth_type: default_auth_type(),
+            },
+            http: default_http_config(),
+            hooks: default_hooks_config(),
+            common: default_common_config(),
+            android: AndroidConfig {
+                main_res: Some("./app/src/main/res".to_string()),
+                ..default_android_config()
+            },
+            ios: None,
+            web: None,
+        }
+    }
+
     pub fn from_file(yaml_config_path: &String) -> Result<Self, AppError> {
-        let file = match File::open(yaml_config_path) {
-            Ok(file) => file,
-            Err(e) => return Err(AppError::AppConfigOpen(format!("{}", e))),
+        Self::from_file_with_profile(yaml_config_path, &None)
+    }
+
+    /// Read the config and overlay the `profile` from its `profiles` section on it, if the
+    /// profile is specified. Maps are merged field by field, other values of the profile,
+    /// including lists, replace the values of the base config. The config is read from stdin
+    /// if the path is [CONFIG_FROM_STDIN].
+    pub fn from_file_with_profile(
+        yaml_config_path: &String,
+        profile: &Option<String>,
+    ) -> Result<Self, AppError> {
+        let value: Value = if yaml_config_path == CONFIG_FROM_STDIN {
+            serde_yaml::from_reader(io::stdin().lock()).map_err(AppError::AppConfigParse)?
+        } else {
+            let file = match File::open(yaml_config_path) {
+                Ok(file) => file,
+                Err(e) => return Err(AppError::AppConfigOpen(format!("{}", e))),
+            };
+            serde_yaml::from_reader(&file).map_err(AppError::AppConfigParse)?
         };
-        match serde_yaml::from_reader(&file) {
-            Ok(app_config) => Ok(app_config),
-            Err(e) => Err(AppError::AppConfigParse(e)),
+        let value = with_profile(value, yaml_config_path, profile)?;
+        let mut app_config: Self =
+            serde_yaml::from_value(value).map_err(AppError::AppConfigParse)?;
+        app_config.resolve_paths(yaml_config_path);
+        Ok(app_config)
+    }
+
+    /// Resolve the output paths against the directory of the config and expand `~` in them.
+    fn resolve_paths(&mut self, yaml_config_path: &String) {
+        let resolve = |path: &mut String| *path = resolve_config_path(path, yaml_config_path);
+        let android = &mut self.android;
+        [
+            &mut android.main_res,
+            &mut android.images.main_res,
+            &mut android.images.compose.output_path,
+            &mut android.icons.main_res,
+            &mut android.colors.main_res,
+            &mut android.colors.compose.output_path,
+        ]
+        .into_iter()
+        .flatten()
+        .for_each(resolve);
+        if let Some(ios) = self.ios.as_mut() {
+            resolve(&mut ios.xcassets);
+        }
+        if let Some(web) = self.web.as_mut() {
+            resolve(&mut web.assets_dir);
+        }
+        if let Some(temp_dir) = self.common.temp_dir.as_mut() {
+            resolve(temp_dir);
         }
     }
 
@@ -294,4 +1563,158 @@ impl AppConfig {
 
         icons_main_res.or(common_main_res)
     }
+
+    /// Returns the required mainRes path from config.
+    pub fn main_res_colors(&self) -> Option<String> {
+        let common_main_res = self.android.main_res.clone();
+        let colors_main_res = self.android.colors.main_res.clone();
+
+        colors_main_res.or(common_main_res)
+    }
+}
+
+/// Remove `profiles` section from the config and overlay the `profile` from it on the config.
+fn with_profile(
+    mut value: Value,
+    yaml_config_path: &String,
+    profile: &Option<String>,
+) -> Result<Value, AppError> {
+    let profiles = match &mut value {
+        Value::Mapping(mapping) => mapping.remove(&Value::from("profiles")),
+        _ => None,
+    };
+    let profile = match profile {
+        Some(profile) => profile,
+        None => return Ok(value),
+    };
+    let profiles = profiles.and_then(|profiles| match profiles {
+        Value::Mapping(mapping) => Some(mapping),
+        _ => None,
+    });
+    let overlay = profiles
+        .as_ref()
+        .and_then(|profiles| profiles.get(&Value::from(profile.as_str())));
+    match overlay {
+        Some(overlay) => {
+            merge_yaml(&mut value, overlay.clone());
+            Ok(value)
+        }
+        None => {
+            let mut names = profiles
+                .iter()
+                .flat_map(|profiles| profiles.iter())
+                .filter_map(|(name, _)| name.as_str().map(|name| format!("`{}`", name)))
+                .collect::<Vec<String>>();
+            names.sort();
+            Err(AppError::AppConfigUnknownProfile(
+                yaml_config_path.clone(),
+                profile.clone(),
+                if names.is_empty() {
+                    "none".to_string()
+                } else {
+                    names.join(", ")
+                },
+            ))
+        }
+    }
+}
+
+fn merge_yaml(base: &mut Value, overlay: Value) {
+    match (base, overlay) {
+        (Value::Mapping(base), Value::Mapping(overlay)) => {
+            for (key, value) in overlay {
+                match base.get_mut(&key) {
+                    Some(base_value) => merge_yaml(base_value, value),
+                    None => {
+                        base.insert(key, value);
+                    }
+                }
+            }
+        }
+        (base, overlay) => *base = overlay,
+    }
+}
+
+#[test]
+fn test_profiles() {
+    let value: Value = serde_yaml::from_str(
+        r#"
+figma:
+  fileId: abc
+common:
+  images:
+    figmaFrameName: Images
+android:
+  mainRes: ./app/src/main/res
+profiles:
+  release:
+    common:
+      images:
+        figmaFrameName: [Images, Release]
+    android:
+      images:
+        format: png
+"#,
+    )
+    .unwrap();
+    let path = "fxn.yaml".to_string();
+    let release = with_profile(value.clone(), &path, &Some("release".to_string())).unwrap();
+    let app_config: AppConfig = serde_yaml::from_value(release).unwrap();
+    assert_eq!(app_config.figma.file_id, "abc");
+    assert_eq!(app_config.common.images.figma_frame_names.len(), 2);
+    assert!(matches!(app_config.android.images.format, ImageFormat::Png));
+    assert_eq!(app_config.main_res_images().unwrap(), "./app/src/main/res");
+
+    let base: AppConfig =
+        serde_yaml::from_value(with_profile(value.clone(), &path, &None).unwrap()).unwrap();
+    assert!(matches!(base.android.images.format, ImageFormat::Webp));
+    let result = with_profile(value, &path, &Some("debug".to_string()));
+    assert_eq!(
+        result.unwrap_err().to_string(),
+        "Unknown profile `debug` in fxn.yaml. Available profiles: `release`"
+    );
+}
+
+#[test]
+fn test_auto_scales() {
+    let app_config: AppConfig = serde_yaml::from_str(
+        r#"
+figma:
+  fileId: abc
+android:
+  images:
+    scales: auto
+ios:
+  xcassets: ./Assets.xcassets
+  images:
+    scales: auto
+"#,
+    )
+    .unwrap();
+    assert_eq!(app_config.android.images.scales, auto_scales());
+    assert_eq!(
+        app_config.ios.unwrap().images.scales,
+        vec![1f32, 2f32, 3f32]
+    );
+
+    let result = serde_yaml::from_str::<AndroidImagesConfig>("scales: all");
+    assert!(result.is_err());
+}
+
+#[test]
+fn test_webp_quality_per_scale() {
+    let webp_options: AndroidImagesWebpConfig =
+        serde_yaml::from_str("quality: {1: 90, 2: 85, 4: 75}").unwrap();
+    assert_eq!(webp_options.quality(1f32), 90f32);
+    assert_eq!(webp_options.quality(1.5f32), 90f32);
+    assert_eq!(webp_options.quality(3f32), 85f32);
+    assert_eq!(webp_options.quality(4f32), 75f32);
+
+    let webp_options: AndroidImagesWebpConfig = serde_yaml::from_str("quality: 70").unwrap();
+    assert_eq!(webp_options.quality(4f32), 70f32);
+    let webp_options: AndroidImagesWebpConfig = serde_yaml::from_str("lossless: true").unwrap();
+    assert_eq!(webp_options.quality(2f32), 85f32);
+
+    let result = serde_yaml::from_str::<AndroidImagesWebpConfig>("quality: {x1: 90}");
+    assert!(result.is_err());
 }
@@ -1,10 +1,12 @@
 use clap::{Parser, Subcommand};
 
+use crate::models::config::{ImageFormat, ImageSizing, Platform};
+
 /// Simple util to export resources from figma to android project
 #[derive(Parser, Debug)]
 #[clap(author, version, about, long_about=None)]
 pub struct Args {
-    /// Subcommand: images, icons
+    /// Subcommand: images, icons, colors, list
     #[clap(subcommand)]
     pub subcommand: Command,
 }
@@ -13,32 +15,204 @@ pub struct Args {
 pub enum Command {
     /// Export images/illustrations from Figma
     Images {
-        /// Figma personal access token, can be omitted if there is a env variable
-        #[clap(short, long, env = "FIGMA_PERSONAL_TOKEN")]
-        token: String,
-        /// Path to yaml config. Use `fxn config` to generate default config here
+        /// Figma personal access token, can be omitted if there is `FXN_FIGMA_TOKEN` env variable
+        #[clap(short, long)]
+        token: Option<String>,
+        /// Path to the file with Figma personal access token
+        #[clap(long)]
+        token_file: Option<String>,
+        /// Path to yaml config, `-` to read it from stdin. Use `fxn config` to generate default config here
         #[clap(short = 'c', long = "config")]
         path_to_config: String,
+        /// Name of the profile from `profiles` section of the config to overlay on the config
+        #[clap(long)]
+        profile: Option<String>,
+        /// Target platform of the exported images
+        #[clap(short, long, value_enum, default_value = "android")]
+        platform: Platform,
+        /// Export all images from the frame. Also used if no images names are specified
+        #[clap(short, long)]
+        all: bool,
+        /// Show which images would be exported and where, without downloading or writing files
+        #[clap(long)]
+        dry_run: bool,
+        /// Export images into temporary files and show which files in the res directory would be
+        /// added, changed or left unchanged, without writing them
+        #[clap(long, conflicts_with = "dry-run")]
+        diff: bool,
+        /// Pick one of the similar names in the terminal for every missing image, instead of
+        /// reporting it as missing. Ignored if stdin isn't a terminal
+        #[clap(short, long)]
+        interactive: bool,
+        /// Export images even if they have not changed since the last export
+        #[clap(short, long)]
+        force: bool,
+        /// Compare Figma nodes of the images with the exported ones, to skip unchanged images
+        /// even if other parts of Figma file have changed
+        #[clap(long)]
+        only_changed: bool,
+        /// Write JSON summary of the export to the file, use `-` to print it to stdout
+        #[clap(long)]
+        report: Option<String>,
+        /// Export images to this directory instead of the res directory (or asset catalog) from config
+        #[clap(short, long = "out")]
+        output_dir: Option<String>,
+        /// Export images in this format instead of the format from config, e.g. to compare formats
+        #[clap(long, value_enum)]
+        format: Option<ImageFormat>,
+        /// Export images only with these comma separated scales instead of the scales from config, e.g. `1,1.5,4`
+        #[clap(long, value_delimiter = ',')]
+        scales: Option<Vec<f32>>,
+        /// Export Android images once with their size in Figma into `drawable` directory (`intrinsic`),
+        /// or with every scale (`density`), instead of the sizing from config
+        #[clap(long, value_enum)]
+        sizing: Option<ImageSizing>,
+        /// Export the only image from the Figma node with this link or id, instead of looking for it in the frames
+        #[clap(long, value_name = "URL_OR_ID")]
+        node: Option<String>,
+        /// Don't check the token before the export, e.g. to work offline with the cached Figma file
+        #[clap(long)]
+        skip_token_check: bool,
+        /// Stop the export on the first image which has failed, instead of exporting the rest
+        #[clap(long)]
+        fail_fast: bool,
+        /// Exit with the same code as for failed images, if there are warnings
+        #[clap(long)]
+        warnings_as_errors: bool,
+        /// Find the images in the result of the previous run instead of loading Figma file,
+        /// e.g. to check config changes with `--dry-run` without network
+        #[clap(long)]
+        offline: bool,
+        /// Show every HTTP request with its status and timing
+        #[clap(short, long)]
+        verbose: bool,
+        /// Show only errors, warnings and the final summary, e.g. for CI logs
+        #[clap(short, long, conflicts_with = "verbose")]
+        quiet: bool,
+        /// Keep running and export images again every time the config file changes
+        #[clap(short, long)]
+        watch: bool,
+        /// Path to the file with images names, one per line, added to the names from the command line.
+        /// Blank lines and lines starting with `#` are ignored
+        #[clap(long, value_name = "PATH")]
+        names_file: Option<String>,
         /// Space separated images names
         names: Vec<String>,
     },
     /// Export vector icons from Figma
     Icons {
-        /// Figma personal access token, can be omitted if there is a env variable
-        #[clap(short, long, env = "FIGMA_PERSONAL_TOKEN")]
-        token: String,
-        /// Path to yaml config. Use `fxn config` to generate default config here
+        /// Figma personal access token, can be omitted if there is `FXN_FIGMA_TOKEN` env variable
+        #[clap(short, long)]
+        token: Option<String>,
+        /// Path to the file with Figma personal access token
+        #[clap(long)]
+        token_file: Option<String>,
+        /// Path to yaml config, `-` to read it from stdin. Use `fxn config` to generate default config here
         #[clap(short = 'c', long = "config")]
         path_to_config: String,
+        /// Name of the profile from `profiles` section of the config to overlay on the config
+        #[clap(long)]
+        profile: Option<String>,
+        /// Show every HTTP request with its status and timing
+        #[clap(short, long)]
+        verbose: bool,
+        /// Show only errors, warnings and the final summary, e.g. for CI logs
+        #[clap(short, long, conflicts_with = "verbose")]
+        quiet: bool,
         /// Space separated images names
         names: Vec<String>,
     },
+    /// Export color styles from Figma to colors.xml
+    Colors {
+        /// Figma personal access token, can be omitted if there is `FXN_FIGMA_TOKEN` env variable
+        #[clap(short, long)]
+        token: Option<String>,
+        /// Path to the file with Figma personal access token
+        #[clap(long)]
+        token_file: Option<String>,
+        /// Path to yaml config, `-` to read it from stdin. Use `fxn config` to generate default config here
+        #[clap(short = 'c', long = "config")]
+        path_to_config: String,
+        /// Name of the profile from `profiles` section of the config to overlay on the config
+        #[clap(long)]
+        profile: Option<String>,
+        /// Show every HTTP request with its status and timing
+        #[clap(short, long)]
+        verbose: bool,
+        /// Show only errors, warnings and the final summary, e.g. for CI logs
+        #[clap(short, long, conflicts_with = "verbose")]
+        quiet: bool,
+    },
+    /// List names of all images (or icons) in the frames from config, without exporting them
+    List {
+        /// Figma personal access token, can be omitted if there is `FXN_FIGMA_TOKEN` env variable
+        #[clap(short, long)]
+        token: Option<String>,
+        /// Path to the file with Figma personal access token
+        #[clap(long)]
+        token_file: Option<String>,
+        /// Path to yaml config, `-` to read it from stdin. Use `fxn config` to generate default config here
+        #[clap(short = 'c', long = "config")]
+        path_to_config: String,
+        /// Name of the profile from `profiles` section of the config to overlay on the config
+        #[clap(long)]
+        profile: Option<String>,
+        /// Target platform, the config is checked for it
+        #[clap(short, long, value_enum, default_value = "android")]
+        platform: Platform,
+        /// List icons from the icons frame instead of images
+        #[clap(long)]
+        icons: bool,
+        /// Show ids of Figma nodes
+        #[clap(long)]
+        ids: bool,
+        /// Show resource names the images would be exported with
+        #[clap(long)]
+        res_names: bool,
+        /// List the images from the result of the previous run instead of loading Figma file
+        #[clap(long)]
+        offline: bool,
+        /// Show every HTTP request with its status and timing
+        #[clap(short, long)]
+        verbose: bool,
+    },
+    /// Check the token, config, Figma file, frames and output directory without exporting anything
+    #[clap(alias = "doctor")]
+    Check {
+        /// Figma personal access token, can be omitted if there is `FXN_FIGMA_TOKEN` env variable
+        #[clap(short, long)]
+        token: Option<String>,
+        /// Path to the file with Figma personal access token
+        #[clap(long)]
+        token_file: Option<String>,
+        /// Path to yaml config, `-` to read it from stdin. Use `fxn config` to generate default config here
+        #[clap(short = 'c', long = "config")]
+        path_to_config: String,
+        /// Name of the profile from `profiles` section of the config to overlay on the config
+        #[clap(long)]
+        profile: Option<String>,
+        /// Target platform, the config and the output directory are checked for it
+        #[clap(short, long, value_enum, default_value = "android")]
+        platform: Platform,
+        /// Show every HTTP request with its status and timing
+        #[clap(short, long)]
+        verbose: bool,
+    },
     /// Generate default yaml config here
     Config {
         /// New config filename
         #[clap(default_value_t = String::from("fxn_default_config.yaml"))]
         new_config_filename: String,
     },
+    /// Create a starter yaml config with the default values
+    Init {
+        /// New config filename
+        #[clap(default_value_t = String::from("fxn.yaml"))]
+        new_config_filename: String,
+        /// Overwrite the file if it already exists
+        #[clap(short, long)]
+        force: bool,
+    },
     /// Clear temporary `.fxa` dir
     Cleanup,
 }
@@ -18,5 +18,87 @@ pub struct Canvas {
 pub struct Frame {
     pub id: String,
     pub name: String,
+    /// Type of the node, e.g. `FRAME`, `COMPONENT` or `COMPONENT_SET`.
+    #[serde(rename = "type", default)]
+    pub node_type: String,
     pub children: Option<Vec<Frame>>,
 }
+
+/// Type of the node with the variants of the component.
+pub const COMPONENT_SET_TYPE: &str = "COMPONENT_SET";
+
+impl Frame {
+    /// Name of the image for the variant of the component set, made of the name of
+    /// the component set and the values of the variant properties,
+    /// e.g. `ic_button_pressed` for `state=pressed` variant of `ic_button`.
+    pub fn variant_name(&self, component_set_name: &str) -> String {
+        let values = self
+            .name
+            .split(',')
+            .map(|property| match property.split_once('=') {
+                Some((_, value)) => value.trim(),
+                None => property.trim(),
+            })
+            .filter(|value| !value.is_empty())
+            .collect::<Vec<&str>>();
+        match values.is_empty() {
+            true => component_set_name.to_string(),
+            false => format!("{}_{}", component_set_name, values.join("_")),
+        }
+    }
+}
+
+/// Style of the Figma file, e.g. color style.
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Style {
+    pub name: String,
+    /// Kind of the style: `FILL`, `TEXT`, `EFFECT` or `GRID`. Color styles are `FILL`.
+    pub style_type: String,
+}
+
+/// Node of the style from `nodes` endpoint, only the fills are needed to get the color.
+#[derive(Debug, Deserialize)]
+pub struct StyleNode {
+    #[serde(default)]
+    pub fills: Vec<Paint>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Paint {
+    #[serde(rename = "type")]
+    pub paint_type: String,
+    #[serde(default = "default_visible")]
+    pub visible: bool,
+    pub opacity: Option<f32>,
+    pub color: Option<Color>,
+}
+
+fn default_visible() -> bool {
+    true
+}
+
+/// RGBA color with components from 0.0 to 1.0.
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq)]
+pub struct Color {
+    pub r: f32,
+    pub g: f32,
+    pub b: f32,
+    pub a: f32,
+}
+
+impl StyleNode {
+    /// Returns the color of the first visible solid fill, with the opacity of the fill applied.
+    /// Gradients and images can't be exported as colors.
+    pub fn solid_color(&self) -> Option<Color> {
+        self.fills
+            .iter()
+            .filter(|paint| paint.visible && paint.paint_type == "SOLID")
+            .find_map(|paint| {
+                paint.color.map(|color| Color {
+                    a: color.a * paint.opacity.unwrap_or(1f32),
+                    ..color
+                })
+            })
+    }
+}